@@ -0,0 +1,277 @@
+use std::{
+    collections::HashMap,
+    path::PathBuf,
+    process::ExitCode,
+};
+
+use window_registry::{
+    IpcClient,
+    IpcRequest,
+    IpcResponse,
+    RegistryEvent,
+    WindowChanges,
+    WindowId,
+    WindowInfo,
+    default_socket_path,
+};
+
+const USAGE: &str = "\
+usage: wreg [--socket PATH] [--json] <command>
+
+commands:
+  list [QUERY]  list every window, or those matching QUERY
+  get <id>      show one window (id is index:gen)
+  tree          show the parent/child hierarchy
+  focus <id>    focus a window
+  watch         print registry events as they happen";
+
+enum Command {
+    List(Option<String>),
+    Get(WindowId),
+    Tree,
+    Focus(WindowId),
+    Watch,
+}
+
+struct Options {
+    socket: PathBuf,
+    json: bool,
+    command: Command,
+}
+
+fn main() -> ExitCode {
+    if std::env::args().any(|arg| arg == "-h" || arg == "--help") {
+        println!("{USAGE}");
+        return ExitCode::SUCCESS;
+    }
+
+    let options = match parse_args(std::env::args().skip(1)) {
+        Ok(options) => options,
+        Err(message) => {
+            eprintln!("wreg: {message}\n\n{USAGE}");
+            return ExitCode::from(2);
+        }
+    };
+
+    match run(options) {
+        Ok(()) => ExitCode::SUCCESS,
+        Err(message) => {
+            eprintln!("wreg: {message}");
+            ExitCode::FAILURE
+        }
+    }
+}
+
+fn parse_args(mut args: impl Iterator<Item = String>) -> Result<Options, String> {
+    let mut socket = None;
+    let mut json = false;
+    let mut positional = Vec::new();
+
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            "--json" => json = true,
+            "--socket" => socket = Some(PathBuf::from(args.next().ok_or("--socket needs a path")?)),
+            _ if arg.starts_with('-') => return Err(format!("unknown option {arg}")),
+            _ => positional.push(arg),
+        }
+    }
+
+    let parse_id = |arg: Option<&String>| -> Result<WindowId, String> {
+        let arg = arg.ok_or("missing window id")?;
+        arg.parse::<WindowId>().map_err(|err| err.to_string())
+    };
+
+    let command = match positional.first().map(String::as_str) {
+        Some("list") => Command::List(positional.get(1).cloned()),
+        Some("get") => Command::Get(parse_id(positional.get(1))?),
+        Some("tree") => Command::Tree,
+        Some("focus") => Command::Focus(parse_id(positional.get(1))?),
+        Some("watch") => Command::Watch,
+        Some(other) => return Err(format!("unknown command {other}")),
+        None => return Err("missing command".to_string()),
+    };
+
+    Ok(Options {
+        socket: socket.unwrap_or_else(default_socket_path),
+        json,
+        command,
+    })
+}
+
+fn run(options: Options) -> Result<(), String> {
+    let mut client = IpcClient::connect(&options.socket)
+        .map_err(|err| format!("cannot connect to {}: {err}", options.socket.display()))?;
+
+    match options.command {
+        Command::List(query) => {
+            let windows = match query {
+                Some(query) => match request(&mut client, IpcRequest::Query { query })? {
+                    IpcResponse::Windows { windows } => windows,
+                    other => return Err(format!("unexpected response: {other:?}")),
+                },
+                None => list(&mut client)?,
+            };
+            if options.json {
+                print_json(&windows);
+            } else {
+                for window in &windows {
+                    println!("{}", format_window(window));
+                }
+            }
+        }
+        Command::Get(id) => match request(&mut client, IpcRequest::Get { id })? {
+            IpcResponse::Window { window: Some(window) } => {
+                if options.json {
+                    print_json(&window);
+                } else {
+                    println!("{window:#?}");
+                }
+            }
+            IpcResponse::Window { window: None } => return Err(format!("no window {id}")),
+            other => return Err(format!("unexpected response: {other:?}")),
+        },
+        Command::Tree => {
+            let windows = list(&mut client)?;
+            if options.json {
+                print_json(&windows);
+            } else {
+                print_tree(&windows);
+            }
+        }
+        Command::Focus(id) => match request(&mut client, IpcRequest::Focus { id })? {
+            IpcResponse::Ok => {}
+            other => return Err(format!("focus failed: {other:?}")),
+        },
+        Command::Watch => {
+            let events = client.subscribe().map_err(|err| err.to_string())?;
+            for event in events {
+                let event = event.map_err(|err| err.to_string())?;
+                if options.json {
+                    print_json(&event);
+                } else {
+                    println!("{}", format_event(&event));
+                }
+            }
+        }
+    }
+    Ok(())
+}
+
+fn request(client: &mut IpcClient, request: IpcRequest) -> Result<IpcResponse, String> {
+    match client.request(&request).map_err(|err| err.to_string())? {
+        IpcResponse::Error { error } => Err(format!("{error:?}")),
+        IpcResponse::BadRequest { message } => Err(message),
+        response => Ok(response),
+    }
+}
+
+fn list(client: &mut IpcClient) -> Result<Vec<WindowInfo>, String> {
+    match request(client, IpcRequest::List)? {
+        IpcResponse::Windows { windows } => Ok(windows),
+        other => Err(format!("unexpected response: {other:?}")),
+    }
+}
+
+fn print_json<T: serde::Serialize>(value: &T) {
+    match serde_json::to_string(value) {
+        Ok(line) => println!("{line}"),
+        Err(err) => eprintln!("wreg: {err}"),
+    }
+}
+
+fn format_window(w: &WindowInfo) -> String {
+    let placement = match (w.workspace, w.output) {
+        (Some(ws), Some(out)) => format!("ws {} out {}", ws.0, out.0),
+        _ => "-".to_string(),
+    };
+    format!(
+        "{:<8} {:<9} {:<14} {} {} {:?}",
+        w.id.to_string(),
+        format!("{:?}", w.lifecycle),
+        placement,
+        if w.is_focused { "*" } else { " " },
+        w.app_id.as_deref().unwrap_or("-"),
+        w.title.as_deref().unwrap_or(""),
+    )
+}
+
+fn print_tree(windows: &[WindowInfo]) {
+    let by_id: HashMap<WindowId, &WindowInfo> = windows.iter().map(|w| (w.id, w)).collect();
+    for root in windows.iter().filter(|w| w.parent_id.is_none_or(|p| !by_id.contains_key(&p))) {
+        print_subtree(root, &by_id, 0);
+    }
+}
+
+fn print_subtree(window: &WindowInfo, by_id: &HashMap<WindowId, &WindowInfo>, depth: usize) {
+    println!("{}{}", "  ".repeat(depth), format_window(window));
+    for child in &window.children {
+        if let Some(child) = by_id.get(child) {
+            print_subtree(child, by_id, depth + 1);
+        }
+    }
+}
+
+fn format_event(event: &RegistryEvent) -> String {
+    match event {
+        RegistryEvent::WindowCreated { id, .. } => format!("created   {id}"),
+        RegistryEvent::WindowDestroyed { id } => format!("destroyed {id}"),
+        RegistryEvent::WindowChanged { id, changes } => {
+            format!("changed   {id} {}", format_changes(changes))
+        }
+        RegistryEvent::WorkspaceCreated { id, name, output } => {
+            format!("workspace {} created {name:?} on output {}", id.0, output.0)
+        }
+        RegistryEvent::WorkspaceRenamed { id, old, new } => format!("workspace {} renamed {old:?} -> {new:?}", id.0),
+        RegistryEvent::WorkspaceReordered { id, old, new } => format!("workspace {} moved {old} -> {new}", id.0),
+        RegistryEvent::WorkspaceActivated { output, old, new } => format!(
+            "output {} active workspace {:?} -> {:?}",
+            output.0,
+            old.map(|ws| ws.0),
+            new.map(|ws| ws.0)
+        ),
+        RegistryEvent::WorkspaceRemoved { id } => format!("workspace {} removed", id.0),
+        RegistryEvent::WorkspaceMoved { id, old, new } => {
+            format!("workspace {} moved to output {} (from {})", id.0, new.0, old.0)
+        }
+        RegistryEvent::OutputAdded { output } => format!(
+            "output {} added {:?} {}x{}+{}+{} scale {}",
+            output.id.0,
+            output.name,
+            output.geometry.width,
+            output.geometry.height,
+            output.geometry.x,
+            output.geometry.y,
+            output.scale
+        ),
+        RegistryEvent::OutputChanged { id, old, new } => format!("output {} changed {old:?} -> {new:?}", id.0),
+        RegistryEvent::OutputRemoved { id, fallback } => {
+            format!("output {} removed, fallback {:?}", id.0, fallback.map(|out| out.0))
+        }
+    }
+}
+
+fn format_changes(c: &WindowChanges) -> String {
+    let mut parts = Vec::new();
+    macro_rules! field {
+        ($name:ident) => {
+            if let Some(change) = &c.$name {
+                parts.push(format!("{}: {:?} -> {:?}", stringify!($name), change.old, change.new));
+            }
+        };
+    }
+    field!(lifecycle);
+    field!(geometry);
+    field!(state);
+    field!(is_focused);
+    field!(workspace);
+    field!(output);
+    field!(layer);
+    field!(stack_index);
+    field!(parent_id);
+    field!(children);
+    field!(modal);
+    field!(floating);
+    field!(title);
+    field!(app_id);
+    parts.join(", ")
+}
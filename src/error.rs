@@ -1,4 +1,5 @@
 use crate::{
+    ChangeField,
     DesktopKey,
     OutputId,
     SurfaceKey,
@@ -9,6 +10,7 @@ use crate::{
 };
 
 #[derive(Debug, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub enum RegistryError {
     DesktopKeyAlreadyRegistered { dk: DesktopKey, existing: WindowId },
     SurfaceKeyAlreadyRegistered { sk: SurfaceKey, existing: WindowId },
@@ -17,14 +19,45 @@ pub enum RegistryError {
     GeometryOverflow { id: WindowId, geometry: WindowGeometry },
     InvalidState { id: WindowId, state: WindowState },
     WorkspaceOutputMismatch { id: WindowId, workspace: Option<WorkspaceId>, output: Option<OutputId> },
+    /// A window was placed on a workspace the registry does not know.
+    UnknownWorkspace { id: WindowId, workspace: WorkspaceId },
+    InvalidWorkspaceId(WorkspaceId),
+    WorkspaceNameTaken { name: String, existing: WorkspaceId },
+    WorkspaceIndexOutOfBounds { workspace: WorkspaceId, index: usize, count: usize },
+    /// The workspace still holds windows and its output has no other workspace to take them.
+    WorkspaceNotEmpty { workspace: WorkspaceId },
+    /// A window was placed on an output the registry does not know.
+    UnknownOutput { id: WindowId, output: OutputId },
+    InvalidOutputId(OutputId),
+    OutputAlreadyRegistered(OutputId),
+    OutputNameTaken { name: String, existing: OutputId },
+    /// Scales must be finite and positive.
+    InvalidOutputScale { output: OutputId, scale: f64 },
+    /// The output still holds windows or workspaces and no other output can take them.
+    NoFallbackOutput { output: OutputId },
     ParentIsSelf { id: WindowId },
     ParentNotFound { id: WindowId, parent: WindowId },
     ParentCycle { id: WindowId, parent: WindowId },
     ChildNotFound { id: WindowId, child: WindowId },
     ChildAlreadyHasParent { id: WindowId, child: WindowId, existing_parent: WindowId },
     ChildAlreadyPresent { id: WindowId, child: WindowId },
-    StackIndexOutOfBounds { id: WindowId, index: i32, count: usize },
+    /// `modal` was set on a window without a parent, or a modal window's parent was cleared.
+    ModalWithoutParent { id: WindowId },
+    /// `raise_above` / `lower_below` relative to a window in another stack (output or layer).
+    NotInSameStack { id: WindowId, sibling: WindowId },
+    /// The restack would take a transient out of its parent's group (e.g. below its parent),
+    /// or place a window relative to one of its own transients.
+    TransientOutsideGroup { id: WindowId, sibling: WindowId },
+    /// `Layout::MasterStack` ratios must lie strictly between 0 and 1.
+    InvalidLayoutRatio { ratio: f64 },
     EventQueueClosed,
     EventQueueTimeout,
+    /// A subscriber's overflow policy discarded `missed` events at this point of the stream.
+    EventQueueLagged { missed: u64 },
+    /// A `RegistryMirror` event did not match its local state: the window's existence when
+    /// `field` is `None`, otherwise the `old` value of that field.
+    MirrorDiverged { id: WindowId, field: Option<ChangeField> },
+    /// A `RuleMatch` title pattern is not a valid regular expression.
+    InvalidRulePattern { pattern: String, message: String },
 }
 
@@ -0,0 +1,143 @@
+use std::collections::HashMap;
+
+use crate::{
+    ChangeField,
+    EventKind,
+    OutputId,
+    RegistryEvent,
+    WindowId,
+    WorkspaceId,
+};
+
+/// Selects which events a `RegistryEventQueue` subscriber receives.
+///
+/// Every criterion that is set must match; empty lists and `None` match everything.
+#[derive(Debug, Clone, Default)]
+pub struct EventFilter {
+    /// Event kinds to deliver.
+    pub kinds: Vec<EventKind>,
+    /// Windows whose events are delivered.
+    pub windows: Vec<WindowId>,
+    /// `WindowChanged` events must carry at least one of these fields.
+    /// Created/destroyed events are not affected.
+    pub fields: Vec<ChangeField>,
+    /// Window must be on this workspace before or after the event. Workspace events must name
+    /// this workspace; output events never match.
+    pub workspace: Option<WorkspaceId>,
+    /// Window must be on this output before or after the event. Workspace and output events
+    /// must name this output, so of the workspace events only creations, activations and moves
+    /// match.
+    pub output: Option<OutputId>,
+}
+
+/// Last known workspace/output per window, tracked by the queue from the events it sends.
+pub(crate) type Placements = HashMap<WindowId, Placement>;
+
+#[derive(Debug, Copy, Clone, Default)]
+pub(crate) struct Placement {
+    pub workspace: Option<WorkspaceId>,
+    pub output: Option<OutputId>,
+}
+
+impl EventFilter {
+    pub fn kinds(kinds: impl IntoIterator<Item = EventKind>) -> Self {
+        Self { kinds: kinds.into_iter().collect(), ..Self::default() }
+    }
+
+    pub fn window(id: WindowId) -> Self {
+        Self { windows: vec![id], ..Self::default() }
+    }
+
+    pub fn with_fields(mut self, fields: impl IntoIterator<Item = ChangeField>) -> Self {
+        self.fields.extend(fields);
+        self
+    }
+
+    pub fn on_workspace(mut self, workspace: WorkspaceId) -> Self {
+        self.workspace = Some(workspace);
+        self
+    }
+
+    pub fn on_output(mut self, output: OutputId) -> Self {
+        self.output = Some(output);
+        self
+    }
+
+    /// `before` is the window's placement prior to `event`.
+    pub(crate) fn matches(&self, event: &RegistryEvent, before: Option<&Placement>) -> bool {
+        if !self.kinds.is_empty() && !self.kinds.contains(&event.kind()) {
+            return false;
+        }
+        if !self.windows.is_empty() && !event.window().is_some_and(|id| self.windows.contains(&id)) {
+            return false;
+        }
+        if !self.fields.is_empty() {
+            if let RegistryEvent::WindowChanged { changes, .. } = event {
+                if !self.fields.iter().any(|field| changes.contains(*field)) {
+                    return false;
+                }
+            }
+        }
+        if self.workspace.is_none() && self.output.is_none() {
+            return true;
+        }
+        if event.window().is_none() {
+            return self.matches_non_window_event(event);
+        }
+
+        let before = before.copied().unwrap_or_default();
+        let mut after = before;
+        if let RegistryEvent::WindowChanged { changes, .. } = event {
+            if let Some(change) = &changes.workspace {
+                after.workspace = change.new;
+            }
+            if let Some(change) = &changes.output {
+                after.output = change.new;
+            }
+        }
+
+        let on = |placement: Placement| {
+            self.workspace.is_none_or(|ws| placement.workspace == Some(ws))
+                && self.output.is_none_or(|out| placement.output == Some(out))
+        };
+        on(before) || on(after)
+    }
+}
+
+impl EventFilter {
+    fn matches_non_window_event(&self, event: &RegistryEvent) -> bool {
+        let (workspaces, outputs) = match event {
+            RegistryEvent::WorkspaceCreated { id, output, .. } => ([Some(*id), None], [Some(*output), None]),
+            RegistryEvent::WorkspaceActivated { output, old, new } => ([*old, *new], [Some(*output), None]),
+            RegistryEvent::WorkspaceMoved { id, old, new } => ([Some(*id), None], [Some(*old), Some(*new)]),
+            other => ([other.workspace(), None], [other.output(), None]),
+        };
+        self.workspace.is_none_or(|ws| workspaces.contains(&Some(ws)))
+            && self.output.is_none_or(|out| outputs.contains(&Some(out)))
+    }
+}
+
+/// Updates the tracked placement after `event` has been delivered.
+pub(crate) fn track_placement(placements: &mut Placements, event: &RegistryEvent) {
+    match event {
+        RegistryEvent::WindowCreated { id, .. } => {
+            placements.insert(*id, Placement::default());
+        }
+        RegistryEvent::WindowChanged { id, changes } => {
+            if changes.workspace.is_none() && changes.output.is_none() {
+                return;
+            }
+            let placement = placements.entry(*id).or_default();
+            if let Some(change) = &changes.workspace {
+                placement.workspace = change.new;
+            }
+            if let Some(change) = &changes.output {
+                placement.output = change.new;
+            }
+        }
+        RegistryEvent::WindowDestroyed { id } => {
+            placements.remove(id);
+        }
+        _ => {}
+    }
+}
@@ -1,13 +1,130 @@
-use std::sync::{Arc, Mutex};
+use std::{
+    collections::{BTreeMap, VecDeque},
+    sync::{
+        atomic::{AtomicU64, Ordering},
+        Arc, Mutex, MutexGuard, PoisonError,
+    },
+    time::Instant,
+};
 
-use crossbeam_channel::{Receiver, RecvTimeoutError, Sender, TryRecvError};
+use crossbeam_channel::{Receiver, RecvTimeoutError, Sender, TryRecvError, TrySendError};
 
-use crate::{RegistryError, RegistryEvent};
+use crate::{
+    event_filter::{track_placement, Placements},
+    registry::coalesce_by,
+    EventEnvelope,
+    EventFilter,
+    EventOperation,
+    RegistryError,
+    RegistryEvent,
+};
+
+/// What a subscriber of a bounded queue does when its buffer is full.
+/// Unbounded queues never overflow.
+#[derive(Debug, Copy, Clone, Default, PartialEq, Eq)]
+pub enum OverflowPolicy {
+    /// Wait for the receiver to make room; `send` (and its caller) stalls until it does.
+    #[default]
+    Block,
+    /// Discard the event being sent.
+    DropNewest,
+    /// Discard the oldest buffered events to make room.
+    DropOldest,
+    /// Merge buffered `WindowChanged` events per window; drop the oldest if that is not enough.
+    CoalesceChanges,
+    /// Unsubscribe. The receiver reads what is buffered, a lag marker, then `EventQueueClosed`.
+    Disconnect,
+}
+
+/// Per-subscriber settings for `RegistryEventQueue::subscribe_with_options`.
+#[derive(Debug, Clone, Default)]
+pub struct SubscribeOptions {
+    pub filter: Option<EventFilter>,
+    pub overflow: OverflowPolicy,
+}
+
+// Lag markers are rare; boxing events would cost an allocation per delivery.
+#[allow(clippy::large_enum_variant)]
+#[derive(Debug, Clone)]
+enum Delivery {
+    Event(EventEnvelope),
+    /// Events were discarded at this point of the stream.
+    Lagged(u64),
+}
+
+/// Buffer of a subscriber with an overflow policy. The queue rewrites it and the receiver
+/// reads it under the same lock, so neither sees the other's changes half done.
+#[derive(Debug, Default)]
+struct Inbox {
+    items: VecDeque<Delivery>,
+    /// `Lagged` markers in `items`; they don't count against the capacity.
+    markers: usize,
+}
+
+#[derive(Debug)]
+struct Overflow {
+    policy: OverflowPolicy,
+    capacity: usize,
+    inbox: Arc<Mutex<Inbox>>,
+    /// Rung after every delivery; disconnected once every receiver is dropped.
+    doorbell: Sender<()>,
+}
+
+#[derive(Debug)]
+enum Outlet {
+    /// Blocking and unbounded subscribers.
+    Channel(Sender<Delivery>),
+    Overflow(Overflow),
+}
+
+#[derive(Debug, Clone)]
+enum Inlet {
+    Channel(Receiver<Delivery>),
+    Inbox { inbox: Arc<Mutex<Inbox>>, doorbell: Receiver<()> },
+}
+
+#[derive(Debug)]
+struct Subscriber {
+    outlet: Outlet,
+    filter: Option<EventFilter>,
+    /// Batches with a lower sequence number are skipped.
+    first_batch: u64,
+    /// Declared last: fields drop in order, so async receivers are woken only after the
+    /// outlet is gone and see the disconnect.
+    wakers: WakeOnDrop,
+}
+
+/// Wakers of async tasks polling one subscription. No-op without the `async` feature.
+#[derive(Debug, Clone, Default)]
+struct Wakers(#[cfg(feature = "async")] Arc<Mutex<Vec<std::task::Waker>>>);
+
+#[derive(Debug)]
+struct WakeOnDrop(Wakers);
+
+#[derive(Debug, Default)]
+struct QueueState {
+    subscribers: Vec<Subscriber>,
+    placements: Placements,
+    /// Sequence number of the next batch to deliver.
+    next_delivery: u64,
+    /// Batches sent before an earlier reservation was; delivered once the gap closes.
+    pending: BTreeMap<u64, Batch>,
+    /// Sequence number of the next event to deliver.
+    next_event: u64,
+}
+
+#[derive(Debug)]
+struct Batch {
+    operation: EventOperation,
+    timestamp: Instant,
+    events: Vec<RegistryEvent>,
+}
 
 #[derive(Debug)]
 struct QueueInner {
-    subscribers: Mutex<Vec<Sender<RegistryEvent>>>,
+    state: Mutex<QueueState>,
     capacity: Option<usize>,
+    next_batch: AtomicU64,
 }
 
 #[derive(Debug, Clone)]
@@ -15,9 +132,30 @@ pub struct RegistryEventQueue {
     inner: Arc<QueueInner>,
 }
 
+/// Receiving end of a `RegistryEventQueue` subscription.
+///
+/// When the subscriber's overflow policy discarded events, the receive methods return
+/// `EventQueueLagged` once at the point of the gap; resync from `snapshot_all` and keep
+/// receiving.
 #[derive(Debug, Clone)]
 pub struct RegistryEventReceiver {
-    receiver: Receiver<RegistryEvent>,
+    inlet: Inlet,
+    #[cfg(feature = "async")]
+    wakers: Wakers,
+}
+
+/// A reserved position in a queue's delivery order.
+///
+/// Batches are delivered in reservation order: a batch sent through a later ticket waits until
+/// every earlier ticket has been sent or dropped. Dropping a ticket delivers nothing.
+#[derive(Debug)]
+#[must_use = "later batches wait until this ticket is sent or dropped"]
+pub struct BatchTicket {
+    queue: RegistryEventQueue,
+    sequence: u64,
+    operation: EventOperation,
+    timestamp: Instant,
+    sent: bool,
 }
 
 impl RegistryEventQueue {
@@ -31,34 +169,108 @@ impl RegistryEventQueue {
 
     fn new(capacity: Option<usize>) -> Self {
         let inner = Arc::new(QueueInner {
-            subscribers: Mutex::new(Vec::new()),
+            state: Mutex::new(QueueState::default()),
             capacity,
+            next_batch: AtomicU64::new(0),
         });
         Self { inner }
     }
 
     pub fn subscribe(&self) -> RegistryEventReceiver {
-        let (tx, rx) = match self.inner.capacity {
-            Some(capacity) => crossbeam_channel::bounded(capacity),
-            None => crossbeam_channel::unbounded(),
+        self.subscribe_with_options(SubscribeOptions::default())
+    }
+
+    /// Subscribes to the events matching `filter` only. Events are matched before they are
+    /// cloned, so filtered-out events cost nothing for this subscriber.
+    ///
+    /// Workspace/output criteria use placements tracked from the events this queue has sent,
+    /// so they are only accurate for windows created after the queue. Subscribe through
+    /// `SharedRegistry::subscribe_with_snapshot_options` to seed them for existing windows.
+    pub fn subscribe_filtered(&self, filter: EventFilter) -> RegistryEventReceiver {
+        self.subscribe_with_options(SubscribeOptions { filter: Some(filter), ..SubscribeOptions::default() })
+    }
+
+    /// Subscribes with a filter and/or an overflow policy other than `Block`.
+    pub fn subscribe_with_options(&self, options: SubscribeOptions) -> RegistryEventReceiver {
+        self.subscribe_from(options, 0, Placements::new())
+    }
+
+    /// Subscribes to batches reserved at or after `first_batch` only. `seed` holds the placements
+    /// as of `first_batch`, for windows the queue may not have seen events for.
+    pub(crate) fn subscribe_from(
+        &self,
+        options: SubscribeOptions,
+        first_batch: u64,
+        seed: Placements,
+    ) -> RegistryEventReceiver {
+        let (outlet, inlet) = match (self.inner.capacity, options.overflow) {
+            (None, _) => {
+                let (tx, rx) = crossbeam_channel::unbounded();
+                (Outlet::Channel(tx), Inlet::Channel(rx))
+            }
+            (Some(capacity), OverflowPolicy::Block) => {
+                let (tx, rx) = crossbeam_channel::bounded(capacity);
+                (Outlet::Channel(tx), Inlet::Channel(rx))
+            }
+            (Some(capacity), policy) => {
+                let inbox = Arc::new(Mutex::new(Inbox::default()));
+                let (tx, rx) = crossbeam_channel::bounded(1);
+                let overflow = Overflow { policy, capacity: capacity.max(1), inbox: inbox.clone(), doorbell: tx };
+                (Outlet::Overflow(overflow), Inlet::Inbox { inbox, doorbell: rx })
+            }
         };
 
-        self.inner.subscribers.lock().expect("event queue lock poisoned").push(tx);
+        let wakers = Wakers::default();
+        let mut state = self.lock();
+        // Earlier batches still waiting for delivery will update the placements they touch, so
+        // only untracked windows are seeded then.
+        let caught_up = state.next_delivery >= first_batch;
+        for (id, placement) in seed {
+            if caught_up {
+                state.placements.insert(id, placement);
+            } else {
+                state.placements.entry(id).or_insert(placement);
+            }
+        }
+        state.subscribers.push(Subscriber {
+            outlet,
+            filter: options.filter,
+            first_batch,
+            wakers: WakeOnDrop(wakers.clone()),
+        });
 
-        RegistryEventReceiver { receiver: rx }
+        RegistryEventReceiver {
+            inlet,
+            #[cfg(feature = "async")]
+            wakers,
+        }
     }
 
+    /// Sends `events` as one batch, after every batch reserved earlier.
     pub fn send(&self, events: Vec<RegistryEvent>) -> Result<(), RegistryError> {
-        let mut subscribers = self
-            .inner
-            .subscribers
-            .lock()
-            .expect("event queue lock poisoned");
+        self.reserve().send(events)
+    }
 
-        for event in events {
-            subscribers.retain(|sender| sender.send(event.clone()).is_ok());
+    /// Reserves the next position in delivery order without taking the queue lock, so it can
+    /// be called while holding the lock that serializes the mutations producing the events.
+    pub fn reserve(&self) -> BatchTicket {
+        self.reserve_for(EventOperation::External)
+    }
+
+    /// `reserve` for a batch produced by `operation`; stamps the batch's envelopes.
+    pub fn reserve_for(&self, operation: EventOperation) -> BatchTicket {
+        BatchTicket {
+            queue: self.clone(),
+            sequence: self.inner.next_batch.fetch_add(1, Ordering::SeqCst),
+            operation,
+            timestamp: Instant::now(),
+            sent: false,
         }
-        Ok(())
+    }
+
+    /// Sequence number the next `reserve` will hand out.
+    pub(crate) fn next_batch(&self) -> u64 {
+        self.inner.next_batch.load(Ordering::SeqCst)
     }
 
     pub fn sender(&self) -> Sender<RegistryEvent> {
@@ -73,27 +285,319 @@ impl RegistryEventQueue {
         });
         tx
     }
+
+    fn lock(&self) -> MutexGuard<'_, QueueState> {
+        self.inner.state.lock().expect("event queue lock poisoned")
+    }
+}
+
+impl BatchTicket {
+    pub fn sequence(&self) -> u64 {
+        self.sequence
+    }
+
+    pub fn send(mut self, events: Vec<RegistryEvent>) -> Result<(), RegistryError> {
+        self.sent = true;
+        let batch = self.batch(events);
+        self.queue.lock().complete(self.sequence, batch);
+        Ok(())
+    }
+
+    fn batch(&self, events: Vec<RegistryEvent>) -> Batch {
+        Batch { operation: self.operation, timestamp: self.timestamp, events }
+    }
+}
+
+impl Drop for BatchTicket {
+    fn drop(&mut self) {
+        if !self.sent {
+            // May run while unwinding; later batches must not stall behind a poisoned lock.
+            let batch = self.batch(Vec::new());
+            let mut state = self.queue.inner.state.lock().unwrap_or_else(PoisonError::into_inner);
+            state.complete(self.sequence, batch);
+        }
+    }
+}
+
+impl QueueState {
+    fn complete(&mut self, sequence: u64, batch: Batch) {
+        self.pending.insert(sequence, batch);
+        while let Some(batch) = self.pending.remove(&self.next_delivery) {
+            self.dispatch(self.next_delivery, batch);
+            self.next_delivery += 1;
+        }
+    }
+
+    fn dispatch(&mut self, batch_id: u64, batch: Batch) {
+        let QueueState { subscribers, placements, next_event, .. } = self;
+
+        for event in batch.events {
+            let envelope = EventEnvelope {
+                sequence: *next_event,
+                batch_id,
+                operation: batch.operation,
+                timestamp: batch.timestamp,
+                event,
+            };
+            *next_event += 1;
+
+            let event = &envelope.event;
+            let before = event.window().and_then(|id| placements.get(&id));
+            subscribers.retain(|sub| {
+                if batch_id < sub.first_batch {
+                    return true;
+                }
+                if let Some(filter) = &sub.filter {
+                    if !filter.matches(event, before) {
+                        return true;
+                    }
+                }
+                let keep = sub.deliver(envelope.clone());
+                if keep {
+                    sub.wakers.0.wake();
+                }
+                keep
+            });
+            track_placement(placements, event);
+        }
+    }
+}
+
+impl Subscriber {
+    /// Returns `false` once the subscriber should be dropped.
+    fn deliver(&self, event: EventEnvelope) -> bool {
+        let overflow = match &self.outlet {
+            Outlet::Channel(sender) => return sender.send(Delivery::Event(event)).is_ok(),
+            Outlet::Overflow(overflow) => overflow,
+        };
+
+        let mut inbox = lock_inbox(&overflow.inbox);
+        let keep = if inbox.events() < overflow.capacity {
+            inbox.push(Delivery::Event(event));
+            true
+        } else {
+            match overflow.policy {
+                OverflowPolicy::DropNewest => {
+                    match inbox.items.back_mut() {
+                        Some(Delivery::Lagged(missed)) => *missed += 1,
+                        _ => inbox.push(Delivery::Lagged(1)),
+                    }
+                    true
+                }
+                OverflowPolicy::DropOldest => {
+                    inbox.push(Delivery::Event(event));
+                    inbox.make_room(overflow.capacity);
+                    true
+                }
+                OverflowPolicy::CoalesceChanges => {
+                    inbox.coalesce(event);
+                    inbox.make_room(overflow.capacity);
+                    true
+                }
+                // Blocking subscribers never get an `Overflow`.
+                OverflowPolicy::Disconnect | OverflowPolicy::Block => {
+                    inbox.push(Delivery::Lagged(1));
+                    false
+                }
+            }
+        };
+        drop(inbox);
+
+        match overflow.doorbell.try_send(()) {
+            Err(TrySendError::Disconnected(())) => false,
+            // A full doorbell is already rung.
+            Ok(()) | Err(TrySendError::Full(())) => keep,
+        }
+    }
+}
+
+impl Inbox {
+    fn events(&self) -> usize {
+        self.items.len() - self.markers
+    }
+
+    fn push(&mut self, item: Delivery) {
+        if matches!(item, Delivery::Lagged(_)) {
+            self.markers += 1;
+        }
+        self.items.push_back(item);
+    }
+
+    fn pop(&mut self) -> Option<Delivery> {
+        let item = self.items.pop_front();
+        if matches!(item, Some(Delivery::Lagged(_))) {
+            self.markers -= 1;
+        }
+        item
+    }
+
+    /// Merges `event` into the buffered events; lag markers are summed into one at the front.
+    fn coalesce(&mut self, event: EventEnvelope) {
+        let mut lagged = 0;
+        let mut events = Vec::with_capacity(self.items.len() + 1);
+        for item in self.items.drain(..) {
+            match item {
+                Delivery::Event(event) => events.push(event),
+                Delivery::Lagged(missed) => lagged += missed,
+            }
+        }
+        events.push(event);
+
+        self.items = coalesce_by(events, |e| &mut e.event).into_iter().map(Delivery::Event).collect();
+        self.markers = 0;
+        if lagged > 0 {
+            self.items.push_front(Delivery::Lagged(lagged));
+            self.markers = 1;
+        }
+    }
+
+    /// Drops events from the front until at most `capacity` are left, counting them in a
+    /// leading `Lagged` marker.
+    fn make_room(&mut self, capacity: usize) {
+        let mut missed = 0;
+        while self.events() > capacity || matches!(self.items.front(), Some(Delivery::Lagged(_))) {
+            match self.pop() {
+                Some(Delivery::Lagged(lagged)) => missed += lagged,
+                Some(Delivery::Event(_)) => missed += 1,
+                None => break,
+            }
+        }
+        if missed > 0 {
+            self.items.push_front(Delivery::Lagged(missed));
+            self.markers += 1;
+        }
+    }
+}
+
+fn lock_inbox(inbox: &Mutex<Inbox>) -> MutexGuard<'_, Inbox> {
+    // Every change to an inbox is complete before anything can panic.
+    inbox.lock().unwrap_or_else(PoisonError::into_inner)
+}
+
+impl Inlet {
+    fn try_take(&self) -> Result<Delivery, TryRecvError> {
+        let (inbox, doorbell) = match self {
+            Inlet::Channel(receiver) => return receiver.try_recv(),
+            Inlet::Inbox { inbox, doorbell } => (inbox, doorbell),
+        };
+        loop {
+            if let Some(item) = lock_inbox(inbox).pop() {
+                return Ok(item);
+            }
+            match doorbell.try_recv() {
+                // A delivery raced the check above.
+                Ok(()) => continue,
+                Err(TryRecvError::Empty) => return Err(TryRecvError::Empty),
+                // The last delivery may have happened right before the disconnect.
+                Err(TryRecvError::Disconnected) => {
+                    return lock_inbox(inbox).pop().ok_or(TryRecvError::Disconnected);
+                }
+            }
+        }
+    }
+
+    /// Waits until `deadline`, or until something arrives without one.
+    fn take(&self, deadline: Option<Instant>) -> Result<Delivery, RecvTimeoutError> {
+        let doorbell = match self {
+            Inlet::Channel(receiver) => return recv_until(receiver, deadline),
+            Inlet::Inbox { doorbell, .. } => doorbell,
+        };
+        loop {
+            match self.try_take() {
+                Ok(item) => return Ok(item),
+                Err(TryRecvError::Disconnected) => return Err(RecvTimeoutError::Disconnected),
+                Err(TryRecvError::Empty) => {}
+            }
+            // A disconnect is reported by `try_take` once the inbox is drained.
+            if let Err(RecvTimeoutError::Timeout) = recv_until(doorbell, deadline) {
+                return Err(RecvTimeoutError::Timeout);
+            }
+        }
+    }
+}
+
+fn recv_until<T>(receiver: &Receiver<T>, deadline: Option<Instant>) -> Result<T, RecvTimeoutError> {
+    match deadline {
+        Some(deadline) => receiver.recv_deadline(deadline),
+        None => receiver.recv().map_err(|_| RecvTimeoutError::Disconnected),
+    }
+}
+
+impl Wakers {
+    fn wake(&self) {
+        #[cfg(feature = "async")]
+        for waker in self.0.lock().expect("waker lock poisoned").drain(..) {
+            waker.wake();
+        }
+    }
+
+    #[cfg(feature = "async")]
+    fn register(&self, waker: &std::task::Waker) {
+        let mut wakers = self.0.lock().expect("waker lock poisoned");
+        if !wakers.iter().any(|w| w.will_wake(waker)) {
+            wakers.push(waker.clone());
+        }
+    }
+}
+
+impl Drop for WakeOnDrop {
+    fn drop(&mut self) {
+        self.0.wake();
+    }
 }
 
 impl RegistryEventReceiver {
     pub fn recv(&self) -> Result<RegistryEvent, RegistryError> {
-        self.receiver.recv().map_err(|_| RegistryError::EventQueueClosed)
+        self.recv_envelope().map(|envelope| envelope.event)
     }
 
     pub fn try_recv(&self) -> Result<Option<RegistryEvent>, RegistryError> {
-        match self.receiver.try_recv() {
-            Ok(event) => Ok(Some(event)),
+        Ok(self.try_recv_envelope()?.map(|envelope| envelope.event))
+    }
+
+    pub fn recv_timeout(&self, timeout: std::time::Duration) -> Result<RegistryEvent, RegistryError> {
+        self.recv_envelope_timeout(timeout).map(|envelope| envelope.event)
+    }
+
+    /// Blocking iterator over events. Lag markers are skipped; use `recv` to observe them.
+    pub fn iter(&self) -> impl Iterator<Item = RegistryEvent> + '_ {
+        std::iter::from_fn(|| self.inlet.take(None).ok()).filter_map(|delivery| match delivery {
+            Delivery::Event(envelope) => Some(envelope.event),
+            Delivery::Lagged(_) => None,
+        })
+    }
+
+    /// Like `recv`, with the event's sequence number, batch, operation and timestamp.
+    pub fn recv_envelope(&self) -> Result<EventEnvelope, RegistryError> {
+        match self.inlet.take(None) {
+            Ok(delivery) => Self::open(delivery),
+            Err(_) => Err(RegistryError::EventQueueClosed),
+        }
+    }
+
+    pub fn try_recv_envelope(&self) -> Result<Option<EventEnvelope>, RegistryError> {
+        match self.inlet.try_take() {
+            Ok(delivery) => Self::open(delivery).map(Some),
             Err(TryRecvError::Empty) => Ok(None),
             Err(TryRecvError::Disconnected) => Err(RegistryError::EventQueueClosed),
         }
     }
 
-    pub fn recv_timeout(&self, timeout: std::time::Duration) -> Result<RegistryEvent, RegistryError> {
-        self.receiver.recv_timeout(timeout).map_err(Self::map_recv_timeout)
+    pub fn recv_envelope_timeout(
+        &self,
+        timeout: std::time::Duration,
+    ) -> Result<EventEnvelope, RegistryError> {
+        match self.inlet.take(Instant::now().checked_add(timeout)) {
+            Ok(delivery) => Self::open(delivery),
+            Err(err) => Err(Self::map_recv_timeout(err)),
+        }
     }
 
-    pub fn iter(&self) -> impl Iterator<Item = RegistryEvent> + '_ {
-        self.receiver.iter()
+    fn open(delivery: Delivery) -> Result<EventEnvelope, RegistryError> {
+        match delivery {
+            Delivery::Event(envelope) => Ok(envelope),
+            Delivery::Lagged(missed) => Err(RegistryError::EventQueueLagged { missed }),
+        }
     }
 
     pub(crate) fn map_recv_timeout(err: RecvTimeoutError) -> RegistryError {
@@ -104,6 +608,59 @@ impl RegistryEventReceiver {
     }
 }
 
+/// Async receiving, with the `async` feature. Works with any executor: senders wake the tasks
+/// that polled an empty subscription.
+#[cfg(feature = "async")]
+impl RegistryEventReceiver {
+    pub fn poll_recv(
+        &self,
+        cx: &mut std::task::Context<'_>,
+    ) -> std::task::Poll<Result<RegistryEvent, RegistryError>> {
+        self.poll_recv_envelope(cx).map(|result| result.map(|envelope| envelope.event))
+    }
+
+    pub fn poll_recv_envelope(
+        &self,
+        cx: &mut std::task::Context<'_>,
+    ) -> std::task::Poll<Result<EventEnvelope, RegistryError>> {
+        use std::task::Poll;
+
+        if let Some(result) = self.try_recv_envelope().transpose() {
+            return Poll::Ready(result);
+        }
+        // Register before checking again so an event sent in between is not missed.
+        self.wakers.register(cx.waker());
+        match self.try_recv_envelope().transpose() {
+            Some(result) => Poll::Ready(result),
+            None => Poll::Pending,
+        }
+    }
+
+    pub async fn recv_async(&self) -> Result<RegistryEvent, RegistryError> {
+        std::future::poll_fn(|cx| self.poll_recv(cx)).await
+    }
+
+    pub async fn recv_envelope_async(&self) -> Result<EventEnvelope, RegistryError> {
+        std::future::poll_fn(|cx| self.poll_recv_envelope(cx)).await
+    }
+}
+
+/// Yields `Err(EventQueueLagged)` at gaps and ends when the queue is closed.
+#[cfg(feature = "async")]
+impl futures_core::Stream for RegistryEventReceiver {
+    type Item = Result<RegistryEvent, RegistryError>;
+
+    fn poll_next(
+        self: std::pin::Pin<&mut Self>,
+        cx: &mut std::task::Context<'_>,
+    ) -> std::task::Poll<Option<Self::Item>> {
+        self.poll_recv(cx).map(|result| match result {
+            Err(RegistryError::EventQueueClosed) => None,
+            other => Some(other),
+        })
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::RegistryEventReceiver;
@@ -122,4 +679,3 @@ mod tests {
         assert!(matches!(closed, RegistryError::EventQueueClosed));
     }
 }
-
@@ -2,6 +2,8 @@ use crate::{
     DesktopKey,
     LifecycleState,
     OutputId,
+    OutputInfo,
+    StackLayer,
     SurfaceKey,
     WindowGeometry,
     WindowId,
@@ -10,12 +12,34 @@ use crate::{
 };
 
 #[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct WindowChange<T> {
     pub old: T,
     pub new: T,
 }
 
+/// Names one field of `WindowChanges`.
+#[derive(Debug, Copy, Clone, Eq, PartialEq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum ChangeField {
+    Lifecycle,
+    Geometry,
+    State,
+    IsFocused,
+    Workspace,
+    Output,
+    Layer,
+    StackIndex,
+    ParentId,
+    Children,
+    Modal,
+    Floating,
+    Title,
+    AppId,
+}
+
 #[derive(Debug, Clone, PartialEq, Default)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct WindowChanges {
     pub lifecycle: Option<WindowChange<LifecycleState>>,
     pub geometry: Option<WindowChange<Option<WindowGeometry>>>,
@@ -23,9 +47,12 @@ pub struct WindowChanges {
     pub is_focused: Option<WindowChange<bool>>,
     pub workspace: Option<WindowChange<Option<WorkspaceId>>>,
     pub output: Option<WindowChange<Option<OutputId>>>,
+    pub layer: Option<WindowChange<StackLayer>>,
     pub stack_index: Option<WindowChange<i32>>,
     pub parent_id: Option<WindowChange<Option<WindowId>>>,
     pub children: Option<WindowChange<Vec<WindowId>>>,
+    pub modal: Option<WindowChange<bool>>,
+    pub floating: Option<WindowChange<bool>>,
     pub title: Option<WindowChange<Option<String>>>,
     pub app_id: Option<WindowChange<Option<String>>>,
 }
@@ -38,15 +65,68 @@ impl WindowChanges {
             && self.is_focused.is_none()
             && self.workspace.is_none()
             && self.output.is_none()
+            && self.layer.is_none()
             && self.stack_index.is_none()
             && self.parent_id.is_none()
             && self.children.is_none()
+            && self.modal.is_none()
+            && self.floating.is_none()
             && self.title.is_none()
             && self.app_id.is_none()
     }
+
+    pub fn contains(&self, field: ChangeField) -> bool {
+        match field {
+            ChangeField::Lifecycle => self.lifecycle.is_some(),
+            ChangeField::Geometry => self.geometry.is_some(),
+            ChangeField::State => self.state.is_some(),
+            ChangeField::IsFocused => self.is_focused.is_some(),
+            ChangeField::Workspace => self.workspace.is_some(),
+            ChangeField::Output => self.output.is_some(),
+            ChangeField::Layer => self.layer.is_some(),
+            ChangeField::StackIndex => self.stack_index.is_some(),
+            ChangeField::ParentId => self.parent_id.is_some(),
+            ChangeField::Children => self.children.is_some(),
+            ChangeField::Modal => self.modal.is_some(),
+            ChangeField::Floating => self.floating.is_some(),
+            ChangeField::Title => self.title.is_some(),
+            ChangeField::AppId => self.app_id.is_some(),
+        }
+    }
+
+    /// Folds a later change set into this one, keeping the earliest `old` and the latest `new`
+    /// for each field. Fields that end up unchanged are cleared.
+    pub fn merge(&mut self, later: WindowChanges) {
+        merge_field(&mut self.lifecycle, later.lifecycle);
+        merge_field(&mut self.geometry, later.geometry);
+        merge_field(&mut self.state, later.state);
+        merge_field(&mut self.is_focused, later.is_focused);
+        merge_field(&mut self.workspace, later.workspace);
+        merge_field(&mut self.output, later.output);
+        merge_field(&mut self.layer, later.layer);
+        merge_field(&mut self.stack_index, later.stack_index);
+        merge_field(&mut self.parent_id, later.parent_id);
+        merge_field(&mut self.children, later.children);
+        merge_field(&mut self.modal, later.modal);
+        merge_field(&mut self.floating, later.floating);
+        merge_field(&mut self.title, later.title);
+        merge_field(&mut self.app_id, later.app_id);
+    }
+}
+
+fn merge_field<T: PartialEq>(earlier: &mut Option<WindowChange<T>>, later: Option<WindowChange<T>>) {
+    let Some(later) = later else { return };
+    let merged = match earlier.take() {
+        Some(first) => WindowChange { old: first.old, new: later.new },
+        None => later,
+    };
+    if merged.old != merged.new {
+        *earlier = Some(merged);
+    }
 }
 
 #[derive(Debug, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub enum RegistryEvent {
     WindowCreated {
         id: WindowId,
@@ -60,5 +140,149 @@ pub enum RegistryEvent {
     WindowDestroyed {
         id: WindowId,
     },
+    WorkspaceCreated {
+        id: WorkspaceId,
+        name: String,
+        output: OutputId,
+    },
+    WorkspaceRenamed {
+        id: WorkspaceId,
+        old: String,
+        new: String,
+    },
+    /// `id` moved from position `old` to `new` in `Registry::workspaces`; the workspaces in
+    /// between shift by one without events of their own.
+    WorkspaceReordered {
+        id: WorkspaceId,
+        old: usize,
+        new: usize,
+    },
+    /// The active workspace of `output` changed.
+    WorkspaceActivated {
+        output: OutputId,
+        old: Option<WorkspaceId>,
+        new: Option<WorkspaceId>,
+    },
+    WorkspaceRemoved {
+        id: WorkspaceId,
+    },
+    /// `id` was moved to another output because `old` was removed.
+    WorkspaceMoved {
+        id: WorkspaceId,
+        old: OutputId,
+        new: OutputId,
+    },
+    OutputAdded {
+        output: OutputInfo,
+    },
+    OutputChanged {
+        id: OutputId,
+        old: OutputInfo,
+        new: OutputInfo,
+    },
+    /// Follows the migration of the output's windows and workspaces to `fallback`.
+    OutputRemoved {
+        id: OutputId,
+        fallback: Option<OutputId>,
+    },
 }
 
+#[derive(Debug, Copy, Clone, Eq, PartialEq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum EventKind {
+    Created,
+    Changed,
+    Destroyed,
+    /// Any `Workspace*` event.
+    Workspace,
+    /// Any `Output*` event.
+    Output,
+}
+
+impl RegistryEvent {
+    pub fn kind(&self) -> EventKind {
+        match self {
+            RegistryEvent::WindowCreated { .. } => EventKind::Created,
+            RegistryEvent::WindowChanged { .. } => EventKind::Changed,
+            RegistryEvent::WindowDestroyed { .. } => EventKind::Destroyed,
+            RegistryEvent::WorkspaceCreated { .. }
+            | RegistryEvent::WorkspaceRenamed { .. }
+            | RegistryEvent::WorkspaceReordered { .. }
+            | RegistryEvent::WorkspaceActivated { .. }
+            | RegistryEvent::WorkspaceRemoved { .. }
+            | RegistryEvent::WorkspaceMoved { .. } => EventKind::Workspace,
+            RegistryEvent::OutputAdded { .. }
+            | RegistryEvent::OutputChanged { .. }
+            | RegistryEvent::OutputRemoved { .. } => EventKind::Output,
+        }
+    }
+
+    /// The window a `Window*` event is about.
+    ///
+    /// # Panics
+    ///
+    /// On workspace and output events.
+    #[deprecated(note = "panics on workspace and output events; use `window`")]
+    pub fn window_id(&self) -> WindowId {
+        self.window().expect("window_id called on a workspace or output event")
+    }
+
+    /// The window a `Window*` event is about; `None` for workspace and output events.
+    pub fn window(&self) -> Option<WindowId> {
+        match self {
+            RegistryEvent::WindowCreated { id, .. }
+            | RegistryEvent::WindowChanged { id, .. }
+            | RegistryEvent::WindowDestroyed { id } => Some(*id),
+            _ => None,
+        }
+    }
+
+    /// The workspace a `Workspace*` event is about; the newly active one for activations.
+    pub fn workspace(&self) -> Option<WorkspaceId> {
+        match self {
+            RegistryEvent::WorkspaceCreated { id, .. }
+            | RegistryEvent::WorkspaceRenamed { id, .. }
+            | RegistryEvent::WorkspaceReordered { id, .. }
+            | RegistryEvent::WorkspaceRemoved { id }
+            | RegistryEvent::WorkspaceMoved { id, .. } => Some(*id),
+            RegistryEvent::WorkspaceActivated { new, .. } => *new,
+            _ => None,
+        }
+    }
+
+    /// The output an `Output*` event is about.
+    pub fn output(&self) -> Option<OutputId> {
+        match self {
+            RegistryEvent::OutputAdded { output } => Some(output.id),
+            RegistryEvent::OutputChanged { id, .. } | RegistryEvent::OutputRemoved { id, .. } => Some(*id),
+            _ => None,
+        }
+    }
+}
+
+/// The registry operation that produced an event batch.
+#[derive(Debug, Copy, Clone, Eq, PartialEq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum EventOperation {
+    Insert,
+    Update,
+    Remove,
+    Map,
+    Unmap,
+    Transaction,
+    /// Sent directly through `RegistryEventQueue::send` or `sender`.
+    External,
+}
+
+/// A `RegistryEvent` as delivered by `RegistryEventQueue`, with delivery metadata.
+#[derive(Debug, Clone)]
+pub struct EventEnvelope {
+    /// Queue-wide, increasing by one per event in delivery order. Filtered subscribers see gaps.
+    pub sequence: u64,
+    /// Shared by all events of one batch (one registry call); the batch's `BatchTicket` sequence.
+    pub batch_id: u64,
+    pub operation: EventOperation,
+    /// When the batch was reserved, i.e. right after the mutation was applied.
+    pub timestamp: std::time::Instant,
+    pub event: RegistryEvent,
+}
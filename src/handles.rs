@@ -1,13 +1,14 @@
 use std::{collections::HashMap, ptr::NonNull};
-use crate::{WindowId, weston_view};
+use crate::{OutputId, WindowId, weston_output, weston_view};
 
 pub struct CompositorHandles {
     views: HashMap<WindowId, NonNull<weston_view>>,
+    outputs: HashMap<OutputId, NonNull<weston_output>>,
 }
 
 impl CompositorHandles {
     pub fn new() -> Self {
-        Self { views: HashMap::new() }
+        Self { views: HashMap::new(), outputs: HashMap::new() }
     }
 
     pub fn set_view(&mut self, id: WindowId, view: *mut weston_view) {
@@ -22,5 +23,17 @@ impl CompositorHandles {
     pub fn get_view(&self, id: WindowId) -> Option<NonNull<weston_view>> {
         self.views.get(&id).copied()
     }
-}
 
+    pub fn set_output(&mut self, id: OutputId, output: *mut weston_output) {
+        let nn = NonNull::new(output).expect("weston_output ptr was null");
+        self.outputs.insert(id, nn);
+    }
+
+    pub fn remove_output(&mut self, id: OutputId) {
+        self.outputs.remove(&id);
+    }
+
+    pub fn get_output(&self, id: OutputId) -> Option<NonNull<weston_output>> {
+        self.outputs.get(&id).copied()
+    }
+}
@@ -1,7 +1,8 @@
 use std::{
     num::NonZeroU32, 
     hash::{Hash, Hasher},
-    fmt::{Debug, Formatter, Result},
+    fmt::{Debug, Display, Formatter, Result},
+    str::FromStr,
 };
 
 use crate::{
@@ -10,6 +11,7 @@ use crate::{
 };
 
 #[derive(Copy, Clone, Debug, Eq, PartialEq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct WindowId {
     pub(crate) index: u32,
     pub(crate) gen: NonZeroU32,
@@ -22,6 +24,36 @@ impl WindowId {
     }
 }
 
+/// Formats as `index:gen`, the form accepted by `FromStr`.
+impl Display for WindowId {
+    fn fmt(&self, f: &mut Formatter<'_>) -> Result {
+        write!(f, "{}:{}", self.index, self.gen)
+    }
+}
+
+impl FromStr for WindowId {
+    type Err = ParseWindowIdError;
+
+    fn from_str(s: &str) -> std::result::Result<Self, Self::Err> {
+        let err = || ParseWindowIdError(s.to_string());
+        let (index, gen) = s.split_once(':').ok_or_else(err)?;
+        let index = index.parse::<u32>().map_err(|_| err())?;
+        let gen = gen.parse::<NonZeroU32>().map_err(|_| err())?;
+        Ok(Self { index, gen })
+    }
+}
+
+#[derive(Debug, Clone, Eq, PartialEq)]
+pub struct ParseWindowIdError(String);
+
+impl Display for ParseWindowIdError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> Result {
+        write!(f, "invalid window id {:?}, expected index:gen", self.0)
+    }
+}
+
+impl std::error::Error for ParseWindowIdError {}
+
 #[repr(transparent)]
 #[derive(Copy, Clone, Eq, PartialEq)]
 pub struct DesktopKey(usize);
@@ -31,6 +63,8 @@ impl DesktopKey {
         Self(ptr as usize)
     }
 
+    /// Only meaningful for keys built with `from_ptr`. A key obtained through `Deserialize`
+    /// holds an opaque token: its pointer must never be dereferenced or handed to libweston.
     pub fn as_ptr(self) -> *mut weston_desktop_surface {
         self.0 as *mut weston_desktop_surface
     }
@@ -57,6 +91,8 @@ impl SurfaceKey {
         Self(ptr as usize)
     }
 
+    /// Only meaningful for keys built with `from_ptr`. A key obtained through `Deserialize`
+    /// holds an opaque token: its pointer must never be dereferenced or handed to libweston.
     pub fn as_ptr(self) -> *mut weston_surface {
         self.0 as *mut weston_surface
     }
@@ -74,3 +110,77 @@ impl Debug for SurfaceKey {
     }
 }
 
+
+// Keys wrap compositor pointers, so by default they serialize as an opaque per-process token:
+// stable for the lifetime of the process (equal keys give equal tokens) but not an address.
+// Deserialized keys carry that token and are only useful for identity comparisons among
+// themselves: they don't equal the key that was serialized, and `as_ptr` on them returns a
+// bogus pointer.
+#[cfg(feature = "serde")]
+mod key_serde {
+    use std::{
+        cell::Cell,
+        collections::hash_map::RandomState,
+        hash::BuildHasher,
+        sync::OnceLock,
+    };
+
+    use serde::{Deserialize, Deserializer, Serialize, Serializer};
+
+    use super::{DesktopKey, SurfaceKey};
+
+    thread_local! {
+        static RAW_KEYS: Cell<bool> = const { Cell::new(false) };
+    }
+
+    /// Serializes `DesktopKey` / `SurfaceKey` as raw pointer values while `f` runs on this thread.
+    ///
+    /// Only meant for local debugging output; raw values expose compositor addresses.
+    pub fn with_raw_keys<R>(f: impl FnOnce() -> R) -> R {
+        struct Reset(bool);
+        impl Drop for Reset {
+            fn drop(&mut self) {
+                RAW_KEYS.with(|raw| raw.set(self.0));
+            }
+        }
+
+        let _reset = Reset(RAW_KEYS.with(|raw| raw.replace(true)));
+        f()
+    }
+
+    fn token(value: usize) -> u64 {
+        static SECRET: OnceLock<RandomState> = OnceLock::new();
+        if RAW_KEYS.with(Cell::get) {
+            value as u64
+        } else {
+            SECRET.get_or_init(RandomState::new).hash_one(value)
+        }
+    }
+
+    impl Serialize for DesktopKey {
+        fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+            serializer.serialize_u64(token(self.0))
+        }
+    }
+
+    impl<'de> Deserialize<'de> for DesktopKey {
+        fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+            u64::deserialize(deserializer).map(|v| DesktopKey(v as usize))
+        }
+    }
+
+    impl Serialize for SurfaceKey {
+        fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+            serializer.serialize_u64(token(self.0))
+        }
+    }
+
+    impl<'de> Deserialize<'de> for SurfaceKey {
+        fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+            u64::deserialize(deserializer).map(|v| SurfaceKey(v as usize))
+        }
+    }
+}
+
+#[cfg(feature = "serde")]
+pub use key_serde::with_raw_keys;
@@ -0,0 +1,293 @@
+use std::{
+    io::{self, BufRead, BufReader, BufWriter, Write},
+    os::unix::net::{UnixListener, UnixStream},
+    path::{Path, PathBuf},
+    sync::Arc,
+};
+
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    OverflowPolicy,
+    Query,
+    RegistryError,
+    RegistryEvent,
+    RegistryEventQueue,
+    SharedRegistry,
+    SubscribeOptions,
+    WindowGeometry,
+    WindowId,
+    WindowInfo,
+    WindowUpdate,
+};
+
+/// One JSON line sent by a client.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(tag = "cmd", rename_all = "snake_case")]
+pub enum IpcRequest {
+    List,
+    /// Windows matching a textual `Query`, answered with `windows`.
+    Query { query: String },
+    Get { id: WindowId },
+    Focus { id: WindowId },
+    Move { id: WindowId, geometry: WindowGeometry },
+    Close { id: WindowId },
+    /// Switches the connection into streaming mode; no further requests are read.
+    Subscribe,
+}
+
+/// One JSON line sent by the server.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum IpcResponse {
+    Windows { windows: Vec<WindowInfo> },
+    Window { window: Option<WindowInfo> },
+    Ok,
+    Subscribed,
+    Event { event: RegistryEvent },
+    /// The client read too slowly and `missed` events were dropped; resync with `list`.
+    Lagged { missed: u64 },
+    Error { error: RegistryError },
+    BadRequest { message: String },
+}
+
+type CloseHandler = dyn Fn(WindowId) -> Result<(), RegistryError> + Send + Sync;
+
+#[derive(Clone)]
+struct IpcContext {
+    reg: SharedRegistry,
+    queue: RegistryEventQueue,
+    close: Option<Arc<CloseHandler>>,
+}
+
+/// Serves JSON-lines requests against a `SharedRegistry` on a Unix domain socket.
+///
+/// Mutations go through the `*_queued` methods, so their events reach every queue subscriber,
+/// including clients that sent `subscribe`.
+pub struct IpcServer {
+    listener: UnixListener,
+    path: PathBuf,
+    ctx: IpcContext,
+}
+
+impl IpcServer {
+    pub fn bind<P: AsRef<Path>>(
+        path: P,
+        reg: SharedRegistry,
+        queue: RegistryEventQueue,
+    ) -> io::Result<Self> {
+        let path = path.as_ref().to_path_buf();
+        let listener = UnixListener::bind(&path)?;
+        Ok(Self {
+            listener,
+            path,
+            ctx: IpcContext { reg, queue, close: None },
+        })
+    }
+
+    /// Installs the handler for `close` requests. The registry cannot close clients itself;
+    /// without a handler `close` is answered with a `bad_request`.
+    pub fn on_close<F>(mut self, handler: F) -> Self
+    where
+        F: Fn(WindowId) -> Result<(), RegistryError> + Send + Sync + 'static,
+    {
+        self.ctx.close = Some(Arc::new(handler));
+        self
+    }
+
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+
+    /// Accepts connections forever, one thread per client. A failed accept is logged to
+    /// stderr and does not stop the server.
+    pub fn serve(&self) -> io::Result<()> {
+        for stream in self.listener.incoming() {
+            let stream = match stream {
+                Ok(stream) => stream,
+                Err(err) => {
+                    eprintln!("ipc: accept on {} failed: {err}", self.path.display());
+                    // Out of file descriptors and the like; give clients time to go away.
+                    std::thread::sleep(std::time::Duration::from_millis(100));
+                    continue;
+                }
+            };
+            let ctx = self.ctx.clone();
+            std::thread::spawn(move || {
+                let _ = handle_client(stream, &ctx);
+            });
+        }
+        Ok(())
+    }
+
+    /// Runs `serve` on a background thread.
+    pub fn spawn(self) -> std::thread::JoinHandle<io::Result<()>> {
+        std::thread::spawn(move || self.serve())
+    }
+}
+
+impl Drop for IpcServer {
+    fn drop(&mut self) {
+        let _ = std::fs::remove_file(&self.path);
+    }
+}
+
+/// Blocking client for `IpcServer`, one request/response pair at a time.
+pub struct IpcClient {
+    reader: BufReader<UnixStream>,
+    writer: UnixStream,
+}
+
+impl IpcClient {
+    pub fn connect<P: AsRef<Path>>(path: P) -> io::Result<Self> {
+        let writer = UnixStream::connect(path)?;
+        let reader = BufReader::new(writer.try_clone()?);
+        Ok(Self { reader, writer })
+    }
+
+    pub fn request(&mut self, request: &IpcRequest) -> io::Result<IpcResponse> {
+        write_request(&mut self.writer, request)?;
+        self.read_response()
+    }
+
+    /// Sends `subscribe` and returns the event stream. Iteration ends when the server closes
+    /// the connection. When the server dropped events because this client read too slowly,
+    /// an `Err` says how many; iteration continues after it.
+    pub fn subscribe(mut self) -> io::Result<impl Iterator<Item = io::Result<RegistryEvent>>> {
+        match self.request(&IpcRequest::Subscribe)? {
+            IpcResponse::Subscribed => {}
+            other => return Err(unexpected(other)),
+        }
+        Ok(std::iter::from_fn(move || match self.read_response() {
+            Ok(IpcResponse::Event { event }) => Some(Ok(event)),
+            Ok(IpcResponse::Lagged { missed }) => {
+                Some(Err(io::Error::other(format!("event stream lagged, {missed} events missed"))))
+            }
+            Ok(other) => Some(Err(unexpected(other))),
+            Err(err) if err.kind() == io::ErrorKind::UnexpectedEof => None,
+            Err(err) => Some(Err(err)),
+        }))
+    }
+
+    fn read_response(&mut self) -> io::Result<IpcResponse> {
+        let mut line = String::new();
+        if self.reader.read_line(&mut line)? == 0 {
+            return Err(io::ErrorKind::UnexpectedEof.into());
+        }
+        serde_json::from_str(&line).map_err(io::Error::from)
+    }
+}
+
+/// `$WREG_SOCKET`, or `wreg.sock` in `$XDG_RUNTIME_DIR` (falling back to the temp dir).
+pub fn default_socket_path() -> PathBuf {
+    if let Some(path) = std::env::var_os("WREG_SOCKET") {
+        return PathBuf::from(path);
+    }
+    let dir = std::env::var_os("XDG_RUNTIME_DIR")
+        .map(PathBuf::from)
+        .unwrap_or_else(std::env::temp_dir);
+    dir.join("wreg.sock")
+}
+
+fn unexpected(response: IpcResponse) -> io::Error {
+    io::Error::new(io::ErrorKind::InvalidData, format!("unexpected response: {response:?}"))
+}
+
+fn handle_client(stream: UnixStream, ctx: &IpcContext) -> io::Result<()> {
+    let reader = BufReader::new(stream.try_clone()?);
+    let mut writer = BufWriter::new(stream);
+
+    for line in reader.lines() {
+        let line = line?;
+        if line.trim().is_empty() {
+            continue;
+        }
+
+        let request = match serde_json::from_str::<IpcRequest>(&line) {
+            Ok(request) => request,
+            Err(err) => {
+                write_response(&mut writer, &IpcResponse::BadRequest { message: err.to_string() })?;
+                continue;
+            }
+        };
+
+        if let IpcRequest::Subscribe = request {
+            return stream_events(&mut writer, ctx);
+        }
+
+        write_response(&mut writer, &handle_request(request, ctx))?;
+    }
+    Ok(())
+}
+
+fn handle_request(request: IpcRequest, ctx: &IpcContext) -> IpcResponse {
+    let result = match request {
+        IpcRequest::List => return IpcResponse::Windows { windows: ctx.reg.snapshot_all() },
+        IpcRequest::Query { query } => {
+            return match Query::parse(&query) {
+                Ok(query) => {
+                    let windows = ctx.reg.query(&query).into_iter().filter_map(|id| ctx.reg.snapshot(id)).collect();
+                    IpcResponse::Windows { windows }
+                }
+                Err(err) => IpcResponse::BadRequest { message: err.to_string() },
+            };
+        }
+        IpcRequest::Get { id } => return IpcResponse::Window { window: ctx.reg.snapshot(id) },
+        IpcRequest::Focus { id } => {
+            let update = WindowUpdate { is_focused: Some(true), ..WindowUpdate::default() };
+            ctx.reg.update_window_queued(id, update, &ctx.queue)
+        }
+        IpcRequest::Move { id, geometry } => {
+            let update = WindowUpdate { geometry: Some(Some(geometry)), ..WindowUpdate::default() };
+            ctx.reg.update_window_queued(id, update, &ctx.queue)
+        }
+        IpcRequest::Close { id } => match &ctx.close {
+            Some(close) => {
+                if ctx.reg.snapshot(id).is_none() {
+                    Err(RegistryError::InvalidWindowId(id))
+                } else {
+                    close(id)
+                }
+            }
+            None => {
+                return IpcResponse::BadRequest { message: "close is not supported".to_string() };
+            }
+        },
+        IpcRequest::Subscribe => {
+            return IpcResponse::BadRequest { message: "subscribe must start a connection stream".to_string() };
+        }
+    };
+
+    match result {
+        Ok(()) => IpcResponse::Ok,
+        Err(error) => IpcResponse::Error { error },
+    }
+}
+
+/// Streams with `DropOldest`, so a client that stops reading never stalls the queue's other
+/// subscribers; it gets a `lagged` frame at the gap instead.
+fn stream_events<W: Write>(writer: &mut W, ctx: &IpcContext) -> io::Result<()> {
+    let options = SubscribeOptions { overflow: OverflowPolicy::DropOldest, ..SubscribeOptions::default() };
+    let receiver = ctx.queue.subscribe_with_options(options);
+    write_response(writer, &IpcResponse::Subscribed)?;
+    loop {
+        let response = match receiver.recv() {
+            Ok(event) => IpcResponse::Event { event },
+            Err(RegistryError::EventQueueLagged { missed }) => IpcResponse::Lagged { missed },
+            Err(_) => return Ok(()),
+        };
+        write_response(writer, &response)?;
+    }
+}
+
+fn write_request<W: Write>(writer: &mut W, request: &IpcRequest) -> io::Result<()> {
+    serde_json::to_writer(&mut *writer, request)?;
+    writer.write_all(b"\n")?;
+    writer.flush()
+}
+
+fn write_response<W: Write>(writer: &mut W, response: &IpcResponse) -> io::Result<()> {
+    serde_json::to_writer(&mut *writer, response)?;
+    writer.write_all(b"\n")?;
+    writer.flush()
+}
@@ -0,0 +1,317 @@
+use std::sync::{Arc, Mutex};
+
+use crate::{
+    ChildRemovalPolicy,
+    DesktopKey,
+    Gaps,
+    Layout,
+    OutputId,
+    OutputInfo,
+    OutputUpdate,
+    Registry,
+    RegistryError,
+    RegistryEvent,
+    SurfaceKey,
+    WindowId,
+    WindowUpdate,
+    WorkspaceId,
+};
+
+/// One registry mutation as it was requested, before validation.
+#[derive(Debug, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "serde", serde(tag = "kind", rename_all = "snake_case"))]
+pub enum JournalOp {
+    Insert { dk: DesktopKey, sk: SurfaceKey },
+    Update { id: WindowId, update: WindowUpdate },
+    Map { id: WindowId },
+    Unmap { id: WindowId },
+    Remove { id: WindowId },
+    Raise { id: WindowId },
+    Lower { id: WindowId },
+    RaiseAbove { id: WindowId, sibling: WindowId },
+    LowerBelow { id: WindowId, sibling: WindowId },
+    SetChildRemovalPolicy { policy: ChildRemovalPolicy },
+    CreateWorkspace { name: String, output: OutputId },
+    RenameWorkspace { id: WorkspaceId, name: String },
+    ReorderWorkspace { id: WorkspaceId, index: usize },
+    ActivateWorkspace { id: WorkspaceId },
+    RemoveWorkspace { id: WorkspaceId },
+    AddOutput { output: OutputInfo },
+    UpdateOutput { id: OutputId, update: OutputUpdate },
+    RemoveOutput { id: OutputId },
+    ApplyLayout { workspace: WorkspaceId, layout: Layout, gaps: Gaps },
+    /// Operations staged in one `Transaction` and committed together, or dropped with
+    /// `abandoned` if the transaction's closure returned that error instead.
+    Transaction {
+        ops: Vec<JournalOp>,
+        #[cfg_attr(feature = "serde", serde(default, skip_serializing_if = "Option::is_none"))]
+        abandoned: Option<RegistryError>,
+    },
+}
+
+impl JournalOp {
+    /// Performs the operation and returns its events, exactly as the original call did.
+    pub fn apply(&self, reg: &mut Registry) -> Result<Vec<RegistryEvent>, RegistryError> {
+        match self {
+            JournalOp::Insert { dk, sk } => reg.insert_window(*dk, *sk).map(|(_, events)| events),
+            JournalOp::Update { id, update } => reg.update_window(*id, update.clone()),
+            JournalOp::Map { id } => reg.on_map(*id),
+            JournalOp::Unmap { id } => reg.on_unmap(*id),
+            JournalOp::Remove { id } => reg.remove_window(*id).map(|(_, events)| events),
+            JournalOp::Raise { id } => reg.raise(*id),
+            JournalOp::Lower { id } => reg.lower(*id),
+            JournalOp::RaiseAbove { id, sibling } => reg.raise_above(*id, *sibling),
+            JournalOp::LowerBelow { id, sibling } => reg.lower_below(*id, *sibling),
+            JournalOp::SetChildRemovalPolicy { policy } => {
+                reg.set_child_removal_policy(*policy);
+                Ok(Vec::new())
+            }
+            JournalOp::CreateWorkspace { name, output } => {
+                reg.create_workspace(name.clone(), *output).map(|(_, events)| events)
+            }
+            JournalOp::RenameWorkspace { id, name } => reg.rename_workspace(*id, name.clone()),
+            JournalOp::ReorderWorkspace { id, index } => reg.reorder_workspace(*id, *index),
+            JournalOp::ActivateWorkspace { id } => reg.activate_workspace(*id),
+            JournalOp::RemoveWorkspace { id } => reg.remove_workspace(*id),
+            JournalOp::AddOutput { output } => reg.add_output(output.clone()),
+            JournalOp::UpdateOutput { id, update } => reg.update_output(*id, update.clone()),
+            JournalOp::RemoveOutput { id } => reg.remove_output(*id),
+            JournalOp::ApplyLayout { workspace, layout, gaps } => reg.apply_layout(*workspace, *layout, *gaps),
+            JournalOp::Transaction { ops, abandoned } => {
+                let mut tx = reg.transaction();
+                for op in ops {
+                    // The transaction keeps the first error and reports it from `commit`.
+                    let _ = tx.apply(op);
+                }
+                match abandoned {
+                    Some(err) => Err(err.clone()),
+                    None => tx.commit(),
+                }
+            }
+        }
+    }
+}
+
+/// Receives every mutation applied through a journaled `SharedRegistry`, in application order,
+/// together with its outcome. Called while the registry write lock is held.
+pub trait JournalSink: Send {
+    fn record(&mut self, op: &JournalOp, outcome: Result<&[RegistryEvent], &RegistryError>);
+}
+
+#[derive(Clone)]
+pub(crate) struct JournalHandle(Arc<Mutex<dyn JournalSink>>);
+
+impl JournalHandle {
+    pub(crate) fn new(sink: impl JournalSink + 'static) -> Self {
+        Self(Arc::new(Mutex::new(sink)))
+    }
+
+    pub(crate) fn record(&self, op: JournalOp, outcome: Result<&[RegistryEvent], &RegistryError>) {
+        self.0.lock().expect("journal lock poisoned").record(&op, outcome);
+    }
+}
+
+impl std::fmt::Debug for JournalHandle {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str("JournalHandle")
+    }
+}
+
+#[cfg(feature = "journal")]
+pub use file::{replay, replay_into, JournalEntry, JournalError, JournalOutcome, JournalWriter, JOURNAL_VERSION};
+
+#[cfg(feature = "journal")]
+mod file {
+    use std::{
+        fmt::{self, Display, Formatter},
+        fs::File,
+        io::{self, BufRead, BufWriter, Write},
+        path::Path,
+    };
+
+    use serde::{Deserialize, Serialize};
+
+    use super::{JournalOp, JournalSink};
+    use crate::{with_raw_keys, Registry, RegistryError, RegistryEvent};
+
+    pub const JOURNAL_VERSION: u32 = 1;
+    const JOURNAL_FORMAT: &str = "window_registry.journal";
+
+    #[derive(Serialize, Deserialize)]
+    struct Header {
+        format: String,
+        version: u32,
+    }
+
+    #[derive(Debug, Clone, Serialize, Deserialize)]
+    #[serde(rename_all = "snake_case")]
+    pub enum JournalOutcome {
+        Events(Vec<RegistryEvent>),
+        Error(RegistryError),
+    }
+
+    /// One JSON line of a journal file after the header.
+    #[derive(Debug, Clone, Serialize, Deserialize)]
+    pub struct JournalEntry {
+        pub seq: u64,
+        pub op: JournalOp,
+        pub outcome: JournalOutcome,
+    }
+
+    /// Writes a JSON-lines journal: a `{"format", "version"}` header, then one `JournalEntry`
+    /// per mutation. Keys are written as opaque tokens, which replay consistently.
+    ///
+    /// Write errors do not fail the mutation being recorded; the first one is kept and later
+    /// entries are dropped. Check `error()` or `flush()`.
+    #[derive(Debug)]
+    pub struct JournalWriter<W: Write> {
+        out: W,
+        next_seq: u64,
+        failed: Option<io::Error>,
+    }
+
+    impl JournalWriter<BufWriter<File>> {
+        pub fn create<P: AsRef<Path>>(path: P) -> io::Result<Self> {
+            Self::new(BufWriter::new(File::create(path)?))
+        }
+    }
+
+    impl<W: Write> JournalWriter<W> {
+        pub fn new(mut out: W) -> io::Result<Self> {
+            let header = Header { format: JOURNAL_FORMAT.to_string(), version: JOURNAL_VERSION };
+            write_line(&mut out, &header)?;
+            Ok(Self { out, next_seq: 0, failed: None })
+        }
+
+        /// Applies `op` to `reg` and records it; for registries not behind a `SharedRegistry`.
+        pub fn apply(&mut self, reg: &mut Registry, op: JournalOp) -> Result<Vec<RegistryEvent>, RegistryError> {
+            let result = op.apply(reg);
+            self.write_entry(&op, result.as_deref());
+            result
+        }
+
+        pub fn error(&self) -> Option<&io::Error> {
+            self.failed.as_ref()
+        }
+
+        pub fn flush(&mut self) -> io::Result<()> {
+            if let Some(err) = self.failed.take() {
+                return Err(err);
+            }
+            self.out.flush()
+        }
+
+        pub fn into_inner(self) -> W {
+            self.out
+        }
+
+        fn write_entry(&mut self, op: &JournalOp, outcome: Result<&[RegistryEvent], &RegistryError>) {
+            if self.failed.is_some() {
+                return;
+            }
+            let entry = JournalEntry {
+                seq: self.next_seq,
+                op: op.clone(),
+                outcome: match outcome {
+                    Ok(events) => JournalOutcome::Events(events.to_vec()),
+                    Err(err) => JournalOutcome::Error(err.clone()),
+                },
+            };
+            self.next_seq += 1;
+            if let Err(err) = write_line(&mut self.out, &entry) {
+                self.failed = Some(err);
+            }
+        }
+    }
+
+    impl<W: Write + Send> JournalSink for JournalWriter<W> {
+        fn record(&mut self, op: &JournalOp, outcome: Result<&[RegistryEvent], &RegistryError>) {
+            self.write_entry(op, outcome);
+        }
+    }
+
+    fn write_line<W: Write, T: Serialize>(out: &mut W, value: &T) -> io::Result<()> {
+        serde_json::to_writer(&mut *out, value)?;
+        out.write_all(b"\n")
+    }
+
+    #[derive(Debug)]
+    pub enum JournalError {
+        Io(io::Error),
+        Parse { line: usize, message: String },
+        UnsupportedFormat { format: String, version: u32 },
+        /// Replaying entry `seq` produced a different outcome than recorded (both as JSON).
+        Mismatch { seq: u64, expected: String, actual: String },
+    }
+
+    impl Display for JournalError {
+        fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+            match self {
+                JournalError::Io(err) => write!(f, "journal i/o error: {err}"),
+                JournalError::Parse { line, message } => write!(f, "journal line {line}: {message}"),
+                JournalError::UnsupportedFormat { format, version } => {
+                    write!(f, "unsupported journal {format:?} version {version}")
+                }
+                JournalError::Mismatch { seq, expected, actual } => {
+                    write!(f, "entry {seq} diverged: recorded {expected}, replayed {actual}")
+                }
+            }
+        }
+    }
+
+    impl std::error::Error for JournalError {}
+
+    impl From<io::Error> for JournalError {
+        fn from(err: io::Error) -> Self {
+            JournalError::Io(err)
+        }
+    }
+
+    /// Rebuilds a registry from a journal, starting from `Registry::new()`.
+    pub fn replay<R: BufRead>(reader: R) -> Result<Registry, JournalError> {
+        replay_into(Registry::new(), reader)
+    }
+
+    /// Replays a journal on top of `reg`, which should be configured like the recorded one
+    /// (child removal policy, focus fallback chain, placement store, rules). Stops at the first
+    /// entry whose replayed outcome differs from the recorded one.
+    pub fn replay_into<R: BufRead>(mut reg: Registry, reader: R) -> Result<Registry, JournalError> {
+        let mut lines = reader.lines().enumerate();
+
+        let header: Header = match lines.next() {
+            Some((n, line)) => parse(n, &line?)?,
+            None => return Err(JournalError::Parse { line: 1, message: "missing header".to_string() }),
+        };
+        if header.format != JOURNAL_FORMAT || header.version != JOURNAL_VERSION {
+            return Err(JournalError::UnsupportedFormat { format: header.format, version: header.version });
+        }
+
+        for (n, line) in lines {
+            let line = line?;
+            if line.trim().is_empty() {
+                continue;
+            }
+            let entry: JournalEntry = parse(n, &line)?;
+            let actual = match entry.op.apply(&mut reg) {
+                Ok(events) => JournalOutcome::Events(events),
+                Err(err) => JournalOutcome::Error(err),
+            };
+
+            // Keys hold the recorded tokens, so both sides are written back raw.
+            let (expected, actual) = with_raw_keys(|| (to_json(&entry.outcome), to_json(&actual)));
+            if expected != actual {
+                return Err(JournalError::Mismatch { seq: entry.seq, expected, actual });
+            }
+        }
+        Ok(reg)
+    }
+
+    fn parse<'a, T: Deserialize<'a>>(index: usize, line: &'a str) -> Result<T, JournalError> {
+        serde_json::from_str(line).map_err(|err| JournalError::Parse { line: index + 1, message: err.to_string() })
+    }
+
+    fn to_json<T: Serialize>(value: &T) -> String {
+        serde_json::to_string(value).expect("journal values serialize")
+    }
+}
@@ -0,0 +1,128 @@
+use crate::{
+    RegistryError,
+    WindowGeometry,
+};
+
+/// How `Registry::apply_layout` tiles the windows of a workspace, in the order they joined it.
+#[derive(Debug, Copy, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum Layout {
+    /// The first `masters` windows are stacked in a column taking `ratio` of the width on the
+    /// left, the others in a column on the right. A column with no windows leaves its width to
+    /// the other one.
+    MasterStack { masters: usize, ratio: f64 },
+    /// Side-by-side columns of equal width.
+    Columns,
+    /// Rows of up to ⌈√n⌉ windows; the windows of a shorter last row share its whole width.
+    Grid,
+    /// Every window covers the whole area.
+    Monocle,
+}
+
+impl Default for Layout {
+    fn default() -> Self {
+        Layout::MasterStack { masters: 1, ratio: 0.5 }
+    }
+}
+
+/// Space between neighbouring tiles (`inner`) and between the tiles and the edge of the area
+/// (`outer`).
+#[derive(Debug, Copy, Clone, Eq, PartialEq, Default)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Gaps {
+    pub inner: u32,
+    pub outer: u32,
+}
+
+impl Layout {
+    /// One tile per window inside `area`, in window order. Sizes are split to the pixel, earlier
+    /// tiles taking the remainder; tiles that don't fit shrink to zero.
+    pub fn arrange(
+        &self,
+        area: WindowGeometry,
+        gaps: Gaps,
+        count: usize,
+    ) -> Result<Vec<WindowGeometry>, RegistryError> {
+        if let Layout::MasterStack { ratio, .. } = *self {
+            if !(ratio > 0.0 && ratio < 1.0) {
+                return Err(RegistryError::InvalidLayoutRatio { ratio });
+            }
+        }
+        if count == 0 {
+            return Ok(Vec::new());
+        }
+
+        let area = shrink(area, i64::from(gaps.outer));
+        let gap = i64::from(gaps.inner);
+        let tiles = match *self {
+            Layout::MasterStack { masters, ratio } => {
+                let masters = masters.min(count);
+                if masters == 0 || masters == count {
+                    rows(area, count, gap)
+                } else {
+                    let room = (i64::from(area.width) - gap).max(0);
+                    let master_width = (room as f64 * ratio).round() as i64;
+                    let master = WindowGeometry { width: fit(master_width), ..area };
+                    let stack = WindowGeometry {
+                        x: fit(i64::from(area.x) + master_width + gap),
+                        width: fit(room - master_width),
+                        ..area
+                    };
+                    let mut tiles = rows(master, masters, gap);
+                    tiles.extend(rows(stack, count - masters, gap));
+                    tiles
+                }
+            }
+            Layout::Columns => columns(area, count, gap),
+            Layout::Grid => {
+                let per_row = (1..count).find(|cols| cols * cols >= count).unwrap_or(count);
+                rows(area, count.div_ceil(per_row), gap)
+                    .into_iter()
+                    .enumerate()
+                    .flat_map(|(row, line)| columns(line, per_row.min(count - row * per_row), gap))
+                    .collect()
+            }
+            Layout::Monocle => vec![area; count],
+        };
+        Ok(tiles)
+    }
+}
+
+fn fit(value: i64) -> i32 {
+    value.clamp(i64::from(i32::MIN), i64::from(i32::MAX)) as i32
+}
+
+fn shrink(area: WindowGeometry, by: i64) -> WindowGeometry {
+    WindowGeometry {
+        x: fit(i64::from(area.x) + by),
+        y: fit(i64::from(area.y) + by),
+        width: fit((i64::from(area.width) - 2 * by).max(0)),
+        height: fit((i64::from(area.height) - 2 * by).max(0)),
+    }
+}
+
+fn columns(area: WindowGeometry, n: usize, gap: i64) -> Vec<WindowGeometry> {
+    split(area.x, area.width, n, gap)
+        .map(|(x, width)| WindowGeometry { x, width, ..area })
+        .collect()
+}
+
+fn rows(area: WindowGeometry, n: usize, gap: i64) -> Vec<WindowGeometry> {
+    split(area.y, area.height, n, gap)
+        .map(|(y, height)| WindowGeometry { y, height, ..area })
+        .collect()
+}
+
+/// Cuts `len` from `start` into `n` spans with `gap` between them, as `(start, len)` pairs.
+fn split(start: i32, len: i32, n: usize, gap: i64) -> impl Iterator<Item = (i32, i32)> {
+    let n = n as i64;
+    let room = (i64::from(len) - gap * (n - 1)).max(0);
+    let (base, extra) = (room / n, room % n);
+    let mut pos = i64::from(start);
+    (0..n).map(move |i| {
+        let size = base + i64::from(i < extra);
+        let span = (fit(pos), fit(size));
+        pos += size + gap;
+        span
+    })
+}
@@ -9,27 +9,70 @@ mod events;
 mod error;
 mod shared;
 mod event_queue;
+mod event_filter;
+mod mirror;
+mod persistence;
+mod rules;
+mod query;
+mod layout;
+mod journal;
 mod handles;
 mod weston;
 mod weston_adapter;
+#[cfg(feature = "ipc")]
+mod ipc;
 
 // Public re-exports
-pub use ids::{WindowId, DesktopKey, SurfaceKey};
+pub use ids::{WindowId, DesktopKey, ParseWindowIdError, SurfaceKey};
+#[cfg(feature = "serde")]
+pub use ids::with_raw_keys;
 pub use model::{
+	ChildRemovalPolicy,
+	Insets,
 	LifecycleState,
 	OutputId,
+	OutputInfo,
+	OutputTransform,
+	OutputUpdate,
+	StackLayer,
 	WindowGeometry,
 	WindowInfo,
 	WindowRecord,
 	WindowState,
 	WindowUpdate,
 	WorkspaceId,
+	WorkspaceInfo,
+};
+pub use registry::{FocusFallback, Slot, Registry, Transaction};
+pub use events::{
+	ChangeField,
+	EventEnvelope,
+	EventKind,
+	EventOperation,
+	RegistryEvent,
+	WindowChange,
+	WindowChanges,
 };
-pub use registry::{Slot, Registry};
-pub use events::{RegistryEvent, WindowChange, WindowChanges};
 pub use error::RegistryError;
 pub use shared::SharedRegistry;
-pub use event_queue::{RegistryEventQueue, RegistryEventReceiver};
+pub use event_queue::{BatchTicket, OverflowPolicy, RegistryEventQueue, RegistryEventReceiver, SubscribeOptions};
+pub use event_filter::EventFilter;
+pub use mirror::RegistryMirror;
+pub use persistence::{Placement, PlacementStore};
+pub use rules::{RuleActions, RuleMatch, WindowRule};
+pub use query::{Query, QueryParseError};
+pub use layout::{Gaps, Layout};
+pub use journal::{JournalOp, JournalSink};
+#[cfg(feature = "journal")]
+pub use journal::{
+	replay,
+	replay_into,
+	JournalEntry,
+	JournalError,
+	JournalOutcome,
+	JournalWriter,
+	JOURNAL_VERSION,
+};
 pub use handles::CompositorHandles;
 pub use weston::on_new_desktop_surface;
 pub use weston::on_new_desktop_surface_with_keys;
@@ -46,3 +89,5 @@ pub use weston::{
 	weston_handle_unmap,
 };
 pub use weston_adapter::{FakeWeston, RegistryAdapter, WestonAdapter, WestonEvent};
+#[cfg(feature = "ipc")]
+pub use ipc::{IpcClient, IpcRequest, IpcResponse, IpcServer, default_socket_path};
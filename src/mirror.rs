@@ -0,0 +1,168 @@
+use std::collections::BTreeMap;
+
+use crate::{
+    ChangeField,
+    LifecycleState,
+    RegistryError,
+    RegistryEvent,
+    StackLayer,
+    WindowChange,
+    WindowChanges,
+    WindowId,
+    WindowInfo,
+    WindowState,
+};
+
+/// Read-only replica of a registry, kept in sync by applying its event stream.
+///
+/// Start from `snapshot_all` (e.g. `SharedRegistry::subscribe_with_snapshot`) and feed every
+/// received event to `apply`. Each `WindowChanged` field's `old` value is checked against the
+/// local copy; a mismatch means events were lost or reordered and is reported as
+/// `RegistryError::MirrorDiverged` without touching the mirror. Call `reset` with a fresh
+/// snapshot to recover.
+#[derive(Debug, Clone, Default)]
+pub struct RegistryMirror {
+    /// Keyed by slot index so iteration matches `Registry::snapshot_all`.
+    windows: BTreeMap<u32, WindowInfo>,
+}
+
+impl RegistryMirror {
+    pub fn new(snapshot: Vec<WindowInfo>) -> Self {
+        let mut mirror = Self::default();
+        mirror.reset(snapshot);
+        mirror
+    }
+
+    pub fn reset(&mut self, snapshot: Vec<WindowInfo>) {
+        self.windows = snapshot.into_iter().map(|w| (w.id.index, w)).collect();
+    }
+
+    pub fn get(&self, id: WindowId) -> Option<&WindowInfo> {
+        self.windows.get(&id.index).filter(|w| w.id == id)
+    }
+
+    pub fn windows(&self) -> impl Iterator<Item = &WindowInfo> + '_ {
+        self.windows.values()
+    }
+
+    pub fn snapshot_all(&self) -> Vec<WindowInfo> {
+        self.windows.values().cloned().collect()
+    }
+
+    pub fn len(&self) -> usize {
+        self.windows.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.windows.is_empty()
+    }
+
+    pub fn apply(&mut self, event: &RegistryEvent) -> Result<(), RegistryError> {
+        match event {
+            RegistryEvent::WindowCreated { id, dk, sk } => {
+                if self.windows.contains_key(&id.index) {
+                    return Err(RegistryError::MirrorDiverged { id: *id, field: None });
+                }
+                // Same defaults as `Registry::insert_window`: on top of the unplaced normal stack.
+                let unplaced = self.windows.values().filter(|w| w.output.is_none() && w.layer == StackLayer::Normal);
+                let stack_index = unplaced.count() as i32;
+                let info = WindowInfo {
+                    id: *id,
+                    dk: *dk,
+                    sk: *sk,
+                    lifecycle: LifecycleState::Created,
+                    geometry: None,
+                    state: WindowState::default(),
+                    is_focused: false,
+                    workspace: None,
+                    output: None,
+                    layer: StackLayer::Normal,
+                    stack_index,
+                    parent_id: None,
+                    children: Vec::new(),
+                    modal: false,
+                    floating: false,
+                    title: None,
+                    app_id: None,
+                };
+                self.windows.insert(id.index, info);
+            }
+            RegistryEvent::WindowChanged { id, changes } => {
+                let info = self
+                    .windows
+                    .get_mut(&id.index)
+                    .filter(|w| w.id == *id)
+                    .ok_or(RegistryError::MirrorDiverged { id: *id, field: None })?;
+                if let Some(field) = first_mismatch(info, changes) {
+                    return Err(RegistryError::MirrorDiverged { id: *id, field: Some(field) });
+                }
+                apply_changes(info, changes);
+            }
+            RegistryEvent::WindowDestroyed { id } => {
+                if self.get(*id).is_none() {
+                    return Err(RegistryError::MirrorDiverged { id: *id, field: None });
+                }
+                self.windows.remove(&id.index);
+            }
+            // Workspaces and outputs are not mirrored.
+            _ => {}
+        }
+        Ok(())
+    }
+
+    /// Applies `events` in order, stopping at the first divergence.
+    pub fn apply_all<'a>(
+        &mut self,
+        events: impl IntoIterator<Item = &'a RegistryEvent>,
+    ) -> Result<(), RegistryError> {
+        events.into_iter().try_for_each(|event| self.apply(event))
+    }
+}
+
+fn first_mismatch(info: &WindowInfo, c: &WindowChanges) -> Option<ChangeField> {
+    fn differs<T: PartialEq>(current: &T, change: &Option<WindowChange<T>>) -> bool {
+        change.as_ref().is_some_and(|change| change.old != *current)
+    }
+
+    [
+        (differs(&info.lifecycle, &c.lifecycle), ChangeField::Lifecycle),
+        (differs(&info.geometry, &c.geometry), ChangeField::Geometry),
+        (differs(&info.state, &c.state), ChangeField::State),
+        (differs(&info.is_focused, &c.is_focused), ChangeField::IsFocused),
+        (differs(&info.workspace, &c.workspace), ChangeField::Workspace),
+        (differs(&info.output, &c.output), ChangeField::Output),
+        (differs(&info.layer, &c.layer), ChangeField::Layer),
+        (differs(&info.stack_index, &c.stack_index), ChangeField::StackIndex),
+        (differs(&info.parent_id, &c.parent_id), ChangeField::ParentId),
+        (differs(&info.children, &c.children), ChangeField::Children),
+        (differs(&info.modal, &c.modal), ChangeField::Modal),
+        (differs(&info.floating, &c.floating), ChangeField::Floating),
+        (differs(&info.title, &c.title), ChangeField::Title),
+        (differs(&info.app_id, &c.app_id), ChangeField::AppId),
+    ]
+    .into_iter()
+    .find_map(|(differs, field)| differs.then_some(field))
+}
+
+fn apply_changes(info: &mut WindowInfo, c: &WindowChanges) {
+    fn set<T: Clone>(current: &mut T, change: &Option<WindowChange<T>>) {
+        if let Some(change) = change {
+            *current = change.new.clone();
+        }
+    }
+
+    set(&mut info.lifecycle, &c.lifecycle);
+    set(&mut info.geometry, &c.geometry);
+    set(&mut info.state, &c.state);
+    set(&mut info.is_focused, &c.is_focused);
+    set(&mut info.workspace, &c.workspace);
+    set(&mut info.output, &c.output);
+    set(&mut info.layer, &c.layer);
+    set(&mut info.stack_index, &c.stack_index);
+    set(&mut info.parent_id, &c.parent_id);
+    set(&mut info.children, &c.children);
+    set(&mut info.modal, &c.modal);
+    set(&mut info.floating, &c.floating);
+    set(&mut info.title, &c.title);
+    set(&mut info.app_id, &c.app_id);
+}
@@ -9,6 +9,7 @@ use crate::{
 };
 
 #[derive(Debug, Copy, Clone, Eq, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub enum LifecycleState {
     Created,   // seen but not mapped
     Mapped,    // visible / participating in layout
@@ -16,7 +17,21 @@ pub enum LifecycleState {
     Destroyed, // terminal (you may not need to store this if you remove records)
 }
 
+/// What `Registry::remove_window` does with the children of the removed window.
+#[derive(Debug, Copy, Clone, Eq, PartialEq, Default)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum ChildRemovalPolicy {
+    /// Children stay alive with `parent_id` cleared.
+    #[default]
+    Orphan,
+    /// Children are handed to the removed window's parent (or orphaned if there is none).
+    Reparent,
+    /// The whole transient subtree is destroyed along with the window.
+    DestroySubtree,
+}
+
 #[derive(Debug, Copy, Clone, Eq, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct WindowGeometry {
     pub x: i32,
     pub y: i32,
@@ -31,6 +46,7 @@ impl Default for WindowGeometry {
 }
 
 #[derive(Debug, Copy, Clone, Eq, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct WindowState {
     pub minimized: bool,
     pub maximized: bool,
@@ -43,13 +59,132 @@ impl Default for WindowState {
     }
 }
 
+/// Stacking layers as layer-shell defines them, back to front. Every output has one stack per
+/// layer, and a layer always covers the ones before it.
+#[derive(Debug, Copy, Clone, Eq, PartialEq, Hash, PartialOrd, Ord, Default)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum StackLayer {
+    Background,
+    Bottom,
+    #[default]
+    Normal,
+    Top,
+    Overlay,
+}
+
+impl StackLayer {
+    /// Every layer, back to front.
+    pub const ALL: [StackLayer; 5] =
+        [StackLayer::Background, StackLayer::Bottom, StackLayer::Normal, StackLayer::Top, StackLayer::Overlay];
+}
+
 #[derive(Debug, Copy, Clone, Eq, PartialEq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct WorkspaceId(pub u32);
 
 #[derive(Debug, Copy, Clone, Eq, PartialEq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct OutputId(pub u32);
 
-#[derive(Debug)]
+#[derive(Debug, Copy, Clone, Eq, PartialEq, Default)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum OutputTransform {
+    #[default]
+    Normal,
+    Rotate90,
+    Rotate180,
+    Rotate270,
+    Flipped,
+    Flipped90,
+    Flipped180,
+    Flipped270,
+}
+
+/// Space kept clear along an output's edges, e.g. the exclusive zones of layer-shell panels.
+#[derive(Debug, Copy, Clone, Eq, PartialEq, Default)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Insets {
+    pub top: u32,
+    pub right: u32,
+    pub bottom: u32,
+    pub left: u32,
+}
+
+/// An output as registered with `Registry::add_output`.
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct OutputInfo {
+    pub id: OutputId,
+    /// Connector name, e.g. `HDMI-A-1`; unique among outputs.
+    pub name: String,
+    /// Position and size in the global logical coordinate space.
+    pub geometry: WindowGeometry,
+    pub scale: f64,
+    pub transform: OutputTransform,
+    pub enabled: bool,
+    /// Kept clear of tiled windows; see `usable_area`.
+    pub reserved: Insets,
+}
+
+impl OutputInfo {
+    /// An enabled, untransformed output at scale 1.
+    pub fn new(id: OutputId, name: impl Into<String>, geometry: WindowGeometry) -> Self {
+        Self {
+            id,
+            name: name.into(),
+            geometry,
+            scale: 1.0,
+            transform: OutputTransform::Normal,
+            enabled: true,
+            reserved: Insets::default(),
+        }
+    }
+
+    /// `geometry` minus `reserved`; empty if the insets cover the whole output.
+    pub fn usable_area(&self) -> WindowGeometry {
+        let (geom, reserved) = (self.geometry, self.reserved);
+        let fit = |value: i64| value.clamp(i64::from(i32::MIN), i64::from(i32::MAX)) as i32;
+        let span = |size: i32, before: u32, after: u32| {
+            let size = i64::from(size.max(0));
+            let before = i64::from(before).min(size);
+            (before, (size - before - i64::from(after)).max(0))
+        };
+        let (left, width) = span(geom.width, reserved.left, reserved.right);
+        let (top, height) = span(geom.height, reserved.top, reserved.bottom);
+        WindowGeometry {
+            x: fit(i64::from(geom.x) + left),
+            y: fit(i64::from(geom.y) + top),
+            width: fit(width),
+            height: fit(height),
+        }
+    }
+}
+
+/// Fields to change with `Registry::update_output`; `None` leaves a field as it is.
+#[derive(Debug, Clone, PartialEq, Default)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct OutputUpdate {
+    pub name: Option<String>,
+    pub geometry: Option<WindowGeometry>,
+    pub scale: Option<f64>,
+    pub transform: Option<OutputTransform>,
+    pub enabled: Option<bool>,
+    pub reserved: Option<Insets>,
+}
+
+/// A workspace as listed by `Registry::workspaces`.
+#[derive(Debug, Clone, Eq, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct WorkspaceInfo {
+    pub id: WorkspaceId,
+    pub name: String,
+    pub output: OutputId,
+    /// Whether this is the workspace shown on `output`.
+    pub active: bool,
+}
+
+#[derive(Debug, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct WindowRecord {
     pub id: WindowId,
     pub dk: DesktopKey,
@@ -62,9 +197,16 @@ pub struct WindowRecord {
     pub is_focused: bool,
     pub workspace: Option<WorkspaceId>,
     pub output: Option<OutputId>,
+    pub layer: StackLayer,
+    /// Position in the stack of the window's output and layer, 0 at the back. Maintained by
+    /// the registry; restack with `raise` / `lower` / `raise_above` / `lower_below`.
     pub stack_index: i32,
     pub parent_id: Option<WindowId>,
     pub children: Vec<WindowId>,
+    /// A modal transient blocks input to its parent while mapped; requires `parent_id`.
+    pub modal: bool,
+    /// Floating windows keep their own geometry; tiling layouts skip them.
+    pub floating: bool,
 
     // later:
     pub title: Option<String>,
@@ -72,6 +214,7 @@ pub struct WindowRecord {
 }
 
 #[derive(Debug, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct WindowInfo {
     pub id: WindowId,
     pub dk: DesktopKey,
@@ -84,25 +227,32 @@ pub struct WindowInfo {
     pub is_focused: bool,
     pub workspace: Option<WorkspaceId>,
     pub output: Option<OutputId>,
+    pub layer: StackLayer,
     pub stack_index: i32,
     pub parent_id: Option<WindowId>,
     pub children: Vec<WindowId>,
+    pub modal: bool,
+    pub floating: bool,
 
     pub title: Option<String>,
     pub app_id: Option<String>,
 }
 
 #[derive(Debug, Clone, Default)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct WindowUpdate {
     pub geometry: Option<Option<WindowGeometry>>,
     pub state: Option<WindowState>,
     pub is_focused: Option<bool>,
     pub workspace: Option<Option<WorkspaceId>>,
     pub output: Option<Option<OutputId>>,
-    pub stack_index: Option<i32>,
+    /// Moves the window to the top of the new layer's stack.
+    pub layer: Option<StackLayer>,
     pub parent_id: Option<Option<WindowId>>,
     pub add_children: Vec<WindowId>,
     pub remove_children: Vec<WindowId>,
+    pub modal: Option<bool>,
+    pub floating: Option<bool>,
     pub title: Option<Option<String>>,
     pub app_id: Option<Option<String>>,
 }
@@ -119,9 +269,12 @@ impl From<&WindowRecord> for WindowInfo {
             is_focused: r.is_focused,
             workspace: r.workspace,
             output: r.output,
+            layer: r.layer,
             stack_index: r.stack_index,
             parent_id: r.parent_id,
             children: r.children.clone(),
+            modal: r.modal,
+            floating: r.floating,
             title: r.title.clone(),
             app_id: r.app_id.clone(),
         }
@@ -0,0 +1,75 @@
+use std::collections::HashMap;
+
+use crate::{
+    OutputId,
+    WindowGeometry,
+    WindowRecord,
+    WindowState,
+    WorkspaceId,
+};
+
+/// Where a window was last seen: the fields restored for the next window with its `app_id`.
+#[derive(Debug, Copy, Clone, Eq, PartialEq, Default)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Placement {
+    pub geometry: Option<WindowGeometry>,
+    pub state: WindowState,
+    pub workspace: Option<WorkspaceId>,
+    pub output: Option<OutputId>,
+}
+
+impl From<&WindowRecord> for Placement {
+    fn from(r: &WindowRecord) -> Self {
+        Self {
+            geometry: r.geometry,
+            state: r.state,
+            workspace: r.workspace,
+            output: r.output,
+        }
+    }
+}
+
+/// Last known `Placement` per `app_id`, kept across window lifetimes.
+///
+/// With the `serde` feature the store serializes as a plain `app_id -> placement` map, so it
+/// can be written out at shutdown and handed back to `Registry::set_placement_store` on start.
+#[derive(Debug, Clone, Default, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "serde", serde(transparent))]
+pub struct PlacementStore {
+    placements: HashMap<String, Placement>,
+}
+
+impl PlacementStore {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn get(&self, app_id: &str) -> Option<&Placement> {
+        self.placements.get(app_id)
+    }
+
+    pub fn insert(&mut self, app_id: impl Into<String>, placement: Placement) -> Option<Placement> {
+        self.placements.insert(app_id.into(), placement)
+    }
+
+    pub fn remove(&mut self, app_id: &str) -> Option<Placement> {
+        self.placements.remove(app_id)
+    }
+
+    pub fn iter(&self) -> impl Iterator<Item = (&str, &Placement)> + '_ {
+        self.placements.iter().map(|(app_id, placement)| (app_id.as_str(), placement))
+    }
+
+    pub fn len(&self) -> usize {
+        self.placements.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.placements.is_empty()
+    }
+
+    pub fn clear(&mut self) {
+        self.placements.clear();
+    }
+}
@@ -0,0 +1,360 @@
+use std::{
+    fmt::{self, Display, Formatter},
+    str::FromStr,
+};
+
+use regex::Regex;
+
+use crate::{
+    LifecycleState,
+    OutputId,
+    WindowId,
+    WindowRecord,
+    WorkspaceId,
+};
+
+/// A predicate over windows, evaluated by `Registry::query` without cloning records.
+///
+/// Build it directly or parse the textual form:
+///
+/// ```text
+/// app_id="firefox" && workspace=3 && mapped
+/// (title~"^Open" || transient) && !minimized
+/// output=none || parent=4:1
+/// ```
+///
+/// Comparisons are `field=value` (also `==`), `field!=value` and `field~"regex"`. Fields:
+/// `app_id`, `title` (quoted strings or `none`), `workspace`, `output` (numbers or `none`), `id`,
+/// `parent` (`index:gen`, `parent` also `none`). Flags: `created`, `mapped`, `unmapped`,
+/// `focused`, `minimized`, `maximized`, `fullscreen`, `transient`, `modal`, `floating`. `!`
+/// binds tighter than `&&`, which binds tighter than `||`.
+#[derive(Debug, Clone)]
+pub enum Query {
+    AppId(Option<String>),
+    AppIdMatches(Regex),
+    Title(Option<String>),
+    TitleMatches(Regex),
+    Workspace(Option<WorkspaceId>),
+    Output(Option<OutputId>),
+    Id(WindowId),
+    Parent(Option<WindowId>),
+    Lifecycle(LifecycleState),
+    Focused,
+    Minimized,
+    Maximized,
+    Fullscreen,
+    Modal,
+    Floating,
+    Not(Box<Query>),
+    And(Box<Query>, Box<Query>),
+    Or(Box<Query>, Box<Query>),
+}
+
+impl Query {
+    pub fn parse(input: &str) -> Result<Self, QueryParseError> {
+        let mut parser = Parser { tokens: tokenize(input)?, pos: 0, end: input.len() };
+        let query = parser.or()?;
+        match parser.peek() {
+            None => Ok(query),
+            Some((at, token)) => Err(QueryParseError::new(*at, format!("unexpected {token}"))),
+        }
+    }
+
+    pub fn and(self, other: Query) -> Self {
+        Query::And(Box::new(self), Box::new(other))
+    }
+
+    pub fn or(self, other: Query) -> Self {
+        Query::Or(Box::new(self), Box::new(other))
+    }
+
+    pub fn matches(&self, rec: &WindowRecord) -> bool {
+        match self {
+            Query::AppId(app_id) => rec.app_id == *app_id,
+            Query::AppIdMatches(re) => rec.app_id.as_deref().is_some_and(|app_id| re.is_match(app_id)),
+            Query::Title(title) => rec.title == *title,
+            Query::TitleMatches(re) => rec.title.as_deref().is_some_and(|title| re.is_match(title)),
+            Query::Workspace(workspace) => rec.workspace == *workspace,
+            Query::Output(output) => rec.output == *output,
+            Query::Id(id) => rec.id == *id,
+            Query::Parent(parent) => rec.parent_id == *parent,
+            Query::Lifecycle(lifecycle) => rec.lifecycle == *lifecycle,
+            Query::Focused => rec.is_focused,
+            Query::Minimized => rec.state.minimized,
+            Query::Maximized => rec.state.maximized,
+            Query::Fullscreen => rec.state.fullscreen,
+            Query::Modal => rec.modal,
+            Query::Floating => rec.floating,
+            Query::Not(inner) => !inner.matches(rec),
+            Query::And(a, b) => a.matches(rec) && b.matches(rec),
+            Query::Or(a, b) => a.matches(rec) || b.matches(rec),
+        }
+    }
+}
+
+impl std::ops::Not for Query {
+    type Output = Query;
+
+    fn not(self) -> Query {
+        Query::Not(Box::new(self))
+    }
+}
+
+impl FromStr for Query {
+    type Err = QueryParseError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        Query::parse(s)
+    }
+}
+
+#[derive(Debug, Clone, Eq, PartialEq)]
+pub struct QueryParseError {
+    position: usize,
+    message: String,
+}
+
+impl QueryParseError {
+    fn new(position: usize, message: impl Into<String>) -> Self {
+        Self { position, message: message.into() }
+    }
+
+    /// Byte offset into the query text.
+    pub fn position(&self) -> usize {
+        self.position
+    }
+}
+
+impl Display for QueryParseError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        write!(f, "invalid query at {}: {}", self.position, self.message)
+    }
+}
+
+impl std::error::Error for QueryParseError {}
+
+#[derive(Debug, Clone, PartialEq)]
+enum Token {
+    /// Field names, flags, numbers, `none` and `index:gen` ids.
+    Word(String),
+    Str(String),
+    Eq,
+    NotEq,
+    Tilde,
+    Bang,
+    And,
+    Or,
+    Open,
+    Close,
+}
+
+impl Display for Token {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        match self {
+            Token::Word(word) => write!(f, "`{word}`"),
+            Token::Str(s) => write!(f, "{s:?}"),
+            Token::Eq => f.write_str("`=`"),
+            Token::NotEq => f.write_str("`!=`"),
+            Token::Tilde => f.write_str("`~`"),
+            Token::Bang => f.write_str("`!`"),
+            Token::And => f.write_str("`&&`"),
+            Token::Or => f.write_str("`||`"),
+            Token::Open => f.write_str("`(`"),
+            Token::Close => f.write_str("`)`"),
+        }
+    }
+}
+
+fn tokenize(input: &str) -> Result<Vec<(usize, Token)>, QueryParseError> {
+    let mut tokens = Vec::new();
+    let mut chars = input.char_indices().peekable();
+
+    while let Some((at, c)) = chars.next() {
+        let mut next_is = |expected: char| chars.next_if(|(_, c)| *c == expected).is_some();
+        let token = match c {
+            c if c.is_whitespace() => continue,
+            '(' => Token::Open,
+            ')' => Token::Close,
+            '~' => Token::Tilde,
+            '=' => {
+                next_is('=');
+                Token::Eq
+            }
+            '!' if next_is('=') => Token::NotEq,
+            '!' => Token::Bang,
+            '&' if next_is('&') => Token::And,
+            '|' if next_is('|') => Token::Or,
+            '"' => {
+                let mut s = String::new();
+                loop {
+                    match chars.next() {
+                        Some((_, '"')) => break,
+                        Some((_, '\\')) => match chars.next() {
+                            Some((_, escaped)) => s.push(escaped),
+                            None => return Err(QueryParseError::new(at, "unterminated string")),
+                        },
+                        Some((_, c)) => s.push(c),
+                        None => return Err(QueryParseError::new(at, "unterminated string")),
+                    }
+                }
+                Token::Str(s)
+            }
+            c if is_word_char(c) => {
+                let mut word = c.to_string();
+                while let Some((_, c)) = chars.next_if(|(_, c)| is_word_char(*c)) {
+                    word.push(c);
+                }
+                Token::Word(word)
+            }
+            other => return Err(QueryParseError::new(at, format!("unexpected character {other:?}"))),
+        };
+        tokens.push((at, token));
+    }
+    Ok(tokens)
+}
+
+fn is_word_char(c: char) -> bool {
+    c.is_ascii_alphanumeric() || c == '_' || c == ':'
+}
+
+struct Parser {
+    tokens: Vec<(usize, Token)>,
+    pos: usize,
+    /// Reported position for "unexpected end" errors.
+    end: usize,
+}
+
+impl Parser {
+    fn peek(&self) -> Option<&(usize, Token)> {
+        self.tokens.get(self.pos)
+    }
+
+    fn next(&mut self) -> Result<(usize, Token), QueryParseError> {
+        let token = self
+            .tokens
+            .get(self.pos)
+            .cloned()
+            .ok_or_else(|| QueryParseError::new(self.end, "unexpected end of query"))?;
+        self.pos += 1;
+        Ok(token)
+    }
+
+    fn eat(&mut self, expected: &Token) -> bool {
+        if self.peek().is_some_and(|(_, token)| token == expected) {
+            self.pos += 1;
+            true
+        } else {
+            false
+        }
+    }
+
+    fn or(&mut self) -> Result<Query, QueryParseError> {
+        let mut query = self.and()?;
+        while self.eat(&Token::Or) {
+            query = query.or(self.and()?);
+        }
+        Ok(query)
+    }
+
+    fn and(&mut self) -> Result<Query, QueryParseError> {
+        let mut query = self.unary()?;
+        while self.eat(&Token::And) {
+            query = query.and(self.unary()?);
+        }
+        Ok(query)
+    }
+
+    fn unary(&mut self) -> Result<Query, QueryParseError> {
+        let (at, token) = self.next()?;
+        match token {
+            Token::Bang => Ok(!self.unary()?),
+            Token::Open => {
+                let query = self.or()?;
+                match self.next()? {
+                    (_, Token::Close) => Ok(query),
+                    (at, other) => Err(QueryParseError::new(at, format!("expected `)`, found {other}"))),
+                }
+            }
+            Token::Word(word) => self.term(at, &word),
+            other => Err(QueryParseError::new(at, format!("expected a field or flag, found {other}"))),
+        }
+    }
+
+    fn term(&mut self, at: usize, word: &str) -> Result<Query, QueryParseError> {
+        let flag = match word {
+            "created" => Some(Query::Lifecycle(LifecycleState::Created)),
+            "mapped" => Some(Query::Lifecycle(LifecycleState::Mapped)),
+            "unmapped" => Some(Query::Lifecycle(LifecycleState::Unmapped)),
+            "focused" => Some(Query::Focused),
+            "minimized" => Some(Query::Minimized),
+            "maximized" => Some(Query::Maximized),
+            "fullscreen" => Some(Query::Fullscreen),
+            "transient" => Some(!Query::Parent(None)),
+            "modal" => Some(Query::Modal),
+            "floating" => Some(Query::Floating),
+            _ => None,
+        };
+        if let Some(flag) = flag {
+            return Ok(flag);
+        }
+
+        let (op_at, op) = self.next()?;
+        if op == Token::Tilde {
+            let (value_at, value) = self.next()?;
+            let Token::Str(pattern) = value else {
+                return Err(QueryParseError::new(value_at, "`~` needs a quoted pattern"));
+            };
+            let re = Regex::new(&pattern).map_err(|err| QueryParseError::new(value_at, err.to_string()))?;
+            return match word {
+                "app_id" => Ok(Query::AppIdMatches(re)),
+                "title" => Ok(Query::TitleMatches(re)),
+                _ => Err(QueryParseError::new(at, format!("`{word}` cannot be matched with `~`"))),
+            };
+        }
+        if op != Token::Eq && op != Token::NotEq {
+            return Err(QueryParseError::new(op_at, format!("expected `=`, `!=` or `~`, found {op}")));
+        }
+
+        let (value_at, value) = self.next()?;
+        let query = match word {
+            "app_id" => Query::AppId(string_value(value_at, value)?),
+            "title" => Query::Title(string_value(value_at, value)?),
+            "workspace" => Query::Workspace(number_value(value_at, value)?.map(WorkspaceId)),
+            "output" => Query::Output(number_value(value_at, value)?.map(OutputId)),
+            "id" => match id_value(value_at, value)? {
+                Some(id) => Query::Id(id),
+                None => return Err(QueryParseError::new(value_at, "`id` cannot be none")),
+            },
+            "parent" => Query::Parent(id_value(value_at, value)?),
+            _ => return Err(QueryParseError::new(at, format!("unknown field `{word}`"))),
+        };
+        Ok(if op == Token::NotEq { !query } else { query })
+    }
+}
+
+fn string_value(at: usize, value: Token) -> Result<Option<String>, QueryParseError> {
+    match value {
+        Token::Str(s) => Ok(Some(s)),
+        Token::Word(word) if word == "none" => Ok(None),
+        other => Err(QueryParseError::new(at, format!("expected a quoted string or none, found {other}"))),
+    }
+}
+
+fn number_value(at: usize, value: Token) -> Result<Option<u32>, QueryParseError> {
+    match value {
+        Token::Word(word) if word == "none" => Ok(None),
+        Token::Word(word) => word
+            .parse()
+            .map(Some)
+            .map_err(|_| QueryParseError::new(at, format!("expected a number or none, found `{word}`"))),
+        other => Err(QueryParseError::new(at, format!("expected a number or none, found {other}"))),
+    }
+}
+
+fn id_value(at: usize, value: Token) -> Result<Option<WindowId>, QueryParseError> {
+    match value {
+        Token::Word(word) if word == "none" => Ok(None),
+        Token::Word(word) => word.parse().map(Some).map_err(|err| QueryParseError::new(at, format!("{err}"))),
+        other => Err(QueryParseError::new(at, format!("expected a window id, found {other}"))),
+    }
+}
@@ -2,35 +2,63 @@ use std::{
     collections::HashMap,
     fmt::Debug,
     num::NonZeroU32,
+    sync::Arc,
+};
+
+use super::{
+    index::Indexes,
+    stacking::{StackKey, Stacks},
+    transaction::coalesce,
+    workspaces::Workspaces,
+    FocusFallback,
 };
 
 use crate::{
+    ChildRemovalPolicy,
     DesktopKey,
     LifecycleState,
+    OutputInfo,
+    PlacementStore,
     RegistryError,
     RegistryEvent,
+    StackLayer,
     SurfaceKey,
     WindowChange,
     WindowChanges,
     WindowId,
     WindowInfo,
     WindowRecord,
+    WindowRule,
     WindowState,
 };
 
-#[derive(Debug)]
+#[derive(Debug, Clone)]
 pub struct Slot {
     pub gen: NonZeroU32,
-    pub value: Option<WindowRecord>,
+    /// Shared between clones of the registry until one of them edits the record, so staging a
+    /// transaction doesn't copy every window.
+    pub value: Option<Arc<WindowRecord>>,
 }
 
-#[derive(Debug)]
+#[derive(Debug, Clone)]
 pub struct Registry {
     pub(crate) slots: Vec<Slot>,
     pub(crate) free: Vec<u32>,
 
     pub surface_map: HashMap<SurfaceKey, WindowId>,
     pub desktop_map: HashMap<DesktopKey, WindowId>,
+    pub(crate) indexes: Indexes,
+    pub(crate) stacks: Stacks,
+    pub(crate) workspaces: Workspaces,
+    pub(crate) outputs: Vec<OutputInfo>,
+
+    pub(crate) child_policy: ChildRemovalPolicy,
+    pub(crate) focus_fallback: Vec<FocusFallback>,
+    pub(crate) focus_history: Vec<WindowId>,
+    pub(crate) placements: Option<PlacementStore>,
+    pub(crate) rules: Vec<WindowRule>,
+    /// Indices into `rules` already applied to each live window.
+    pub(crate) applied_rules: HashMap<WindowId, Vec<usize>>,
 }
 
 impl Registry {
@@ -40,6 +68,16 @@ impl Registry {
             free: Vec::new(),
             surface_map: HashMap::new(),
             desktop_map: HashMap::new(),
+            indexes: Indexes::default(),
+            stacks: Stacks::default(),
+            workspaces: Workspaces::default(),
+            outputs: Vec::new(),
+            child_policy: ChildRemovalPolicy::default(),
+            focus_fallback: FocusFallback::default_chain(),
+            focus_history: Vec::new(),
+            placements: None,
+            rules: Vec::new(),
+            applied_rules: HashMap::new(),
         }
     }
 
@@ -64,12 +102,15 @@ impl Registry {
     }
 
     /// Inserts value and returns its WindowId (fresh id each time).
-    /// This is the low-level, libweston-agnostic insertion.
-    pub fn insert(&mut self, value: WindowRecord) -> WindowId {
+    /// This is the low-level, libweston-agnostic insertion. The window goes on top of its
+    /// stack, overriding `value.stack_index`.
+    pub fn insert(&mut self, mut value: WindowRecord) -> WindowId {
         let id = self.alloc_id();
+        self.indexes.add(id, &value);
+        value.stack_index = self.stacks.push(Stacks::key(&value), id);
         let slot = &mut self.slots[id.index as usize];
         debug_assert!(slot.value.is_none());
-        slot.value = Some(value);
+        slot.value = Some(Arc::new(value));
         id
     }
 
@@ -83,7 +124,6 @@ impl Registry {
         dk: DesktopKey,
         sk: SurfaceKey,
     ) -> Result<(WindowId, Vec<RegistryEvent>), RegistryError> {
-        let stack_index = self.live_count() as i32;
         if let Some(existing) = self.desktop_map.get(&dk).copied() {
             return Err(RegistryError::DesktopKeyAlreadyRegistered { dk, existing });
         }
@@ -92,6 +132,7 @@ impl Registry {
         }
 
         let id = self.alloc_id();
+        let stack_index = self.stacks.push((None, StackLayer::Normal), id);
 
         let record = WindowRecord {
             id,
@@ -103,16 +144,19 @@ impl Registry {
             is_focused: false,
             workspace: None,
             output: None,
+            layer: StackLayer::Normal,
             stack_index,
             parent_id: None,
             children: Vec::new(),
+            modal: false,
+            floating: false,
             title: None,
             app_id: None,
         };
 
         let slot = &mut self.slots[id.index as usize];
         debug_assert!(slot.value.is_none());
-        slot.value = Some(record);
+        slot.value = Some(Arc::new(record));
 
         self.desktop_map.insert(dk, id);
         self.surface_map.insert(sk, id);
@@ -126,16 +170,18 @@ impl Registry {
     pub fn get(&self, id: WindowId) -> Option<&WindowRecord> {
         let slot = self.slots.get(id.index as usize)?;
         if slot.gen == id.gen {
-            slot.value.as_ref()
+            slot.value.as_deref()
         } else {
             None
         }
     }
 
+    /// Edits made here bypass validation, events, the `app_id` / workspace / output indexes
+    /// and the stacks; prefer `update_window`.
     pub fn get_mut(&mut self, id: WindowId) -> Option<&mut WindowRecord> {
         let slot = self.slots.get_mut(id.index as usize)?;
         if slot.gen == id.gen {
-            slot.value.as_mut()
+            slot.value.as_mut().map(Arc::make_mut)
         } else {
             None
         }
@@ -148,7 +194,7 @@ impl Registry {
     pub fn snapshot_all(&self) -> Vec<WindowInfo> {
         self.slots
             .iter()
-            .filter_map(|s| s.value.as_ref())
+            .filter_map(|s| s.value.as_deref())
             .map(WindowInfo::from)
             .collect()
     }
@@ -180,48 +226,62 @@ impl Registry {
         if slot.gen != id.gen {
             return None;
         }
-        let out = slot.value.take();
-        if out.is_some() {
+        let out = slot.value.take().map(Arc::unwrap_or_clone);
+        if let Some(rec) = &out {
+            self.indexes.remove(id, rec);
+            self.stacks.take(Stacks::key(rec), id);
+            self.regroup_stack(Stacks::key(rec));
+            self.renumber_stack(Stacks::key(rec), &[], &mut Vec::new());
             self.free.push(id.index);
+            self.forget_focus(id);
+            self.applied_rules.remove(&id);
         }
         out
     }
 
+    /// Removes a window and keeps the parent/child hierarchy consistent.
+    ///
+    /// Events are emitted in this order:
+    /// 1. the parent's `children` change (including adopted children under `Reparent`),
+    /// 2. one `parent_id` change per surviving child (orphans also lose `modal`),
+    /// 3. `stack_index` changes for windows above the removed ones in their stacks,
+    /// 4. `WindowDestroyed` for destroyed descendants (deepest first), then for `id`,
+    /// 5. the focus gain of the fallback window if a removed window was focused.
     pub fn remove_window(
         &mut self,
         id: WindowId,
     ) -> Result<(WindowRecord, Vec<RegistryEvent>), RegistryError> {
-        let slot = self
-            .slots
-            .get_mut(id.index as usize)
-            .ok_or(RegistryError::InvalidWindowId(id))?;
+        let record = self.get(id).ok_or(RegistryError::InvalidWindowId(id))?;
+        let parent_id = record.parent_id;
+        let children = record.children.clone();
 
-        if slot.gen != id.gen {
-            return Err(RegistryError::InvalidWindowId(id));
+        let mut doomed = Vec::new();
+        if self.child_policy == ChildRemovalPolicy::DestroySubtree {
+            self.collect_descendants(id, &mut doomed);
         }
 
-        let record = slot.value.take().ok_or(RegistryError::InvalidWindowId(id))?;
-        let removed_stack_index = record.stack_index;
-
-        // Remove reverse lookups
-        self.desktop_map.remove(&record.dk);
-        self.surface_map.remove(&record.sk);
-
-        // Free slot for reuse
-        self.free.push(id.index);
-
         let mut events = Vec::new();
 
-        if removed_stack_index >= 0 {
-            for slot in &mut self.slots {
-                let Some(other) = slot.value.as_mut() else { continue };
-                if other.stack_index > removed_stack_index {
-                    let old = other.stack_index;
-                    other.stack_index -= 1;
+        let adopter = match self.child_policy {
+            ChildRemovalPolicy::Reparent => parent_id,
+            _ => None,
+        };
+
+        if let Some(parent) = parent_id {
+            if let Some(parent_rec) = self.get_mut(parent) {
+                let old_children = parent_rec.children.clone();
+                parent_rec.children.retain(|cid| *cid != id);
+                if adopter == Some(parent) {
+                    parent_rec.children.extend(children.iter().copied());
+                }
+                if parent_rec.children != old_children {
                     events.push(RegistryEvent::WindowChanged {
-                        id: other.id,
+                        id: parent,
                         changes: WindowChanges {
-                            stack_index: Some(WindowChange { old, new: other.stack_index }),
+                            children: Some(WindowChange {
+                                old: old_children,
+                                new: parent_rec.children.clone(),
+                            }),
                             ..WindowChanges::default()
                         },
                     });
@@ -229,47 +289,169 @@ impl Registry {
             }
         }
 
+        if self.child_policy != ChildRemovalPolicy::DestroySubtree {
+            for child_id in &children {
+                let Some(child) = self.get_mut(*child_id) else { continue };
+                let old = child.parent_id;
+                child.parent_id = adopter;
+                // An orphaned dialog has nothing left to block.
+                let modal = (child.modal && adopter.is_none()).then(|| {
+                    child.modal = false;
+                    WindowChange { old: true, new: false }
+                });
+                events.push(RegistryEvent::WindowChanged {
+                    id: *child_id,
+                    changes: WindowChanges {
+                        parent_id: Some(WindowChange { old, new: adopter }),
+                        modal,
+                        ..WindowChanges::default()
+                    },
+                });
+            }
+        }
+
+        let mut touched_stacks: Vec<StackKey> = Vec::with_capacity(doomed.len() + 1);
+        let mut lost_focus = None;
+        for doomed_id in &doomed {
+            let rec = self.take_record(*doomed_id);
+            self.remember_placement(&rec);
+            if !touched_stacks.contains(&Stacks::key(&rec)) {
+                touched_stacks.push(Stacks::key(&rec));
+            }
+            if rec.is_focused {
+                lost_focus = Some(rec);
+            }
+        }
+        let record = self.take_record(id);
+        self.remember_placement(&record);
+        if !touched_stacks.contains(&Stacks::key(&record)) {
+            touched_stacks.push(Stacks::key(&record));
+        }
+        for key in touched_stacks {
+            self.regroup_stack(key);
+            self.renumber_stack(key, &[], &mut events);
+        }
+
+        for doomed_id in doomed {
+            events.push(RegistryEvent::WindowDestroyed { id: doomed_id });
+        }
         events.push(RegistryEvent::WindowDestroyed { id });
 
+        if record.is_focused {
+            self.apply_focus_fallback(&record, &mut events);
+        } else if let Some(lost) = lost_focus {
+            self.apply_focus_fallback(&lost, &mut events);
+        }
+
         Ok((record, events))
     }
 
+    pub fn child_removal_policy(&self) -> ChildRemovalPolicy {
+        self.child_policy
+    }
+
+    pub fn set_child_removal_policy(&mut self, policy: ChildRemovalPolicy) {
+        self.child_policy = policy;
+    }
+
+    /// Runs an operation made of several fallible steps on a copy, kept only if every step
+    /// succeeds.
+    pub(crate) fn all_or_nothing<T>(
+        &mut self,
+        op: impl FnOnce(&mut Registry) -> Result<T, RegistryError>,
+    ) -> Result<T, RegistryError> {
+        let mut staged = self.clone();
+        let value = op(&mut staged)?;
+        *self = staged;
+        Ok(value)
+    }
+
+    /// Empties the slot of a live window and drops its reverse lookups.
+    fn take_record(&mut self, id: WindowId) -> WindowRecord {
+        let slot = &mut self.slots[id.index as usize];
+        debug_assert_eq!(slot.gen, id.gen);
+        let record = slot.value.take().map(Arc::unwrap_or_clone).expect("take_record on a dead window");
+
+        self.desktop_map.remove(&record.dk);
+        self.surface_map.remove(&record.sk);
+        self.indexes.remove(id, &record);
+        self.stacks.take(Stacks::key(&record), id);
+        self.free.push(id.index);
+        self.forget_focus(id);
+        self.applied_rules.remove(&id);
+
+        record
+    }
+
+    /// Appends every descendant of `id`, children after their own descendants.
+    fn collect_descendants(&self, id: WindowId, out: &mut Vec<WindowId>) {
+        let Some(rec) = self.get(id) else { return };
+        for child_id in &rec.children {
+            self.collect_descendants(*child_id, out);
+            out.push(*child_id);
+        }
+    }
+
     // Optional: lifecycle transitions (C-level completeness)
+    /// The first map runs the window rules; their changes are merged into the lifecycle change.
+    /// Mapping a modal transient over the focused window moves focus to it.
     pub fn on_map(&mut self, id: WindowId) -> Result<Vec<RegistryEvent>, RegistryError> {
-        let r = self.get_mut(id).ok_or(RegistryError::InvalidWindowId(id))?;
-        let old = r.lifecycle;
-        if old != LifecycleState::Mapped {
-            r.lifecycle = LifecycleState::Mapped;
-            Ok(vec![RegistryEvent::WindowChanged {
-                id,
-                changes: WindowChanges {
-                    lifecycle: Some(WindowChange { old, new: LifecycleState::Mapped }),
-                    ..WindowChanges::default()
-                },
-            }])
+        let old = self.get(id).ok_or(RegistryError::InvalidWindowId(id))?.lifecycle;
+        if old == LifecycleState::Mapped {
+            return Ok(vec![]);
+        }
+
+        // Before the lifecycle change, so a failing rule action leaves the window untouched.
+        let rule_events = if old == LifecycleState::Created {
+            self.apply_map_rules(id)?
         } else {
-            Ok(vec![])
+            Vec::new()
+        };
+
+        if let Some(r) = self.get_mut(id) {
+            r.lifecycle = LifecycleState::Mapped;
         }
+        let mut events = vec![RegistryEvent::WindowChanged {
+            id,
+            changes: WindowChanges {
+                lifecycle: Some(WindowChange { old, new: LifecycleState::Mapped }),
+                ..WindowChanges::default()
+            },
+        }];
+        events.extend(rule_events);
+        self.focus_blocking_modal(&mut events);
+        Ok(coalesce(events))
     }
 
+    /// Hides a mapped window. If it was focused, focus moves to the window chosen by the
+    /// focus fallback chain and both focus changes are emitted in the same batch.
     pub fn on_unmap(&mut self, id: WindowId) -> Result<Vec<RegistryEvent>, RegistryError> {
         let r = self.get_mut(id).ok_or(RegistryError::InvalidWindowId(id))?;
         let old = r.lifecycle;
-        if old == LifecycleState::Mapped {
-            r.lifecycle = LifecycleState::Unmapped;
-            Ok(vec![RegistryEvent::WindowChanged {
-                id,
-                changes: WindowChanges {
-                    lifecycle: Some(WindowChange { old, new: LifecycleState::Unmapped }),
-                    ..WindowChanges::default()
-                },
-            }])
-        } else {
-            Ok(vec![])
+        if old != LifecycleState::Mapped {
+            return Ok(vec![]);
+        }
+
+        r.lifecycle = LifecycleState::Unmapped;
+        let mut changes = WindowChanges {
+            lifecycle: Some(WindowChange { old, new: LifecycleState::Unmapped }),
+            ..WindowChanges::default()
+        };
+        let was_focused = r.is_focused;
+        if was_focused {
+            r.is_focused = false;
+            changes.is_focused = Some(WindowChange { old: true, new: false });
+        }
+
+        let mut events = vec![RegistryEvent::WindowChanged { id, changes }];
+
+        if was_focused {
+            let next = self.get(id).and_then(|lost| self.choose_focus_fallback(lost));
+            if let Some(next) = next {
+                self.grant_focus(next, &mut events);
+            }
         }
-    }
 
-    pub(crate) fn live_count(&self) -> usize {
-        self.slots.iter().filter(|s| s.value.is_some()).count()
+        Ok(events)
     }
 }
@@ -0,0 +1,145 @@
+use crate::{
+    LifecycleState,
+    OutputId,
+    RegistryEvent,
+    WindowChange,
+    WindowChanges,
+    WindowId,
+    WindowRecord,
+    WorkspaceId,
+};
+
+use super::Registry;
+
+/// One step of the focus fallback chain run when the focused window is unmapped or destroyed.
+///
+/// Steps are tried in order; the first one that yields a mapped, non-minimized window wins. A
+/// window blocked by a modal dialog hands the focus on to the dialog.
+#[derive(Debug, Copy, Clone)]
+pub enum FocusFallback {
+    /// The parent of the transient that lost focus.
+    Parent,
+    /// The most recently focused window still eligible for focus.
+    MostRecentlyFocused,
+    /// The topmost window on the same workspace and output.
+    TopOfStack,
+    /// Caller-provided policy; receives the registry and the window that lost focus.
+    Custom(fn(&Registry, &WindowRecord) -> Option<WindowId>),
+}
+
+impl FocusFallback {
+    pub fn default_chain() -> Vec<FocusFallback> {
+        vec![
+            FocusFallback::Parent,
+            FocusFallback::MostRecentlyFocused,
+            FocusFallback::TopOfStack,
+        ]
+    }
+}
+
+impl Registry {
+    pub fn focus_fallback(&self) -> &[FocusFallback] {
+        &self.focus_fallback
+    }
+
+    /// Replaces the fallback chain. An empty chain leaves nothing focused.
+    pub fn set_focus_fallback(&mut self, chain: Vec<FocusFallback>) {
+        self.focus_fallback = chain;
+    }
+
+    /// Windows in most-recently-focused order, most recent first.
+    ///
+    /// Every focus gain made through `update_window` or the focus fallback moves the window to
+    /// the front; destroyed windows are pruned.
+    pub fn focus_history(&self) -> &[WindowId] {
+        &self.focus_history
+    }
+
+    pub fn focus_history_on_workspace(&self, workspace: WorkspaceId) -> Vec<WindowId> {
+        self.focus_history
+            .iter()
+            .copied()
+            .filter(|id| self.get(*id).is_some_and(|rec| rec.workspace == Some(workspace)))
+            .collect()
+    }
+
+    pub fn focus_history_on_output(&self, output: OutputId) -> Vec<WindowId> {
+        self.focus_history
+            .iter()
+            .copied()
+            .filter(|id| self.get(*id).is_some_and(|rec| rec.output == Some(output)))
+            .collect()
+    }
+
+    pub(crate) fn is_focus_candidate(&self, id: WindowId, lost: WindowId) -> bool {
+        if id == lost {
+            return false;
+        }
+        match self.get(id) {
+            Some(rec) => {
+                rec.lifecycle == LifecycleState::Mapped && !rec.state.minimized && !self.skips_focus(id)
+            }
+            None => false,
+        }
+    }
+
+    pub(crate) fn choose_focus_fallback(&self, lost: &WindowRecord) -> Option<WindowId> {
+        for step in &self.focus_fallback {
+            let candidate = match step {
+                FocusFallback::Parent => lost.parent_id,
+                FocusFallback::MostRecentlyFocused => self
+                    .focus_history
+                    .iter()
+                    .copied()
+                    .find(|id| self.is_focus_candidate(*id, lost.id)),
+                FocusFallback::TopOfStack => self
+                    .slots
+                    .iter()
+                    .filter_map(|s| s.value.as_deref())
+                    .filter(|rec| rec.workspace == lost.workspace && rec.output == lost.output)
+                    .filter(|rec| self.is_focus_candidate(rec.id, lost.id))
+                    .max_by_key(|rec| (rec.layer, rec.stack_index))
+                    .map(|rec| rec.id),
+                FocusFallback::Custom(policy) => policy(self, lost),
+            };
+            if let Some(id) = candidate.map(|id| self.focus_target(id)) {
+                if self.is_focus_candidate(id, lost.id) {
+                    return Some(id);
+                }
+            }
+        }
+        None
+    }
+
+    /// Focuses the fallback for `lost`, if any, and emits its focus change.
+    pub(crate) fn apply_focus_fallback(&mut self, lost: &WindowRecord, events: &mut Vec<RegistryEvent>) {
+        if let Some(next) = self.choose_focus_fallback(lost) {
+            self.grant_focus(next, events);
+        }
+    }
+
+    pub(crate) fn grant_focus(&mut self, id: WindowId, events: &mut Vec<RegistryEvent>) {
+        let Some(rec) = self.get_mut(id) else { return };
+        if rec.is_focused {
+            return;
+        }
+        rec.is_focused = true;
+        events.push(RegistryEvent::WindowChanged {
+            id,
+            changes: WindowChanges {
+                is_focused: Some(WindowChange { old: false, new: true }),
+                ..WindowChanges::default()
+            },
+        });
+        self.record_focus(id);
+    }
+
+    pub(crate) fn record_focus(&mut self, id: WindowId) {
+        self.focus_history.retain(|other| *other != id);
+        self.focus_history.insert(0, id);
+    }
+
+    pub(crate) fn forget_focus(&mut self, id: WindowId) {
+        self.focus_history.retain(|other| *other != id);
+    }
+}
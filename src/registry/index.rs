@@ -0,0 +1,70 @@
+use std::{collections::HashMap, hash::Hash};
+
+use crate::{
+    OutputId,
+    WindowId,
+    WindowRecord,
+    WorkspaceId,
+};
+
+use super::Registry;
+
+/// Secondary indexes from record fields to the live windows carrying them, kept in step with
+/// the records like `desktop_map` / `surface_map`. Each list is in the order windows joined it.
+#[derive(Debug, Clone, Default)]
+pub(crate) struct Indexes {
+    pub(crate) app_id: HashMap<String, Vec<WindowId>>,
+    pub(crate) workspace: HashMap<WorkspaceId, Vec<WindowId>>,
+    pub(crate) output: HashMap<OutputId, Vec<WindowId>>,
+}
+
+impl Indexes {
+    pub(crate) fn add(&mut self, id: WindowId, rec: &WindowRecord) {
+        relink(&mut self.app_id, id, None, rec.app_id.clone());
+        relink(&mut self.workspace, id, None, rec.workspace);
+        relink(&mut self.output, id, None, rec.output);
+    }
+
+    pub(crate) fn remove(&mut self, id: WindowId, rec: &WindowRecord) {
+        relink(&mut self.app_id, id, rec.app_id.clone(), None);
+        relink(&mut self.workspace, id, rec.workspace, None);
+        relink(&mut self.output, id, rec.output, None);
+    }
+}
+
+/// Moves `id` from the `old` key's list to the `new` key's list, dropping emptied lists.
+pub(crate) fn relink<K: Hash + Eq>(
+    index: &mut HashMap<K, Vec<WindowId>>,
+    id: WindowId,
+    old: Option<K>,
+    new: Option<K>,
+) {
+    if old == new {
+        return;
+    }
+    if let Some(old) = old {
+        if let Some(ids) = index.get_mut(&old) {
+            ids.retain(|other| *other != id);
+            if ids.is_empty() {
+                index.remove(&old);
+            }
+        }
+    }
+    if let Some(new) = new {
+        index.entry(new).or_default().push(id);
+    }
+}
+
+impl Registry {
+    pub fn windows_on_workspace(&self, workspace: WorkspaceId) -> &[WindowId] {
+        self.indexes.workspace.get(&workspace).map_or(&[], Vec::as_slice)
+    }
+
+    pub fn windows_on_output(&self, output: OutputId) -> &[WindowId] {
+        self.indexes.output.get(&output).map_or(&[], Vec::as_slice)
+    }
+
+    pub fn windows_with_app_id(&self, app_id: &str) -> &[WindowId] {
+        self.indexes.app_id.get(app_id).map_or(&[], Vec::as_slice)
+    }
+}
@@ -0,0 +1,70 @@
+use crate::{
+    Gaps,
+    Layout,
+    LifecycleState,
+    RegistryError,
+    RegistryEvent,
+    StackLayer,
+    WindowGeometry,
+    WindowId,
+    WindowUpdate,
+    WorkspaceId,
+};
+
+use super::Registry;
+
+impl Registry {
+    /// The windows a layout tiles on `workspace`, in the order they joined it: mapped top-level
+    /// windows in the `Normal` layer that are not floating, minimized, maximized or fullscreen.
+    pub fn tiled_windows(&self, workspace: WorkspaceId) -> Vec<WindowId> {
+        self.windows_on_workspace(workspace)
+            .iter()
+            .copied()
+            .filter(|id| {
+                self.get(*id).is_some_and(|rec| {
+                    rec.lifecycle == LifecycleState::Mapped
+                        && rec.parent_id.is_none()
+                        && rec.layer == StackLayer::Normal
+                        && !rec.floating
+                        && !(rec.state.minimized || rec.state.maximized || rec.state.fullscreen)
+                })
+            })
+            .collect()
+    }
+
+    /// Where `layout` puts each of `tiled_windows(workspace)`, inside the usable area of the
+    /// workspace's output. Changes nothing.
+    pub fn compute_layout(
+        &self,
+        workspace: WorkspaceId,
+        layout: Layout,
+        gaps: Gaps,
+    ) -> Result<Vec<(WindowId, WindowGeometry)>, RegistryError> {
+        let output = self.workspace(workspace).ok_or(RegistryError::InvalidWorkspaceId(workspace))?.output;
+        let area = self.output(output).ok_or(RegistryError::InvalidOutputId(output))?.usable_area();
+        let windows = self.tiled_windows(workspace);
+        let tiles = layout.arrange(area, gaps, windows.len())?;
+        Ok(windows.into_iter().zip(tiles).collect())
+    }
+
+    /// Moves the tiled windows of `workspace` into `layout`, all or nothing, with one
+    /// `geometry` change per window that moved.
+    pub fn apply_layout(
+        &mut self,
+        workspace: WorkspaceId,
+        layout: Layout,
+        gaps: Gaps,
+    ) -> Result<Vec<RegistryEvent>, RegistryError> {
+        let tiles = self.compute_layout(workspace, layout, gaps)?;
+        for (id, geometry) in &tiles {
+            self.validate_geometry(*id, *geometry)?;
+        }
+
+        let mut events = Vec::new();
+        for (id, geometry) in tiles {
+            let update = WindowUpdate { geometry: Some(Some(geometry)), ..WindowUpdate::default() };
+            events.extend(self.update_window(id, update)?);
+        }
+        Ok(events)
+    }
+}
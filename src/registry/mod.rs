@@ -1,5 +1,19 @@
 mod core;
+mod focus;
+mod index;
+mod layout;
+mod modal;
+mod outputs;
+mod placement;
+mod query;
+mod rules;
+mod stacking;
+mod transaction;
 mod updates;
 mod validation;
+mod workspaces;
 
 pub use core::{Registry, Slot};
+pub use focus::FocusFallback;
+pub use transaction::Transaction;
+pub(crate) use transaction::coalesce_by;
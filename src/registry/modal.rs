@@ -0,0 +1,67 @@
+use crate::{
+    LifecycleState,
+    RegistryEvent,
+    WindowChange,
+    WindowChanges,
+    WindowId,
+};
+
+use super::Registry;
+
+impl Registry {
+    /// The mapped modal transient blocking input to `id`, the topmost one if there are several.
+    pub fn modal_child(&self, id: WindowId) -> Option<WindowId> {
+        self.get(id)?
+            .children
+            .iter()
+            .filter_map(|child| self.get(*child))
+            .filter(|rec| rec.modal && rec.lifecycle == LifecycleState::Mapped)
+            .max_by_key(|rec| (rec.layer, rec.stack_index))
+            .map(|rec| rec.id)
+    }
+
+    /// Whether a mapped modal transient keeps `id` from taking focus.
+    pub fn is_input_blocked(&self, id: WindowId) -> bool {
+        self.modal_child(id).is_some()
+    }
+
+    /// Every input-blocked window, in slot order.
+    pub fn input_blocked_windows(&self) -> Vec<WindowId> {
+        self.slots
+            .iter()
+            .filter_map(|s| s.value.as_deref())
+            .filter(|rec| self.is_input_blocked(rec.id))
+            .map(|rec| rec.id)
+            .collect()
+    }
+
+    /// The window that receives focus meant for `id`: `id` itself, or the innermost modal
+    /// transient blocking it.
+    pub fn focus_target(&self, id: WindowId) -> WindowId {
+        let mut target = id;
+        while let Some(modal) = self.modal_child(target) {
+            target = modal;
+        }
+        target
+    }
+
+    /// Moves focus from a window that a modal transient now blocks onto the dialog.
+    pub(crate) fn focus_blocking_modal(&mut self, events: &mut Vec<RegistryEvent>) {
+        let Some(focused) = self.find_focused(None) else { return };
+        let target = self.focus_target(focused);
+        if target == focused {
+            return;
+        }
+        if let Some(rec) = self.get_mut(focused) {
+            rec.is_focused = false;
+        }
+        events.push(RegistryEvent::WindowChanged {
+            id: focused,
+            changes: WindowChanges {
+                is_focused: Some(WindowChange { old: true, new: false }),
+                ..WindowChanges::default()
+            },
+        });
+        self.grant_focus(target, events);
+    }
+}
@@ -0,0 +1,159 @@
+use crate::{
+    OutputId,
+    OutputInfo,
+    OutputUpdate,
+    RegistryError,
+    RegistryEvent,
+    WindowGeometry,
+    WindowId,
+    WindowUpdate,
+};
+
+use super::Registry;
+
+impl Registry {
+    /// Every output, in the order they were added.
+    pub fn outputs(&self) -> &[OutputInfo] {
+        &self.outputs
+    }
+
+    pub fn output(&self, id: OutputId) -> Option<&OutputInfo> {
+        self.outputs.iter().find(|out| out.id == id)
+    }
+
+    pub fn output_by_name(&self, name: &str) -> Option<OutputId> {
+        self.outputs.iter().find(|out| out.name == name).map(|out| out.id)
+    }
+
+    /// Registers an output under the id the compositor gave it.
+    ///
+    /// Once any output exists, `update_window` and `create_workspace` only accept known
+    /// outputs.
+    pub fn add_output(&mut self, output: OutputInfo) -> Result<Vec<RegistryEvent>, RegistryError> {
+        if self.output(output.id).is_some() {
+            return Err(RegistryError::OutputAlreadyRegistered(output.id));
+        }
+        if let Some(existing) = self.output_by_name(&output.name) {
+            return Err(RegistryError::OutputNameTaken { name: output.name, existing });
+        }
+        validate_scale(output.id, output.scale)?;
+
+        self.outputs.push(output.clone());
+        Ok(vec![RegistryEvent::OutputAdded { output }])
+    }
+
+    /// Changes an output's mode. Windows are not moved or resized; disabling an output does not
+    /// migrate them either, it only stops the output from being picked as a fallback.
+    pub fn update_output(&mut self, id: OutputId, update: OutputUpdate) -> Result<Vec<RegistryEvent>, RegistryError> {
+        let pos = self.outputs.iter().position(|out| out.id == id).ok_or(RegistryError::InvalidOutputId(id))?;
+        if let Some(name) = &update.name {
+            if let Some(existing) = self.output_by_name(name).filter(|existing| *existing != id) {
+                return Err(RegistryError::OutputNameTaken { name: name.clone(), existing });
+            }
+        }
+        if let Some(scale) = update.scale {
+            validate_scale(id, scale)?;
+        }
+
+        let old = self.outputs[pos].clone();
+        let out = &mut self.outputs[pos];
+        if let Some(name) = update.name {
+            out.name = name;
+        }
+        if let Some(geometry) = update.geometry {
+            out.geometry = geometry;
+        }
+        if let Some(scale) = update.scale {
+            out.scale = scale;
+        }
+        if let Some(transform) = update.transform {
+            out.transform = transform;
+        }
+        if let Some(enabled) = update.enabled {
+            out.enabled = enabled;
+        }
+        if let Some(reserved) = update.reserved {
+            out.reserved = reserved;
+        }
+
+        if *out == old {
+            return Ok(Vec::new());
+        }
+        Ok(vec![RegistryEvent::OutputChanged { id, old, new: out.clone() }])
+    }
+
+    /// Removes an output (hotplug). Its workspaces and windows move to the first other enabled
+    /// output, else the first other output; window geometry keeps its offset from the output's
+    /// origin, clamped to fit the fallback.
+    ///
+    /// Events: `WorkspaceMoved` per workspace and the activation changes, one `WindowChanged`
+    /// per moved window, then `OutputRemoved`. Fails with `NoFallbackOutput` if anything would
+    /// be stranded; disable the output instead until another one appears. If any window can't
+    /// be moved nothing changes.
+    pub fn remove_output(&mut self, id: OutputId) -> Result<Vec<RegistryEvent>, RegistryError> {
+        let pos = self.outputs.iter().position(|out| out.id == id).ok_or(RegistryError::InvalidOutputId(id))?;
+        let others = || self.outputs.iter().filter(|out| out.id != id);
+        let fallback = others().find(|out| out.enabled).or_else(|| others().next()).cloned();
+
+        let windows: Vec<WindowId> = self.windows_on_output(id).to_vec();
+        let has_workspaces = self.workspaces.order.iter().any(|ws| ws.output == id);
+        let mut events = Vec::new();
+        match &fallback {
+            Some(fallback) => {
+                let from = self.outputs[pos].geometry;
+                events = self.all_or_nothing(|reg| {
+                    let mut events = Vec::new();
+                    reg.move_workspaces(id, fallback.id, &mut events);
+                    for window in windows {
+                        let geometry = reg.get(window).and_then(|rec| rec.geometry);
+                        let update = WindowUpdate {
+                            output: Some(Some(fallback.id)),
+                            geometry: geometry.map(|geom| Some(migrate_geometry(geom, from, fallback.geometry))),
+                            ..WindowUpdate::default()
+                        };
+                        events.extend(reg.update_window(window, update)?);
+                    }
+                    Ok(events)
+                })?;
+            }
+            None if !windows.is_empty() || has_workspaces => {
+                return Err(RegistryError::NoFallbackOutput { output: id });
+            }
+            None => {}
+        }
+
+        self.outputs.remove(pos);
+        events.push(RegistryEvent::OutputRemoved { id, fallback: fallback.map(|out| out.id) });
+        Ok(events)
+    }
+
+    /// Checks a window's next output against the known outputs, if any are registered.
+    pub(crate) fn validate_window_output(&self, id: WindowId, output: Option<OutputId>) -> Result<(), RegistryError> {
+        let Some(output) = output else { return Ok(()) };
+        if self.outputs.is_empty() || self.output(output).is_some() {
+            return Ok(());
+        }
+        Err(RegistryError::UnknownOutput { id, output })
+    }
+}
+
+fn validate_scale(output: OutputId, scale: f64) -> Result<(), RegistryError> {
+    if scale.is_finite() && scale > 0.0 {
+        Ok(())
+    } else {
+        Err(RegistryError::InvalidOutputScale { output, scale })
+    }
+}
+
+/// Keeps `geom`'s offset from `from`'s origin on `to`, pulled back inside `to` where it fits.
+fn migrate_geometry(geom: WindowGeometry, from: WindowGeometry, to: WindowGeometry) -> WindowGeometry {
+    let place = |pos: i32, from: i32, to: i32, size: i32, room: i32| {
+        let pos = to.saturating_add(pos.saturating_sub(from));
+        pos.clamp(to, to.saturating_add((room - size).max(0)))
+    };
+    WindowGeometry {
+        x: place(geom.x, from.x, to.x, geom.width, to.width),
+        y: place(geom.y, from.y, to.y, geom.height, to.height),
+        ..geom
+    }
+}
@@ -0,0 +1,68 @@
+use crate::{
+    LifecycleState,
+    Placement,
+    PlacementStore,
+    WindowRecord,
+    WindowUpdate,
+};
+
+use super::Registry;
+
+impl Registry {
+    pub fn placement_store(&self) -> Option<&PlacementStore> {
+        self.placements.as_ref()
+    }
+
+    pub fn placement_store_mut(&mut self) -> Option<&mut PlacementStore> {
+        self.placements.as_mut()
+    }
+
+    /// Enables placement memory with `store` as its starting contents, or disables it with
+    /// `None`. Returns the previous store.
+    ///
+    /// While enabled, `remove_window` saves the placement of every top-level window that was
+    /// mapped and has an `app_id`, and `update_window` restores it when a top-level window gets
+    /// its first `app_id`.
+    pub fn set_placement_store(&mut self, store: Option<PlacementStore>) -> Option<PlacementStore> {
+        std::mem::replace(&mut self.placements, store)
+    }
+
+    /// Saves the placement of a window that is being destroyed.
+    pub(crate) fn remember_placement(&mut self, rec: &WindowRecord) {
+        let Some(store) = self.placements.as_mut() else { return };
+        let Some(app_id) = rec.app_id.as_deref() else { return };
+        // Dialogs share their application's app_id but not its placement, and a window that
+        // never mapped has nothing worth keeping.
+        if rec.parent_id.is_some() || rec.lifecycle == LifecycleState::Created {
+            return;
+        }
+        let mut placement = Placement::from(rec);
+        placement.state.minimized = false;
+        store.insert(app_id, placement);
+    }
+
+    /// Fills the fields `update` leaves alone from the saved placement when it gives `current`
+    /// its first `app_id`, so the restore lands in the same `WindowChanged`.
+    pub(crate) fn restore_placement(&self, current: &WindowRecord, update: &mut WindowUpdate) {
+        let Some(store) = self.placements.as_ref() else { return };
+        let Some(Some(app_id)) = update.app_id.as_ref() else { return };
+        if current.app_id.is_some() || current.parent_id.is_some() || matches!(update.parent_id, Some(Some(_))) {
+            return;
+        }
+        let Some(saved) = store.get(app_id) else { return };
+
+        if update.geometry.is_none() && saved.geometry.is_some() {
+            update.geometry = Some(saved.geometry);
+        }
+        if update.state.is_none() {
+            update.state = Some(saved.state);
+        }
+        // Workspace and output change together, and only to ones that still exist.
+        let known = self.validate_window_output(current.id, saved.output).is_ok()
+            && self.validate_window_workspace(current.id, saved.workspace, saved.output).is_ok();
+        if update.workspace.is_none() && update.output.is_none() && saved.workspace.is_some() && known {
+            update.workspace = Some(saved.workspace);
+            update.output = Some(saved.output);
+        }
+    }
+}
@@ -0,0 +1,15 @@
+use crate::{Query, WindowId};
+
+use super::Registry;
+
+impl Registry {
+    /// Ids of the live windows matching `query`, in slot order.
+    pub fn query(&self, query: &Query) -> Vec<WindowId> {
+        self.slots
+            .iter()
+            .filter_map(|s| s.value.as_deref())
+            .filter(|rec| query.matches(rec))
+            .map(|rec| rec.id)
+            .collect()
+    }
+}
@@ -0,0 +1,118 @@
+use crate::{
+    rules::RuleSubject,
+    RegistryError,
+    RegistryEvent,
+    WindowId,
+    WindowRecord,
+    WindowRule,
+    WindowUpdate,
+};
+
+use super::Registry;
+
+impl Registry {
+    pub fn rules(&self) -> &[WindowRule] {
+        &self.rules
+    }
+
+    /// Replaces the rule list. Rules run once per window, when its `app_id` or title first
+    /// becomes known in `update_window` and when it is first mapped; replacing the list lets
+    /// the new rules run again at a live window's next trigger.
+    pub fn set_rules(&mut self, rules: Vec<WindowRule>) {
+        self.rules = rules;
+        self.applied_rules.clear();
+    }
+
+    pub fn add_rule(&mut self, rule: WindowRule) {
+        self.rules.push(rule);
+    }
+
+    /// Whether a rule applied to `id` asked for it never to be focused automatically.
+    pub fn skips_focus(&self, id: WindowId) -> bool {
+        self.applied_rules.get(&id).is_some_and(|applied| {
+            applied
+                .iter()
+                .any(|index| self.rules.get(*index).is_some_and(|rule| rule.actions.skip_focus))
+        })
+    }
+
+    /// Folds the rules triggered by `update` into it and returns their indices, to be marked
+    /// applied once the update succeeds.
+    pub(crate) fn match_update_rules(&self, current: &WindowRecord, update: &mut WindowUpdate) -> Vec<usize> {
+        let learns_app_id = current.app_id.is_none() && matches!(update.app_id, Some(Some(_)));
+        let learns_title = current.title.is_none() && matches!(update.title, Some(Some(_)));
+        if self.rules.is_empty() || !(learns_app_id || learns_title) {
+            return Vec::new();
+        }
+
+        let app_id = match &update.app_id {
+            Some(next) => next.clone(),
+            None => current.app_id.clone(),
+        };
+        let title = match &update.title {
+            Some(next) => next.clone(),
+            None => current.title.clone(),
+        };
+        let subject = RuleSubject {
+            app_id: app_id.as_deref(),
+            title: title.as_deref(),
+            has_parent: update.parent_id.unwrap_or(current.parent_id).is_some(),
+            output: update.output.unwrap_or(current.output),
+        };
+        self.match_rules(current.id, &subject, update)
+    }
+
+    /// Runs the rules for a window's first map through `update_window`.
+    pub(crate) fn apply_map_rules(&mut self, id: WindowId) -> Result<Vec<RegistryEvent>, RegistryError> {
+        let rec = self.get(id).ok_or(RegistryError::InvalidWindowId(id))?;
+        let subject = RuleSubject {
+            app_id: rec.app_id.as_deref(),
+            title: rec.title.as_deref(),
+            has_parent: rec.parent_id.is_some(),
+            output: rec.output,
+        };
+        let mut update = WindowUpdate::default();
+        let matched = self.match_rules(id, &subject, &mut update);
+        if matched.is_empty() {
+            return Ok(Vec::new());
+        }
+
+        let events = self.update_window(id, update)?;
+        self.mark_rules_applied(id, matched);
+        Ok(events)
+    }
+
+    pub(crate) fn mark_rules_applied(&mut self, id: WindowId, matched: Vec<usize>) {
+        if !matched.is_empty() {
+            self.applied_rules.entry(id).or_default().extend(matched);
+        }
+    }
+
+    /// Applies every matching rule not yet run for `id` to `update`, in list order.
+    fn match_rules(&self, id: WindowId, subject: &RuleSubject<'_>, update: &mut WindowUpdate) -> Vec<usize> {
+        let applied = self.applied_rules.get(&id);
+        let mut matched = Vec::new();
+        for (index, rule) in self.rules.iter().enumerate() {
+            if applied.is_some_and(|applied| applied.contains(&index)) || !rule.matches.matches(subject) {
+                continue;
+            }
+            let actions = &rule.actions;
+            if let Some((workspace, output)) = actions.place {
+                update.workspace = Some(Some(workspace));
+                update.output = Some(Some(output));
+            }
+            if let Some(geometry) = actions.geometry {
+                update.geometry = Some(Some(geometry));
+                update.floating = Some(true);
+            }
+            if let Some(state) = actions.state {
+                update.state = Some(state);
+            }
+            if actions.skip_focus && update.is_focused == Some(true) {
+                update.is_focused = None;
+            }
+            matched.push(index);
+        }
+        matched
+    }
+}
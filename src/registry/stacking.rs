@@ -0,0 +1,361 @@
+use std::collections::HashMap;
+
+use crate::{
+    OutputId,
+    RegistryError,
+    RegistryEvent,
+    StackLayer,
+    WindowChange,
+    WindowChanges,
+    WindowId,
+    WindowRecord,
+    WorkspaceId,
+};
+
+use super::{index::relink, Registry};
+
+/// The stack a window lives in: its output (windows without one share a stack) and layer.
+pub(crate) type StackKey = (Option<OutputId>, StackLayer);
+
+/// One back-to-front list per output and layer. A window's `stack_index` is its position in its
+/// list, so restacking only renumbers the windows of that list.
+///
+/// Transients sharing their parent's stack form a group with it: the parent followed directly by
+/// its children's groups, so children always sit right above their parent.
+#[derive(Debug, Clone, Default)]
+pub(crate) struct Stacks(HashMap<StackKey, Vec<WindowId>>);
+
+impl Stacks {
+    pub(crate) fn key(rec: &WindowRecord) -> StackKey {
+        (rec.output, rec.layer)
+    }
+
+    pub(crate) fn get(&self, key: StackKey) -> &[WindowId] {
+        self.0.get(&key).map_or(&[], Vec::as_slice)
+    }
+
+    /// Puts `id` on top of the stack and returns its position.
+    pub(crate) fn push(&mut self, key: StackKey, id: WindowId) -> i32 {
+        let stack = self.0.entry(key).or_default();
+        stack.push(id);
+        stack.len() as i32 - 1
+    }
+
+    /// Takes `id` out of the stack; positions above it are stale until `renumber_stack`.
+    pub(crate) fn take(&mut self, key: StackKey, id: WindowId) {
+        if let Some(stack) = self.0.get_mut(&key) {
+            stack.retain(|other| *other != id);
+            if stack.is_empty() {
+                self.0.remove(&key);
+            }
+        }
+    }
+
+    fn set(&mut self, key: StackKey, stack: Vec<WindowId>) {
+        if stack.is_empty() {
+            self.0.remove(&key);
+        } else {
+            self.0.insert(key, stack);
+        }
+    }
+}
+
+/// Where a restack puts a window's group, among the groups of its level: the stack's top-level
+/// windows, or the other transients of its parent.
+#[derive(Debug, Copy, Clone)]
+enum Target {
+    Top,
+    Bottom,
+    Above(WindowId),
+    Below(WindowId),
+}
+
+impl Registry {
+    /// One stack, back to front.
+    pub fn stack(&self, output: Option<OutputId>, layer: StackLayer) -> &[WindowId] {
+        self.stacks.get((output, layer))
+    }
+
+    /// Every window on `output`, back to front across layers.
+    pub fn stacking_order(&self, output: OutputId) -> Vec<WindowId> {
+        StackLayer::ALL
+            .iter()
+            .flat_map(|layer| self.stacks.get((Some(output), *layer)).iter().copied())
+            .collect()
+    }
+
+    /// Moves `id` and its transients to the top of its stack. A transient goes to the top of its
+    /// parent's group, and the parent's group is raised with it.
+    pub fn raise(&mut self, id: WindowId) -> Result<Vec<RegistryEvent>, RegistryError> {
+        let key = self.stack_key(id)?;
+        let mut stack = self.stacks.get(key).to_vec();
+        let mut current = Some(id);
+        while let Some(window) = current {
+            self.move_group(key, &mut stack, window, Target::Top);
+            current = self.stack_parent(window, key);
+        }
+        Ok(self.commit_restack(key, stack, id))
+    }
+
+    /// Moves `id` and its transients to the bottom of its stack, or for a transient, to just
+    /// above its parent.
+    pub fn lower(&mut self, id: WindowId) -> Result<Vec<RegistryEvent>, RegistryError> {
+        let key = self.stack_key(id)?;
+        let mut stack = self.stacks.get(key).to_vec();
+        self.move_group(key, &mut stack, id, Target::Bottom);
+        Ok(self.commit_restack(key, stack, id))
+    }
+
+    /// Moves `id` and its transients directly above the group of `sibling` at `id`'s level.
+    /// A transient can only be placed among its parent's other transients.
+    pub fn raise_above(&mut self, id: WindowId, sibling: WindowId) -> Result<Vec<RegistryEvent>, RegistryError> {
+        self.restack_relative(id, sibling, Target::Above)
+    }
+
+    /// Moves `id` and its transients directly below the group of `sibling` at `id`'s level.
+    /// A transient can never go below its parent.
+    pub fn lower_below(&mut self, id: WindowId, sibling: WindowId) -> Result<Vec<RegistryEvent>, RegistryError> {
+        self.restack_relative(id, sibling, Target::Below)
+    }
+
+    /// Rewrites `stack_index` for the windows of a stack whose position changed, with one event
+    /// each: bottom-up, except that the windows in `last` come after the others.
+    pub(crate) fn renumber_stack(&mut self, key: StackKey, last: &[WindowId], events: &mut Vec<RegistryEvent>) {
+        let stack = self.stacks.get(key).to_vec();
+        let (rest, moved): (Vec<_>, Vec<_>) =
+            stack.into_iter().enumerate().partition(|(_, other)| !last.contains(other));
+        for (pos, other) in rest.into_iter().chain(moved) {
+            let Some(rec) = self.get_mut(other) else { continue };
+            let old = rec.stack_index;
+            if old != pos as i32 {
+                rec.stack_index = pos as i32;
+                events.push(RegistryEvent::WindowChanged {
+                    id: other,
+                    changes: WindowChanges {
+                        stack_index: Some(WindowChange { old, new: pos as i32 }),
+                        ..WindowChanges::default()
+                    },
+                });
+            }
+        }
+    }
+
+    /// Restores transient groups in a stack after parents changed or windows left it: groups
+    /// keep the order of their first window, children follow their parent in their current
+    /// order. Positions are stale until `renumber_stack`.
+    pub(crate) fn regroup_stack(&mut self, key: StackKey) {
+        let stack = self.stacks.get(key).to_vec();
+        let mut children: HashMap<WindowId, Vec<WindowId>> = HashMap::new();
+        let mut roots = Vec::new();
+        for id in &stack {
+            match self.stack_parent(*id, key) {
+                Some(parent) => children.entry(parent).or_default().push(*id),
+                None => roots.push(*id),
+            }
+        }
+
+        fn flatten(id: WindowId, children: &HashMap<WindowId, Vec<WindowId>>, out: &mut Vec<WindowId>) {
+            out.push(id);
+            for child in children.get(&id).into_iter().flatten() {
+                flatten(*child, children, out);
+            }
+        }
+        let mut order = Vec::with_capacity(stack.len());
+        for root in roots {
+            flatten(root, &children, &mut order);
+        }
+        self.stacks.set(key, order);
+    }
+
+    /// Re-stacks after `update_window` changed `id`'s output, layer or parent, or the parent of
+    /// `adopted` children: `id` lands on top of its new stack, newly parented windows on top of
+    /// their parent's group. Transients stacked with `id` follow it to a new output or layer, with
+    /// an event each. `id`'s own index change goes into `changes`.
+    pub(crate) fn restack_after_update(
+        &mut self,
+        id: WindowId,
+        old_key: StackKey,
+        adopted: &[WindowId],
+        events: &mut Vec<RegistryEvent>,
+        changes: &mut WindowChanges,
+    ) {
+        let Some(rec) = self.get(id) else { return };
+        let new_key = Stacks::key(rec);
+        let workspace = rec.workspace;
+        let mut keys = vec![old_key];
+        let mut followers = Vec::new();
+        if new_key != old_key {
+            let group = self.left_behind(id, old_key);
+            for window in &group {
+                self.stacks.take(old_key, *window);
+            }
+            self.stacks.take(old_key, id);
+            self.stacks.push(new_key, id);
+            for window in group {
+                self.stacks.push(new_key, window);
+                followers.push((window, self.follow_stack(window, new_key, workspace)));
+            }
+            keys.push(new_key);
+        }
+
+        let parented = changes.parent_id.as_ref().is_some_and(|change| change.new.is_some());
+        let lifted = parented.then_some(id).into_iter().chain(adopted.iter().copied());
+        for window in lifted.collect::<Vec<_>>() {
+            let Some(key) = self.get(window).map(Stacks::key) else { continue };
+            let mut stack = self.stacks.get(key).to_vec();
+            let start = stack.iter().position(|other| *other == window).expect("live window is stacked");
+            let len = self.group_len(key, &stack, start);
+            let group: Vec<WindowId> = stack.drain(start..start + len).collect();
+            stack.extend(group);
+            self.stacks.set(key, stack);
+            if !keys.contains(&key) {
+                keys.push(key);
+            }
+        }
+        for key in &keys {
+            self.regroup_stack(*key);
+        }
+
+        let moved = followers.iter_mut().map(|(window, changes)| (*window, changes));
+        for (window, changes) in moved.chain([(id, changes)]) {
+            let index = self.stacks.get(new_key).iter().position(|other| *other == window).unwrap_or_default() as i32;
+            if let Some(rec) = self.get_mut(window) {
+                let old = std::mem::replace(&mut rec.stack_index, index);
+                if old != index {
+                    changes.stack_index = Some(WindowChange { old, new: index });
+                }
+            }
+        }
+        events.extend(followers.into_iter().map(|(id, changes)| RegistryEvent::WindowChanged { id, changes }));
+        for key in keys {
+            self.renumber_stack(key, &[], events);
+        }
+    }
+
+    /// The transients grouped with `id` in `old_key`'s stack, bottom-up, after `id` itself left it.
+    fn left_behind(&self, id: WindowId, old_key: StackKey) -> Vec<WindowId> {
+        let in_group = |mut other: WindowId| loop {
+            match self.get(other).and_then(|rec| rec.parent_id) {
+                Some(parent) if parent == id => return true,
+                Some(parent) if self.stack_parent(other, old_key) == Some(parent) => other = parent,
+                _ => return false,
+            }
+        };
+        let stack = self.stacks.get(old_key);
+        let Some(start) = stack.iter().position(|other| *other == id) else { return Vec::new() };
+        stack[start + 1..].iter().copied().take_while(|other| in_group(*other)).collect()
+    }
+
+    /// Moves a transient to the output and layer of `key`, and to `workspace` if its output
+    /// changes, returning the changes.
+    fn follow_stack(&mut self, id: WindowId, key: StackKey, workspace: Option<WorkspaceId>) -> WindowChanges {
+        let mut changes = WindowChanges::default();
+        let Some(rec) = self.get_mut(id) else { return changes };
+        let (old_output, old_workspace, old_layer) = (rec.output, rec.workspace, rec.layer);
+        if key.0 != old_output {
+            rec.output = key.0;
+            changes.output = Some(WindowChange { old: old_output, new: key.0 });
+            if workspace != old_workspace {
+                rec.workspace = workspace;
+                changes.workspace = Some(WindowChange { old: old_workspace, new: workspace });
+            }
+        }
+        if key.1 != old_layer {
+            rec.layer = key.1;
+            changes.layer = Some(WindowChange { old: old_layer, new: key.1 });
+        }
+        relink(&mut self.indexes.output, id, old_output, key.0);
+        if changes.workspace.is_some() {
+            relink(&mut self.indexes.workspace, id, old_workspace, workspace);
+        }
+        changes
+    }
+
+    fn stack_key(&self, id: WindowId) -> Result<StackKey, RegistryError> {
+        self.get(id).map(Stacks::key).ok_or(RegistryError::InvalidWindowId(id))
+    }
+
+    /// `id`'s parent, if it shares the stack; transients in another stack are not grouped.
+    fn stack_parent(&self, id: WindowId, key: StackKey) -> Option<WindowId> {
+        let parent = self.get(id)?.parent_id?;
+        self.get(parent).is_some_and(|rec| Stacks::key(rec) == key).then_some(parent)
+    }
+
+    /// Length of the group starting at `stack[pos]`.
+    fn group_len(&self, key: StackKey, stack: &[WindowId], pos: usize) -> usize {
+        let head = stack[pos];
+        let in_group = |mut other: WindowId| loop {
+            match self.stack_parent(other, key) {
+                Some(parent) if parent == head => return true,
+                Some(parent) => other = parent,
+                None => return false,
+            }
+        };
+        1 + stack[pos + 1..].iter().take_while(|other| in_group(**other)).count()
+    }
+
+    fn restack_relative(
+        &mut self,
+        id: WindowId,
+        sibling: WindowId,
+        target: fn(WindowId) -> Target,
+    ) -> Result<Vec<RegistryEvent>, RegistryError> {
+        let key = self.stack_key(id)?;
+        if self.stack_key(sibling)? != key {
+            return Err(RegistryError::NotInSameStack { id, sibling });
+        }
+        if sibling == id {
+            return Ok(Vec::new());
+        }
+
+        // The group containing `sibling` among the groups of `id`'s level.
+        let level = self.stack_parent(id, key);
+        let mut anchor = sibling;
+        while self.stack_parent(anchor, key) != level {
+            match self.stack_parent(anchor, key) {
+                Some(parent) if parent != id => anchor = parent,
+                _ => return Err(RegistryError::TransientOutsideGroup { id, sibling }),
+            }
+        }
+
+        let mut stack = self.stacks.get(key).to_vec();
+        self.move_group(key, &mut stack, id, target(anchor));
+        Ok(self.commit_restack(key, stack, id))
+    }
+
+    /// Moves `id`'s group within `stack`, staying inside its level.
+    fn move_group(&self, key: StackKey, stack: &mut Vec<WindowId>, id: WindowId, target: Target) {
+        let pos = |stack: &[WindowId], id: WindowId| stack.iter().position(|other| *other == id).expect("stacked");
+        let start = pos(stack, id);
+        let len = self.group_len(key, stack, start);
+        let group: Vec<WindowId> = stack.drain(start..start + len).collect();
+
+        let (low, high) = match self.stack_parent(id, key) {
+            Some(parent) => {
+                let at = pos(stack, parent);
+                (at + 1, at + self.group_len(key, stack, at))
+            }
+            None => (0, stack.len()),
+        };
+        let at = match target {
+            Target::Top => high,
+            Target::Bottom => low,
+            Target::Above(anchor) => {
+                let at = pos(stack, anchor);
+                at + self.group_len(key, stack, at)
+            }
+            Target::Below(anchor) => pos(stack, anchor),
+        };
+        stack.splice(at..at, group);
+    }
+
+    /// Stores a restacked order. Events: the other moved windows bottom-up, then `id`'s group.
+    fn commit_restack(&mut self, key: StackKey, stack: Vec<WindowId>, id: WindowId) -> Vec<RegistryEvent> {
+        let start = stack.iter().position(|other| *other == id).expect("stacked");
+        let group = stack[start..start + self.group_len(key, &stack, start)].to_vec();
+        self.stacks.set(key, stack);
+        let mut events = Vec::new();
+        self.renumber_stack(key, &group, &mut events);
+        events
+    }
+}
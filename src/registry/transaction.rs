@@ -0,0 +1,250 @@
+use std::collections::HashMap;
+
+use crate::{
+    DesktopKey,
+    Gaps,
+    Layout,
+    JournalOp,
+    OutputId,
+    OutputInfo,
+    OutputUpdate,
+    RegistryError,
+    RegistryEvent,
+    SurfaceKey,
+    WindowId,
+    WindowRecord,
+    WindowUpdate,
+    WorkspaceId,
+};
+
+use super::Registry;
+
+/// A batch of registry operations applied all-or-nothing.
+///
+/// Operations run against a staged copy of the registry, so each one is validated against the
+/// state left by the previous ones. Nothing reaches the real registry until `commit`; dropping
+/// the transaction (or any failed operation) discards the whole batch.
+///
+/// Staging copies the registry's lookup maps, indexes and stacks, which is linear in the number
+/// of windows; window records are shared with the staged copy until an operation edits them.
+/// `SharedRegistry` pays this under its write lock, so prefer single operations for one-off
+/// changes to a large registry.
+#[derive(Debug)]
+pub struct Transaction<'a> {
+    target: &'a mut Registry,
+    staged: Registry,
+    events: Vec<RegistryEvent>,
+    failed: Option<RegistryError>,
+    /// Every requested operation, for journaling.
+    ops: Vec<JournalOp>,
+}
+
+impl Registry {
+    pub fn transaction(&mut self) -> Transaction<'_> {
+        Transaction {
+            staged: self.clone(),
+            target: self,
+            events: Vec::new(),
+            failed: None,
+            ops: Vec::new(),
+        }
+    }
+}
+
+impl Transaction<'_> {
+    pub fn insert_window(&mut self, dk: DesktopKey, sk: SurfaceKey) -> Result<WindowId, RegistryError> {
+        self.ops.push(JournalOp::Insert { dk, sk });
+        let (id, events) = self.check(|reg| reg.insert_window(dk, sk))?;
+        self.events.extend(events);
+        Ok(id)
+    }
+
+    pub fn update_window(&mut self, id: WindowId, update: WindowUpdate) -> Result<(), RegistryError> {
+        self.ops.push(JournalOp::Update { id, update: update.clone() });
+        let events = self.check(|reg| reg.update_window(id, update))?;
+        self.events.extend(events);
+        Ok(())
+    }
+
+    pub fn on_map(&mut self, id: WindowId) -> Result<(), RegistryError> {
+        self.ops.push(JournalOp::Map { id });
+        let events = self.check(|reg| reg.on_map(id))?;
+        self.events.extend(events);
+        Ok(())
+    }
+
+    pub fn on_unmap(&mut self, id: WindowId) -> Result<(), RegistryError> {
+        self.ops.push(JournalOp::Unmap { id });
+        let events = self.check(|reg| reg.on_unmap(id))?;
+        self.events.extend(events);
+        Ok(())
+    }
+
+    pub fn remove_window(&mut self, id: WindowId) -> Result<WindowRecord, RegistryError> {
+        self.ops.push(JournalOp::Remove { id });
+        let (record, events) = self.check(|reg| reg.remove_window(id))?;
+        self.events.extend(events);
+        Ok(record)
+    }
+
+    pub fn raise(&mut self, id: WindowId) -> Result<(), RegistryError> {
+        self.run(JournalOp::Raise { id })
+    }
+
+    pub fn lower(&mut self, id: WindowId) -> Result<(), RegistryError> {
+        self.run(JournalOp::Lower { id })
+    }
+
+    pub fn raise_above(&mut self, id: WindowId, sibling: WindowId) -> Result<(), RegistryError> {
+        self.run(JournalOp::RaiseAbove { id, sibling })
+    }
+
+    pub fn lower_below(&mut self, id: WindowId, sibling: WindowId) -> Result<(), RegistryError> {
+        self.run(JournalOp::LowerBelow { id, sibling })
+    }
+
+    pub fn create_workspace(&mut self, name: impl Into<String>, output: OutputId) -> Result<WorkspaceId, RegistryError> {
+        let name = name.into();
+        self.ops.push(JournalOp::CreateWorkspace { name: name.clone(), output });
+        let (id, events) = self.check(|reg| reg.create_workspace(name, output))?;
+        self.events.extend(events);
+        Ok(id)
+    }
+
+    pub fn rename_workspace(&mut self, id: WorkspaceId, name: impl Into<String>) -> Result<(), RegistryError> {
+        self.run(JournalOp::RenameWorkspace { id, name: name.into() })
+    }
+
+    pub fn reorder_workspace(&mut self, id: WorkspaceId, index: usize) -> Result<(), RegistryError> {
+        self.run(JournalOp::ReorderWorkspace { id, index })
+    }
+
+    pub fn activate_workspace(&mut self, id: WorkspaceId) -> Result<(), RegistryError> {
+        self.run(JournalOp::ActivateWorkspace { id })
+    }
+
+    pub fn remove_workspace(&mut self, id: WorkspaceId) -> Result<(), RegistryError> {
+        self.run(JournalOp::RemoveWorkspace { id })
+    }
+
+    pub fn add_output(&mut self, output: OutputInfo) -> Result<(), RegistryError> {
+        self.run(JournalOp::AddOutput { output })
+    }
+
+    pub fn update_output(&mut self, id: OutputId, update: OutputUpdate) -> Result<(), RegistryError> {
+        self.run(JournalOp::UpdateOutput { id, update })
+    }
+
+    pub fn remove_output(&mut self, id: OutputId) -> Result<(), RegistryError> {
+        self.run(JournalOp::RemoveOutput { id })
+    }
+
+    pub fn apply_layout(&mut self, workspace: WorkspaceId, layout: Layout, gaps: Gaps) -> Result<(), RegistryError> {
+        self.run(JournalOp::ApplyLayout { workspace, layout, gaps })
+    }
+
+    /// Records and applies an operation that only returns events.
+    fn run(&mut self, op: JournalOp) -> Result<(), RegistryError> {
+        self.ops.push(op.clone());
+        let events = self.check(|reg| op.apply(reg))?;
+        self.events.extend(events);
+        Ok(())
+    }
+
+    /// Replays a journaled operation inside this transaction.
+    pub(crate) fn apply(&mut self, op: &JournalOp) -> Result<(), RegistryError> {
+        match op {
+            JournalOp::Insert { dk, sk } => self.insert_window(*dk, *sk).map(drop),
+            JournalOp::Update { id, update } => self.update_window(*id, update.clone()),
+            JournalOp::Map { id } => self.on_map(*id),
+            JournalOp::Unmap { id } => self.on_unmap(*id),
+            JournalOp::Remove { id } => self.remove_window(*id).map(drop),
+            JournalOp::SetChildRemovalPolicy { policy } => {
+                self.ops.push(op.clone());
+                self.staged.set_child_removal_policy(*policy);
+                Ok(())
+            }
+            JournalOp::CreateWorkspace { name, output } => self.create_workspace(name.clone(), *output).map(drop),
+            JournalOp::Transaction { ops, abandoned } => {
+                ops.iter().try_for_each(|op| self.apply(op))?;
+                match abandoned {
+                    Some(err) => Err(self.failed.insert(err.clone()).clone()),
+                    None => Ok(()),
+                }
+            }
+            other => self.run(other.clone()),
+        }
+    }
+
+    /// The operations requested so far, as one journal entry; `abandoned` is the error the
+    /// transaction's closure gave up with, if it did.
+    pub(crate) fn journal_op(&self, abandoned: Option<&RegistryError>) -> JournalOp {
+        JournalOp::Transaction { ops: self.ops.clone(), abandoned: abandoned.cloned() }
+    }
+
+    /// Read access to the staged state, including every operation applied so far.
+    pub fn staged(&self) -> &Registry {
+        &self.staged
+    }
+
+    /// Applies the batch and returns its coalesced events.
+    ///
+    /// Fails with the first operation error if any operation failed, leaving the registry
+    /// untouched.
+    pub fn commit(self) -> Result<Vec<RegistryEvent>, RegistryError> {
+        if let Some(err) = self.failed {
+            return Err(err);
+        }
+        *self.target = self.staged;
+        Ok(coalesce(self.events))
+    }
+
+    fn check<T, F>(&mut self, op: F) -> Result<T, RegistryError>
+    where
+        F: FnOnce(&mut Registry) -> Result<T, RegistryError>,
+    {
+        if let Some(err) = &self.failed {
+            return Err(err.clone());
+        }
+        op(&mut self.staged).inspect_err(|err| self.failed = Some(err.clone()))
+    }
+}
+
+/// Merges every `WindowChanged` for a window into its first occurrence and drops changes that
+/// cancel out. Any other event is a barrier: changes are never moved across it, so the merged
+/// events still replay in order (a window's stack index, say, depends on the windows created
+/// before it).
+pub(crate) fn coalesce(events: Vec<RegistryEvent>) -> Vec<RegistryEvent> {
+    fn event(e: &mut RegistryEvent) -> &mut RegistryEvent {
+        e
+    }
+    coalesce_by(events, event)
+}
+
+/// `coalesce` for items wrapping an event; a merged item keeps its first occurrence's wrapper.
+pub(crate) fn coalesce_by<T>(items: Vec<T>, event: fn(&mut T) -> &mut RegistryEvent) -> Vec<T> {
+    let mut out: Vec<T> = Vec::with_capacity(items.len());
+    let mut changed_at: HashMap<WindowId, usize> = HashMap::new();
+
+    for mut item in items {
+        if let RegistryEvent::WindowChanged { id, changes } = event(&mut item) {
+            if let Some(&pos) = changed_at.get(id) {
+                let later = std::mem::take(changes);
+                if let RegistryEvent::WindowChanged { changes: existing, .. } = event(&mut out[pos]) {
+                    existing.merge(later);
+                }
+                continue;
+            }
+            changed_at.insert(*id, out.len());
+        } else {
+            changed_at.clear();
+        }
+        out.push(item);
+    }
+
+    out.retain_mut(|item| match event(item) {
+        RegistryEvent::WindowChanged { changes, .. } => !changes.is_empty(),
+        _ => true,
+    });
+    out
+}
@@ -8,25 +8,29 @@ use crate::{
     WindowUpdate,
 };
 
-use super::Registry;
+use super::{index::relink, Registry};
 
 
 impl Registry {
     pub fn update_window(
         &mut self,
         id: crate::WindowId,
-        update: WindowUpdate,
+        mut update: WindowUpdate,
     ) -> Result<Vec<RegistryEvent>, RegistryError> {
         let current = self.get(id).ok_or(RegistryError::InvalidWindowId(id))?;
+        self.restore_placement(current, &mut update);
+        let matched_rules = self.match_update_rules(current, &mut update);
 
         let current_geometry = current.geometry;
         let current_state = current.state;
         let current_focus = current.is_focused;
         let current_workspace = current.workspace;
         let current_output = current.output;
-        let current_stack_index = current.stack_index;
+        let current_layer = current.layer;
         let current_parent_id = current.parent_id;
         let current_children = current.children.clone();
+        let current_modal = current.modal;
+        let current_floating = current.floating;
         let current_title = current.title.clone();
         let current_app_id = current.app_id.clone();
 
@@ -52,12 +56,9 @@ impl Registry {
                 output: next_output,
             });
         }
-
-        if let Some(index) = update.stack_index {
-            let count = self.live_count();
-            if index < 0 || index as usize >= count {
-                return Err(RegistryError::StackIndexOutOfBounds { id, index, count });
-            }
+        if update.workspace.is_some() || update.output.is_some() {
+            self.validate_window_output(id, next_output)?;
+            self.validate_window_workspace(id, next_workspace, next_output)?;
         }
 
         if let Some(next_parent) = update.parent_id {
@@ -107,16 +108,34 @@ impl Registry {
         }
 
         for child_id in &update.remove_children {
-            if self.get(*child_id).is_none() {
-                return Err(RegistryError::ChildNotFound { id, child: *child_id });
+            let child = self
+                .get(*child_id)
+                .ok_or(RegistryError::ChildNotFound { id, child: *child_id })?;
+            if child.modal && child.parent_id == Some(id) {
+                return Err(RegistryError::ModalWithoutParent { id: *child_id });
+            }
+        }
+
+        if update.modal.unwrap_or(current_modal) && update.parent_id.unwrap_or(current_parent_id).is_none() {
+            return Err(RegistryError::ModalWithoutParent { id });
+        }
+
+        // Focus meant for a window behind a modal dialog goes to the dialog instead.
+        let mut focus_redirect = None;
+        if update.is_focused == Some(true) {
+            let target = self.focus_target(id);
+            if target != id {
+                update.is_focused = None;
+                focus_redirect = Some(target);
             }
         }
 
         let mut events = Vec::new();
         let mut changes = WindowChanges::default();
 
-        if update.is_focused == Some(true) && !current_focus {
-            if let Some(other_id) = self.find_focused(Some(id)) {
+        let gaining = focus_redirect.or((update.is_focused == Some(true) && !current_focus).then_some(id));
+        if let Some(gaining) = gaining {
+            if let Some(other_id) = self.find_focused(Some(gaining)) {
                 if let Some(other) = self.get_mut(other_id) {
                     let old = other.is_focused;
                     if old {
@@ -129,44 +148,6 @@ impl Registry {
             }
         }
 
-        if let Some(new_index) = update.stack_index {
-            if new_index != current_stack_index {
-                let mut affected = Vec::new();
-                for slot in &self.slots {
-                    let Some(other) = slot.value.as_ref() else { continue };
-                    if other.id == id {
-                        continue;
-                    }
-                    let idx = other.stack_index;
-                    if current_stack_index < new_index {
-                        if idx > current_stack_index && idx <= new_index {
-                            affected.push((other.id, idx, idx - 1));
-                        }
-                    } else if new_index < current_stack_index {
-                        if idx >= new_index && idx < current_stack_index {
-                            affected.push((other.id, idx, idx + 1));
-                        }
-                    }
-                }
-
-                affected.sort_by_key(|(win_id, idx, _)| (*idx, win_id.index, win_id.gen));
-                for (other_id, old, new) in affected {
-                    if let Some(other) = self.get_mut(other_id) {
-                        other.stack_index = new;
-                        let mut other_changes = WindowChanges::default();
-                        other_changes.stack_index = Some(WindowChange { old, new });
-                        events.push(RegistryEvent::WindowChanged { id: other_id, changes: other_changes });
-                    }
-                }
-
-                if let Some(target) = self.get_mut(id) {
-                    let old = target.stack_index;
-                    target.stack_index = new_index;
-                    changes.stack_index = Some(WindowChange { old, new: new_index });
-                }
-            }
-        }
-
         if let Some(next_parent) = update.parent_id {
             if next_parent != current_parent_id {
                 if let Some(old_parent) = current_parent_id {
@@ -275,14 +256,39 @@ impl Registry {
                 if let Some(target) = self.get_mut(id) {
                     target.is_focused = new_focus;
                 }
+                if new_focus {
+                    self.record_focus(id);
+                }
                 changes.is_focused = Some(WindowChange { old: current_focus, new: new_focus });
             }
         }
+        if let Some(target) = focus_redirect {
+            self.grant_focus(target, &mut events);
+        }
+
+        if let Some(new_modal) = update.modal {
+            if new_modal != current_modal {
+                if let Some(target) = self.get_mut(id) {
+                    target.modal = new_modal;
+                }
+                changes.modal = Some(WindowChange { old: current_modal, new: new_modal });
+            }
+        }
+
+        if let Some(new_floating) = update.floating {
+            if new_floating != current_floating {
+                if let Some(target) = self.get_mut(id) {
+                    target.floating = new_floating;
+                }
+                changes.floating = Some(WindowChange { old: current_floating, new: new_floating });
+            }
+        }
 
         if update.workspace.is_some() && next_workspace != current_workspace {
             if let Some(target) = self.get_mut(id) {
                 target.workspace = next_workspace;
             }
+            relink(&mut self.indexes.workspace, id, current_workspace, next_workspace);
             changes.workspace = Some(WindowChange { old: current_workspace, new: next_workspace });
         }
 
@@ -290,9 +296,23 @@ impl Registry {
             if let Some(target) = self.get_mut(id) {
                 target.output = next_output;
             }
+            relink(&mut self.indexes.output, id, current_output, next_output);
             changes.output = Some(WindowChange { old: current_output, new: next_output });
         }
 
+        let next_layer = update.layer.unwrap_or(current_layer);
+        if next_layer != current_layer {
+            if let Some(target) = self.get_mut(id) {
+                target.layer = next_layer;
+            }
+            changes.layer = Some(WindowChange { old: current_layer, new: next_layer });
+        }
+        let regroups = !update.add_children.is_empty() || !update.remove_children.is_empty();
+        if changes.output.is_some() || changes.layer.is_some() || changes.parent_id.is_some() || regroups {
+            let old_key = (current_output, current_layer);
+            self.restack_after_update(id, old_key, &update.add_children, &mut events, &mut changes);
+        }
+
         if let Some(new_title) = update.title {
             if new_title != current_title {
                 if let Some(target) = self.get_mut(id) {
@@ -307,14 +327,20 @@ impl Registry {
                 if let Some(target) = self.get_mut(id) {
                     target.app_id = new_app_id.clone();
                 }
+                relink(&mut self.indexes.app_id, id, current_app_id.clone(), new_app_id.clone());
                 changes.app_id = Some(WindowChange { old: current_app_id, new: new_app_id });
             }
         }
 
+        let reparented = changes.modal.is_some() || changes.parent_id.is_some();
         if !changes.is_empty() {
             events.push(RegistryEvent::WindowChanged { id, changes });
         }
+        if reparented {
+            self.focus_blocking_modal(&mut events);
+        }
 
+        self.mark_rules_applied(id, matched_rules);
         Ok(events)
     }
 }
@@ -5,7 +5,7 @@ use super::Registry;
 impl Registry {
     pub(crate) fn find_focused(&self, exclude: Option<WindowId>) -> Option<WindowId> {
         for slot in &self.slots {
-            let Some(rec) = slot.value.as_ref() else { continue };
+            let Some(rec) = slot.value.as_deref() else { continue };
             if Some(rec.id) == exclude {
                 continue;
             }
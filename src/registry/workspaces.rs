@@ -0,0 +1,215 @@
+use std::collections::HashMap;
+
+use crate::{
+    OutputId,
+    RegistryError,
+    RegistryEvent,
+    WindowId,
+    WindowUpdate,
+    WorkspaceId,
+    WorkspaceInfo,
+};
+
+use super::Registry;
+
+#[derive(Debug, Clone)]
+pub(crate) struct WorkspaceEntry {
+    pub(crate) id: WorkspaceId,
+    pub(crate) name: String,
+    pub(crate) output: OutputId,
+}
+
+/// Known workspaces in user-visible order, and the active one per output.
+#[derive(Debug, Clone, Default)]
+pub(crate) struct Workspaces {
+    pub(crate) order: Vec<WorkspaceEntry>,
+    pub(crate) active: HashMap<OutputId, WorkspaceId>,
+    /// Ids are never reused, so stale ones fail with `InvalidWorkspaceId`.
+    next_id: u32,
+}
+
+impl Workspaces {
+    pub(crate) fn position(&self, id: WorkspaceId) -> Option<usize> {
+        self.order.iter().position(|ws| ws.id == id)
+    }
+
+    pub(crate) fn get(&self, id: WorkspaceId) -> Option<&WorkspaceEntry> {
+        self.order.iter().find(|ws| ws.id == id)
+    }
+}
+
+impl Registry {
+    /// Every workspace, in order.
+    pub fn workspaces(&self) -> Vec<WorkspaceInfo> {
+        self.workspaces.order.iter().map(|ws| self.workspace_info(ws)).collect()
+    }
+
+    pub fn workspace(&self, id: WorkspaceId) -> Option<WorkspaceInfo> {
+        self.workspaces.get(id).map(|ws| self.workspace_info(ws))
+    }
+
+    pub fn workspace_by_name(&self, name: &str) -> Option<WorkspaceId> {
+        self.workspaces.order.iter().find(|ws| ws.name == name).map(|ws| ws.id)
+    }
+
+    pub fn active_workspace(&self, output: OutputId) -> Option<WorkspaceId> {
+        self.workspaces.active.get(&output).copied()
+    }
+
+    /// Appends a workspace on `output`; it becomes the output's active workspace if the output
+    /// had none.
+    ///
+    /// Once any workspace exists, `update_window` only accepts known workspaces, on their own
+    /// output.
+    pub fn create_workspace(
+        &mut self,
+        name: impl Into<String>,
+        output: OutputId,
+    ) -> Result<(WorkspaceId, Vec<RegistryEvent>), RegistryError> {
+        let name = name.into();
+        if let Some(existing) = self.workspace_by_name(&name) {
+            return Err(RegistryError::WorkspaceNameTaken { name, existing });
+        }
+        if !self.outputs.is_empty() && self.output(output).is_none() {
+            return Err(RegistryError::InvalidOutputId(output));
+        }
+
+        self.workspaces.next_id += 1;
+        let id = WorkspaceId(self.workspaces.next_id);
+        self.workspaces.order.push(WorkspaceEntry { id, name: name.clone(), output });
+
+        let mut events = vec![RegistryEvent::WorkspaceCreated { id, name, output }];
+        if self.active_workspace(output).is_none() {
+            self.set_active_workspace(output, Some(id), &mut events);
+        }
+        Ok((id, events))
+    }
+
+    pub fn rename_workspace(
+        &mut self,
+        id: WorkspaceId,
+        name: impl Into<String>,
+    ) -> Result<Vec<RegistryEvent>, RegistryError> {
+        let name = name.into();
+        let pos = self.workspaces.position(id).ok_or(RegistryError::InvalidWorkspaceId(id))?;
+        if let Some(existing) = self.workspace_by_name(&name) {
+            if existing == id {
+                return Ok(Vec::new());
+            }
+            return Err(RegistryError::WorkspaceNameTaken { name, existing });
+        }
+
+        let old = std::mem::replace(&mut self.workspaces.order[pos].name, name.clone());
+        Ok(vec![RegistryEvent::WorkspaceRenamed { id, old, new: name }])
+    }
+
+    /// Moves a workspace to position `index` of `workspaces()`.
+    pub fn reorder_workspace(&mut self, id: WorkspaceId, index: usize) -> Result<Vec<RegistryEvent>, RegistryError> {
+        let old = self.workspaces.position(id).ok_or(RegistryError::InvalidWorkspaceId(id))?;
+        let count = self.workspaces.order.len();
+        if index >= count {
+            return Err(RegistryError::WorkspaceIndexOutOfBounds { workspace: id, index, count });
+        }
+        if index == old {
+            return Ok(Vec::new());
+        }
+
+        let entry = self.workspaces.order.remove(old);
+        self.workspaces.order.insert(index, entry);
+        Ok(vec![RegistryEvent::WorkspaceReordered { id, old, new: index }])
+    }
+
+    /// Makes `id` the active workspace of its output.
+    pub fn activate_workspace(&mut self, id: WorkspaceId) -> Result<Vec<RegistryEvent>, RegistryError> {
+        let output = self.workspaces.get(id).ok_or(RegistryError::InvalidWorkspaceId(id))?.output;
+        let mut events = Vec::new();
+        self.set_active_workspace(output, Some(id), &mut events);
+        Ok(events)
+    }
+
+    /// Removes a workspace. Its windows move to another workspace on the same output (the next
+    /// one in order, else the previous one), which also takes over as active if needed.
+    ///
+    /// Events: the activation change, one `workspace` change per moved window, then
+    /// `WorkspaceRemoved`. If any window can't be moved nothing changes.
+    pub fn remove_workspace(&mut self, id: WorkspaceId) -> Result<Vec<RegistryEvent>, RegistryError> {
+        let pos = self.workspaces.position(id).ok_or(RegistryError::InvalidWorkspaceId(id))?;
+        let output = self.workspaces.order[pos].output;
+        let order = &self.workspaces.order;
+        let replacement = order[pos + 1..]
+            .iter()
+            .chain(order[..pos].iter().rev())
+            .find(|ws| ws.output == output)
+            .map(|ws| ws.id);
+        let windows: Vec<WindowId> = self.windows_on_workspace(id).to_vec();
+        if replacement.is_none() && !windows.is_empty() {
+            return Err(RegistryError::WorkspaceNotEmpty { workspace: id });
+        }
+
+        self.all_or_nothing(|reg| {
+            let mut events = Vec::new();
+            if reg.active_workspace(output) == Some(id) {
+                reg.set_active_workspace(output, replacement, &mut events);
+            }
+            for window in windows {
+                let update = WindowUpdate { workspace: Some(replacement), ..WindowUpdate::default() };
+                events.extend(reg.update_window(window, update)?);
+            }
+
+            reg.workspaces.order.remove(pos);
+            events.push(RegistryEvent::WorkspaceRemoved { id });
+            Ok(events)
+        })
+    }
+
+    /// Moves every workspace of `from` to `to`, for output removal. `from` loses its active
+    /// workspace, which becomes active on `to` if `to` had none.
+    pub(crate) fn move_workspaces(&mut self, from: OutputId, to: OutputId, events: &mut Vec<RegistryEvent>) {
+        for ws in self.workspaces.order.iter_mut().filter(|ws| ws.output == from) {
+            ws.output = to;
+            events.push(RegistryEvent::WorkspaceMoved { id: ws.id, old: from, new: to });
+        }
+        let Some(active) = self.active_workspace(from) else { return };
+        self.set_active_workspace(from, None, events);
+        if self.active_workspace(to).is_none() {
+            self.set_active_workspace(to, Some(active), events);
+        }
+    }
+
+    /// Checks a window's next workspace against the known workspaces, if any are defined.
+    pub(crate) fn validate_window_workspace(
+        &self,
+        id: WindowId,
+        workspace: Option<WorkspaceId>,
+        output: Option<OutputId>,
+    ) -> Result<(), RegistryError> {
+        let Some(workspace) = workspace else { return Ok(()) };
+        if self.workspaces.order.is_empty() {
+            return Ok(());
+        }
+        let entry = self.workspaces.get(workspace).ok_or(RegistryError::UnknownWorkspace { id, workspace })?;
+        if Some(entry.output) != output {
+            return Err(RegistryError::WorkspaceOutputMismatch { id, workspace: Some(workspace), output });
+        }
+        Ok(())
+    }
+
+    fn set_active_workspace(&mut self, output: OutputId, next: Option<WorkspaceId>, events: &mut Vec<RegistryEvent>) {
+        let old = match next {
+            Some(id) => self.workspaces.active.insert(output, id),
+            None => self.workspaces.active.remove(&output),
+        };
+        if old != next {
+            events.push(RegistryEvent::WorkspaceActivated { output, old, new: next });
+        }
+    }
+
+    fn workspace_info(&self, ws: &WorkspaceEntry) -> WorkspaceInfo {
+        WorkspaceInfo {
+            id: ws.id,
+            name: ws.name.clone(),
+            output: ws.output,
+            active: self.active_workspace(ws.output) == Some(ws.id),
+        }
+    }
+}
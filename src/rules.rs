@@ -0,0 +1,88 @@
+use regex::Regex;
+
+use crate::{
+    OutputId,
+    RegistryError,
+    WindowGeometry,
+    WindowState,
+    WorkspaceId,
+};
+
+/// Selects the windows a `WindowRule` applies to.
+///
+/// Every criterion that is set must match; the default matches every window.
+#[derive(Debug, Clone, Default)]
+pub struct RuleMatch {
+    /// Exact `app_id`.
+    pub app_id: Option<String>,
+    /// Searched in the title; a window without a title never matches.
+    pub title: Option<Regex>,
+    /// Whether the window must (`true`) or must not (`false`) have a parent.
+    pub has_parent: Option<bool>,
+    pub output: Option<OutputId>,
+}
+
+impl RuleMatch {
+    pub fn app_id(app_id: impl Into<String>) -> Self {
+        Self { app_id: Some(app_id.into()), ..Self::default() }
+    }
+
+    pub fn with_title(mut self, pattern: &str) -> Result<Self, RegistryError> {
+        let regex = Regex::new(pattern).map_err(|err| RegistryError::InvalidRulePattern {
+            pattern: pattern.to_string(),
+            message: err.to_string(),
+        })?;
+        self.title = Some(regex);
+        Ok(self)
+    }
+
+    pub fn with_parent(mut self, has_parent: bool) -> Self {
+        self.has_parent = Some(has_parent);
+        self
+    }
+
+    pub fn on_output(mut self, output: OutputId) -> Self {
+        self.output = Some(output);
+        self
+    }
+
+    pub(crate) fn matches(&self, view: &RuleSubject<'_>) -> bool {
+        self.app_id.as_deref().is_none_or(|app_id| view.app_id == Some(app_id))
+            && self.title.as_ref().is_none_or(|re| view.title.is_some_and(|title| re.is_match(title)))
+            && self.has_parent.is_none_or(|has_parent| view.has_parent == has_parent)
+            && self.output.is_none_or(|output| view.output == Some(output))
+    }
+}
+
+/// What a matching `WindowRule` does. Actions override the fields of the triggering update.
+#[derive(Debug, Clone, Default)]
+pub struct RuleActions {
+    /// Workspace and output, assigned together.
+    pub place: Option<(WorkspaceId, OutputId)>,
+    /// Geometry forced on the window, which also becomes `floating` so layouts keep it.
+    pub geometry: Option<WindowGeometry>,
+    pub state: Option<WindowState>,
+    /// Never focus the window automatically: a focus request in the triggering update is
+    /// dropped and the focus fallback chain skips it.
+    pub skip_focus: bool,
+}
+
+#[derive(Debug, Clone, Default)]
+pub struct WindowRule {
+    pub matches: RuleMatch,
+    pub actions: RuleActions,
+}
+
+impl WindowRule {
+    pub fn new(matches: RuleMatch, actions: RuleActions) -> Self {
+        Self { matches, actions }
+    }
+}
+
+/// The window fields rules match on, as they will be once the triggering operation applies.
+pub(crate) struct RuleSubject<'a> {
+    pub app_id: Option<&'a str>,
+    pub title: Option<&'a str>,
+    pub has_parent: bool,
+    pub output: Option<OutputId>,
+}
@@ -2,17 +2,27 @@ use std::sync::{Arc, RwLock};
 
 use crate::{
     Registry, RegistryEvent, RegistryError,
-    DesktopKey, RegistryEventQueue, SurfaceKey, WindowId, WindowInfo, WindowUpdate,
+    ChildRemovalPolicy, DesktopKey, EventOperation, Gaps, JournalOp, JournalSink, Layout, OutputId, OutputInfo, PlacementStore, Query,
+    RegistryEventQueue, RegistryEventReceiver, SubscribeOptions, SurfaceKey, Transaction, WindowGeometry, WindowId,
+    WindowInfo, WindowUpdate, WorkspaceId, WorkspaceInfo,
 };
+use crate::{event_filter::Placement, journal::JournalHandle};
 
 #[derive(Clone, Debug)]
 pub struct SharedRegistry {
     inner: Arc<RwLock<Registry>>,
+    journal: Option<JournalHandle>,
 }
 
 impl SharedRegistry {
     pub fn new(reg: Registry) -> Self {
-        Self { inner: Arc::new(RwLock::new(reg)) }
+        Self { inner: Arc::new(RwLock::new(reg)), journal: None }
+    }
+
+    /// Like `new`, but every mutation (including failed ones) is recorded to `journal` in the
+    /// order it is applied.
+    pub fn with_journal(reg: Registry, journal: impl JournalSink + 'static) -> Self {
+        Self { inner: Arc::new(RwLock::new(reg)), journal: Some(JournalHandle::new(journal)) }
     }
 
     // READ snapshots
@@ -26,6 +36,33 @@ impl SharedRegistry {
         r.snapshot_all()
     }
 
+    /// Takes a snapshot and subscribes to `queue` atomically: the receiver gets exactly the
+    /// events of `*_queued` mutations applied after the snapshot, with no gap or duplicates.
+    /// The snapshot also seeds the placements a workspace/output filter matches against.
+    pub fn subscribe_with_snapshot(
+        &self,
+        queue: &RegistryEventQueue,
+    ) -> (Vec<WindowInfo>, RegistryEventReceiver) {
+        self.subscribe_with_snapshot_options(queue, SubscribeOptions::default())
+    }
+
+    pub fn subscribe_with_snapshot_options(
+        &self,
+        queue: &RegistryEventQueue,
+        options: SubscribeOptions,
+    ) -> (Vec<WindowInfo>, RegistryEventReceiver) {
+        // `*_queued` writers reserve their batch under the write lock, so while the read lock
+        // is held every earlier batch is already reserved and every later one is not.
+        let r = self.inner.read().expect("registry lock poisoned");
+        let snapshot = r.snapshot_all();
+        let seed = snapshot
+            .iter()
+            .map(|info| (info.id, Placement { workspace: info.workspace, output: info.output }))
+            .collect();
+        let receiver = queue.subscribe_from(options, queue.next_batch(), seed);
+        (snapshot, receiver)
+    }
+
     pub fn from_desktop(&self, dk: DesktopKey) -> Option<WindowId> {
         let r = self.inner.read().expect("registry lock poisoned");
         r.from_desktop(dk)
@@ -36,7 +73,114 @@ impl SharedRegistry {
         r.from_surface(sk)
     }
 
-    // WRITE + dispatch after unlock
+    pub fn query(&self, query: &Query) -> Vec<WindowId> {
+        let r = self.inner.read().expect("registry lock poisoned");
+        r.query(query)
+    }
+
+    pub fn windows_on_workspace(&self, workspace: WorkspaceId) -> Vec<WindowId> {
+        let r = self.inner.read().expect("registry lock poisoned");
+        r.windows_on_workspace(workspace).to_vec()
+    }
+
+    pub fn windows_on_output(&self, output: OutputId) -> Vec<WindowId> {
+        let r = self.inner.read().expect("registry lock poisoned");
+        r.windows_on_output(output).to_vec()
+    }
+
+    pub fn windows_with_app_id(&self, app_id: &str) -> Vec<WindowId> {
+        let r = self.inner.read().expect("registry lock poisoned");
+        r.windows_with_app_id(app_id).to_vec()
+    }
+
+    /// Workspaces are changed through transactions (`Transaction::create_workspace`, ...).
+    pub fn workspaces(&self) -> Vec<WorkspaceInfo> {
+        let r = self.inner.read().expect("registry lock poisoned");
+        r.workspaces()
+    }
+
+    pub fn workspace_by_name(&self, name: &str) -> Option<WorkspaceId> {
+        let r = self.inner.read().expect("registry lock poisoned");
+        r.workspace_by_name(name)
+    }
+
+    pub fn active_workspace(&self, output: OutputId) -> Option<WorkspaceId> {
+        let r = self.inner.read().expect("registry lock poisoned");
+        r.active_workspace(output)
+    }
+
+    /// Restacking goes through transactions (`Transaction::raise`, ...).
+    pub fn stacking_order(&self, output: OutputId) -> Vec<WindowId> {
+        let r = self.inner.read().expect("registry lock poisoned");
+        r.stacking_order(output)
+    }
+
+    /// Outputs are changed through transactions (`Transaction::add_output`, ...).
+    pub fn outputs(&self) -> Vec<OutputInfo> {
+        let r = self.inner.read().expect("registry lock poisoned");
+        r.outputs().to_vec()
+    }
+
+    pub fn output(&self, id: OutputId) -> Option<OutputInfo> {
+        let r = self.inner.read().expect("registry lock poisoned");
+        r.output(id).cloned()
+    }
+
+    pub fn output_by_name(&self, name: &str) -> Option<OutputId> {
+        let r = self.inner.read().expect("registry lock poisoned");
+        r.output_by_name(name)
+    }
+
+    pub fn tiled_windows(&self, workspace: WorkspaceId) -> Vec<WindowId> {
+        let r = self.inner.read().expect("registry lock poisoned");
+        r.tiled_windows(workspace)
+    }
+
+    /// Layouts are applied through transactions (`Transaction::apply_layout`).
+    pub fn compute_layout(
+        &self,
+        workspace: WorkspaceId,
+        layout: Layout,
+        gaps: Gaps,
+    ) -> Result<Vec<(WindowId, WindowGeometry)>, RegistryError> {
+        let r = self.inner.read().expect("registry lock poisoned");
+        r.compute_layout(workspace, layout, gaps)
+    }
+
+    pub fn is_input_blocked(&self, id: WindowId) -> bool {
+        let r = self.inner.read().expect("registry lock poisoned");
+        r.is_input_blocked(id)
+    }
+
+    pub fn input_blocked_windows(&self) -> Vec<WindowId> {
+        let r = self.inner.read().expect("registry lock poisoned");
+        r.input_blocked_windows()
+    }
+
+    pub fn focus_history(&self) -> Vec<WindowId> {
+        let r = self.inner.read().expect("registry lock poisoned");
+        r.focus_history().to_vec()
+    }
+
+    pub fn focus_history_on_workspace(&self, workspace: WorkspaceId) -> Vec<WindowId> {
+        let r = self.inner.read().expect("registry lock poisoned");
+        r.focus_history_on_workspace(workspace)
+    }
+
+    pub fn focus_history_on_output(&self, output: OutputId) -> Vec<WindowId> {
+        let r = self.inner.read().expect("registry lock poisoned");
+        r.focus_history_on_output(output)
+    }
+
+    /// A copy of the placement memory, e.g. to persist it at shutdown.
+    pub fn placement_store(&self) -> Option<PlacementStore> {
+        let r = self.inner.read().expect("registry lock poisoned");
+        r.placement_store().cloned()
+    }
+
+    // WRITE + dispatch after unlock. The `*_queued` variants reserve their batch's place in
+    // the queue while still holding the write lock, so subscribers see batches in the order
+    // the mutations were applied.
     pub fn insert_window_with<F>(
         &self,
         dk: DesktopKey,
@@ -48,7 +192,10 @@ impl SharedRegistry {
     {
         let (id, events) = {
             let mut r = self.inner.write().expect("registry lock poisoned");
-            r.insert_window(dk, sk)?
+            let op = self.journal_op(|| JournalOp::Insert { dk, sk });
+            let result = r.insert_window(dk, sk);
+            self.record(op, result.as_ref().map(|(_, events)| events.as_slice()));
+            result?
         }; // unlock here
 
         dispatch(events);
@@ -65,7 +212,10 @@ impl SharedRegistry {
     {
         let events = {
             let mut r = self.inner.write().expect("registry lock poisoned");
-            let (_record, events) = r.remove_window(id)?;
+            let op = self.journal_op(|| JournalOp::Remove { id });
+            let result = r.remove_window(id);
+            self.record(op, result.as_ref().map(|(_, events)| events.as_slice()));
+            let (_record, events) = result?;
             events
         }; // unlock
 
@@ -79,7 +229,10 @@ impl SharedRegistry {
     {
         let events = {
             let mut r = self.inner.write().expect("registry lock poisoned");
-            r.on_map(id)?
+            let op = self.journal_op(|| JournalOp::Map { id });
+            let result = r.on_map(id);
+            self.record(op, result.as_deref());
+            result?
         };
 
         dispatch(events);
@@ -92,7 +245,10 @@ impl SharedRegistry {
     {
         let events = {
             let mut r = self.inner.write().expect("registry lock poisoned");
-            r.on_unmap(id)?
+            let op = self.journal_op(|| JournalOp::Unmap { id });
+            let result = r.on_unmap(id);
+            self.record(op, result.as_deref());
+            result?
         };
 
         dispatch(events);
@@ -110,7 +266,10 @@ impl SharedRegistry {
     {
         let events = {
             let mut r = self.inner.write().expect("registry lock poisoned");
-            r.update_window(id, update)?
+            let op = self.journal_op(|| JournalOp::Update { id, update: update.clone() });
+            let result = r.update_window(id, update);
+            self.record(op, result.as_deref());
+            result?
         };
 
         dispatch(events);
@@ -123,12 +282,16 @@ impl SharedRegistry {
         sk: SurfaceKey,
         queue: &RegistryEventQueue,
     ) -> Result<WindowId, RegistryError> {
-        let (id, events) = {
+        let (id, events, ticket) = {
             let mut r = self.inner.write().expect("registry lock poisoned");
-            r.insert_window(dk, sk)?
+            let op = self.journal_op(|| JournalOp::Insert { dk, sk });
+            let result = r.insert_window(dk, sk);
+            self.record(op, result.as_ref().map(|(_, events)| events.as_slice()));
+            let (id, events) = result?;
+            (id, events, queue.reserve_for(EventOperation::Insert))
         };
 
-        queue.send(events)?;
+        ticket.send(events)?;
         Ok(id)
     }
 
@@ -137,13 +300,16 @@ impl SharedRegistry {
         id: WindowId,
         queue: &RegistryEventQueue,
     ) -> Result<(), RegistryError> {
-        let events = {
+        let (events, ticket) = {
             let mut r = self.inner.write().expect("registry lock poisoned");
-            let (_record, events) = r.remove_window(id)?;
-            events
+            let op = self.journal_op(|| JournalOp::Remove { id });
+            let result = r.remove_window(id);
+            self.record(op, result.as_ref().map(|(_, events)| events.as_slice()));
+            let (_record, events) = result?;
+            (events, queue.reserve_for(EventOperation::Remove))
         };
 
-        queue.send(events)?;
+        ticket.send(events)?;
         Ok(())
     }
 
@@ -152,12 +318,15 @@ impl SharedRegistry {
         id: WindowId,
         queue: &RegistryEventQueue,
     ) -> Result<(), RegistryError> {
-        let events = {
+        let (events, ticket) = {
             let mut r = self.inner.write().expect("registry lock poisoned");
-            r.on_map(id)?
+            let op = self.journal_op(|| JournalOp::Map { id });
+            let result = r.on_map(id);
+            self.record(op, result.as_deref());
+            (result?, queue.reserve_for(EventOperation::Map))
         };
 
-        queue.send(events)?;
+        ticket.send(events)?;
         Ok(())
     }
 
@@ -166,12 +335,15 @@ impl SharedRegistry {
         id: WindowId,
         queue: &RegistryEventQueue,
     ) -> Result<(), RegistryError> {
-        let events = {
+        let (events, ticket) = {
             let mut r = self.inner.write().expect("registry lock poisoned");
-            r.on_unmap(id)?
+            let op = self.journal_op(|| JournalOp::Unmap { id });
+            let result = r.on_unmap(id);
+            self.record(op, result.as_deref());
+            (result?, queue.reserve_for(EventOperation::Unmap))
         };
 
-        queue.send(events)?;
+        ticket.send(events)?;
         Ok(())
     }
 
@@ -181,15 +353,93 @@ impl SharedRegistry {
         update: WindowUpdate,
         queue: &RegistryEventQueue,
     ) -> Result<(), RegistryError> {
-        let events = {
+        let (events, ticket) = {
             let mut r = self.inner.write().expect("registry lock poisoned");
-            r.update_window(id, update)?
+            let op = self.journal_op(|| JournalOp::Update { id, update: update.clone() });
+            let result = r.update_window(id, update);
+            self.record(op, result.as_deref());
+            (result?, queue.reserve_for(EventOperation::Update))
         };
 
-        queue.send(events)?;
+        ticket.send(events)?;
         Ok(())
     }
 
+    /// Runs `build` against a registry transaction under a single write lock and dispatches
+    /// the coalesced events after unlocking. If `build` or the commit fails nothing is applied;
+    /// either way the staged operations are journaled with the error.
+    pub fn transaction_with<T, B, F>(&self, build: B, mut dispatch: F) -> Result<T, RegistryError>
+    where
+        B: FnOnce(&mut Transaction<'_>) -> Result<T, RegistryError>,
+        F: FnMut(Vec<RegistryEvent>),
+    {
+        let (value, events) = {
+            let mut r = self.inner.write().expect("registry lock poisoned");
+            let mut tx = r.transaction();
+            let value = match build(&mut tx) {
+                Ok(value) => value,
+                Err(err) => {
+                    self.record(self.journal_op(|| tx.journal_op(Some(&err))), Err(&err));
+                    return Err(err);
+                }
+            };
+            let op = self.journal_op(|| tx.journal_op(None));
+            let result = tx.commit();
+            self.record(op, result.as_deref());
+            (value, result?)
+        };
+
+        dispatch(events);
+        Ok(value)
+    }
+
+    pub fn transaction_queued<T, B>(
+        &self,
+        build: B,
+        queue: &RegistryEventQueue,
+    ) -> Result<T, RegistryError>
+    where
+        B: FnOnce(&mut Transaction<'_>) -> Result<T, RegistryError>,
+    {
+        let (value, events, ticket) = {
+            let mut r = self.inner.write().expect("registry lock poisoned");
+            let mut tx = r.transaction();
+            let value = match build(&mut tx) {
+                Ok(value) => value,
+                Err(err) => {
+                    self.record(self.journal_op(|| tx.journal_op(Some(&err))), Err(&err));
+                    return Err(err);
+                }
+            };
+            let op = self.journal_op(|| tx.journal_op(None));
+            let result = tx.commit();
+            self.record(op, result.as_deref());
+            (value, result?, queue.reserve_for(EventOperation::Transaction))
+        };
+
+        ticket.send(events)?;
+        Ok(value)
+    }
+
+    /// Journaled, unlike `Registry::set_child_removal_policy`; it produces no events.
+    pub fn set_child_removal_policy(&self, policy: ChildRemovalPolicy) {
+        let mut r = self.inner.write().expect("registry lock poisoned");
+        r.set_child_removal_policy(policy);
+        self.record(self.journal_op(|| JournalOp::SetChildRemovalPolicy { policy }), Ok(&[]));
+    }
+
+    /// Builds the journal entry for an operation, only when a journal is attached.
+    fn journal_op(&self, op: impl FnOnce() -> JournalOp) -> Option<JournalOp> {
+        self.journal.as_ref().map(|_| op())
+    }
+
+    /// Called with the write lock held, so entries are in application order.
+    fn record(&self, op: Option<JournalOp>, outcome: Result<&[RegistryEvent], &RegistryError>) {
+        if let (Some(journal), Some(op)) = (&self.journal, op) {
+            journal.record(op, outcome);
+        }
+    }
+
     #[cfg(any(feature = "test-utils", feature = "test-access"))]
     pub fn poison_for_test(&self) {
         let reg_clone = self.clone();
@@ -1,11 +1,21 @@
-use std::collections::{HashMap, HashSet};
+use std::{
+    collections::{HashMap, HashSet},
+    ops::Deref,
+    path::{Path, PathBuf},
+    sync::atomic::{AtomicUsize, Ordering},
+};
 
 use window_registry::{
     DesktopKey,
     LifecycleState,
+    OutputId,
+    Registry,
+    RegistryEvent,
     SharedRegistry,
     SurfaceKey,
     WindowId,
+    WindowUpdate,
+    WorkspaceId,
     weston_desktop_surface,
     weston_surface,
 };
@@ -36,6 +46,86 @@ impl Drop for TestPtrs {
     }
 }
 
+/// A unique socket path in the temp dir, removed when dropped. Servers spawned in tests are
+/// never dropped, so their sockets would otherwise pile up.
+#[allow(dead_code)]
+pub struct TempSocket(PathBuf);
+
+#[allow(dead_code)]
+impl TempSocket {
+    pub fn new(name: &str) -> Self {
+        static NEXT: AtomicUsize = AtomicUsize::new(0);
+        let n = NEXT.fetch_add(1, Ordering::Relaxed);
+        let path = std::env::temp_dir().join(format!("wreg-{name}-{}-{n}.sock", std::process::id()));
+        // Left over by an earlier run that had the same pid.
+        let _ = std::fs::remove_file(&path);
+        Self(path)
+    }
+}
+
+impl Deref for TempSocket {
+    type Target = Path;
+
+    fn deref(&self) -> &Path {
+        &self.0
+    }
+}
+
+impl AsRef<Path> for TempSocket {
+    fn as_ref(&self) -> &Path {
+        &self.0
+    }
+}
+
+impl Drop for TempSocket {
+    fn drop(&mut self) {
+        let _ = std::fs::remove_file(&self.0);
+    }
+}
+
+#[allow(dead_code)]
+pub fn insert(reg: &mut Registry, p: &TestPtrs) -> WindowId {
+    let (dk, sk) = unsafe { p.keys() };
+    reg.insert_window(dk, sk).expect("insert_window should succeed").0
+}
+
+#[allow(dead_code)]
+pub fn insert_mapped(reg: &mut Registry, p: &TestPtrs) -> WindowId {
+    let id = insert(reg, p);
+    reg.on_map(id).expect("on_map should succeed");
+    id
+}
+
+#[allow(dead_code)]
+pub fn focus(reg: &mut Registry, id: WindowId) -> Vec<RegistryEvent> {
+    let update = WindowUpdate { is_focused: Some(true), ..WindowUpdate::default() };
+    reg.update_window(id, update).expect("focus should succeed")
+}
+
+#[allow(dead_code)]
+pub fn place(reg: &mut Registry, id: WindowId, workspace: u32, output: u32) {
+    let update = WindowUpdate {
+        workspace: Some(Some(WorkspaceId(workspace))),
+        output: Some(Some(OutputId(output))),
+        ..WindowUpdate::default()
+    };
+    reg.update_window(id, update).expect("place should succeed");
+}
+
+/// `(id, old, new)` for every stack index change in `events`.
+#[allow(dead_code)]
+pub fn stack_moves(events: &[RegistryEvent]) -> Vec<(WindowId, i32, i32)> {
+    events
+        .iter()
+        .filter_map(|event| match event {
+            RegistryEvent::WindowChanged { id, changes } => {
+                changes.stack_index.as_ref().map(|change| (*id, change.old, change.new))
+            }
+            _ => None,
+        })
+        .collect()
+}
+
 /// Invariants that should hold no matter what your higher-level policy is.
 #[allow(dead_code)]
 pub fn assert_shared_registry_hard_invariants(reg: &SharedRegistry) {
@@ -0,0 +1,141 @@
+#![cfg(feature = "async")]
+
+use std::{
+    future::Future,
+    pin::{pin, Pin},
+    sync::Arc,
+    task::{Context, Poll, Wake, Waker},
+    thread::{self, Thread},
+    time::Duration,
+};
+
+use futures_core::Stream;
+use window_registry::{
+    OverflowPolicy,
+    Registry,
+    RegistryError,
+    RegistryEvent,
+    RegistryEventQueue,
+    RegistryEventReceiver,
+    SubscribeOptions,
+    WindowId,
+};
+
+mod common;
+use common::TestPtrs;
+
+/// Minimal single-future executor: parks the thread until the waker fires.
+struct ThreadWaker(Thread);
+
+impl Wake for ThreadWaker {
+    fn wake(self: Arc<Self>) {
+        self.0.unpark();
+    }
+}
+
+fn block_on<F: Future>(future: F) -> F::Output {
+    let mut future = pin!(future);
+    let waker = Waker::from(Arc::new(ThreadWaker(thread::current())));
+    let mut cx = Context::from_waker(&waker);
+    loop {
+        if let Poll::Ready(output) = future.as_mut().poll(&mut cx) {
+            return output;
+        }
+        thread::park();
+    }
+}
+
+fn next(
+    rx: &mut RegistryEventReceiver,
+) -> impl Future<Output = Option<Result<RegistryEvent, RegistryError>>> + '_ {
+    std::future::poll_fn(move |cx| Pin::new(&mut *rx).poll_next(cx))
+}
+
+fn window_id() -> (TestPtrs, WindowId) {
+    let mut reg = Registry::new();
+    let p = TestPtrs::new();
+    let (dk, sk) = unsafe { p.keys() };
+    let id = reg.insert_window(dk, sk).expect("insert").0;
+    (p, id)
+}
+
+#[test]
+fn recv_async_wakes_on_send_from_another_thread() {
+    let (_p, id) = window_id();
+    let queue = RegistryEventQueue::unbounded();
+    let rx = queue.subscribe();
+
+    let sender = thread::spawn(move || {
+        thread::sleep(Duration::from_millis(20));
+        queue.send(vec![RegistryEvent::WindowDestroyed { id }]).expect("send");
+        queue
+    });
+
+    let event = block_on(rx.recv_async()).expect("event");
+    assert_eq!(event.window(), Some(id));
+    drop(sender.join().expect("sender should finish"));
+}
+
+#[test]
+fn stream_yields_events_then_ends_when_queue_closes() {
+    let (_p, id) = window_id();
+    let queue = RegistryEventQueue::unbounded();
+    let mut rx = queue.subscribe();
+
+    let sender = thread::spawn(move || {
+        for _ in 0..3 {
+            thread::sleep(Duration::from_millis(5));
+            queue.send(vec![RegistryEvent::WindowDestroyed { id }]).expect("send");
+        }
+    });
+
+    let received = block_on(async {
+        let mut count = 0;
+        while let Some(item) = next(&mut rx).await {
+            assert_eq!(item.expect("no lag").window(), Some(id));
+            count += 1;
+        }
+        count
+    });
+    sender.join().expect("sender should finish");
+    assert_eq!(received, 3);
+}
+
+#[test]
+fn stream_reports_lag() {
+    let (_p, id) = window_id();
+    let queue = RegistryEventQueue::bounded(1);
+    let mut rx = queue.subscribe_with_options(SubscribeOptions {
+        overflow: OverflowPolicy::DropNewest,
+        ..SubscribeOptions::default()
+    });
+    queue
+        .send(vec![RegistryEvent::WindowDestroyed { id }, RegistryEvent::WindowDestroyed { id }])
+        .expect("send");
+    drop(queue);
+
+    let items = block_on(async {
+        let mut items = Vec::new();
+        while let Some(item) = next(&mut rx).await {
+            items.push(item);
+        }
+        items
+    });
+    assert_eq!(items.len(), 2);
+    assert!(items[0].is_ok());
+    assert!(matches!(items[1], Err(RegistryError::EventQueueLagged { missed: 1 })));
+}
+
+#[test]
+fn recv_async_reports_close_of_pending_receiver() {
+    let queue = RegistryEventQueue::unbounded();
+    let rx = queue.subscribe();
+
+    let closer = thread::spawn(move || {
+        thread::sleep(Duration::from_millis(20));
+        drop(queue);
+    });
+
+    assert!(matches!(block_on(rx.recv_async()), Err(RegistryError::EventQueueClosed)));
+    closer.join().expect("closer should finish");
+}
@@ -0,0 +1,213 @@
+use window_registry::{
+    OverflowPolicy,
+    Registry,
+    RegistryError,
+    RegistryEvent,
+    RegistryEventQueue,
+    RegistryEventReceiver,
+    SubscribeOptions,
+    WindowChange,
+    WindowChanges,
+    WindowId,
+};
+
+mod common;
+use common::TestPtrs;
+
+fn subscribe(queue: &RegistryEventQueue, overflow: OverflowPolicy) -> RegistryEventReceiver {
+    queue.subscribe_with_options(SubscribeOptions { overflow, ..SubscribeOptions::default() })
+}
+
+fn window_ids(count: usize) -> (Vec<TestPtrs>, Vec<WindowId>) {
+    let mut reg = Registry::new();
+    let ptrs: Vec<TestPtrs> = (0..count).map(|_| TestPtrs::new()).collect();
+    let ids = ptrs
+        .iter()
+        .map(|p| {
+            let (dk, sk) = unsafe { p.keys() };
+            reg.insert_window(dk, sk).expect("insert").0
+        })
+        .collect();
+    (ptrs, ids)
+}
+
+fn destroyed(id: WindowId) -> RegistryEvent {
+    RegistryEvent::WindowDestroyed { id }
+}
+
+fn title_changed(id: WindowId, old: &str, new: &str) -> RegistryEvent {
+    let changes = WindowChanges {
+        title: Some(WindowChange { old: Some(old.to_string()), new: Some(new.to_string()) }),
+        ..WindowChanges::default()
+    };
+    RegistryEvent::WindowChanged { id, changes }
+}
+
+/// Receives until the queue is empty, recording lag markers as `Err(missed)`.
+fn drain(receiver: &RegistryEventReceiver) -> Vec<Result<WindowId, u64>> {
+    let mut out = Vec::new();
+    loop {
+        match receiver.try_recv() {
+            Ok(Some(event)) => out.push(Ok(event.window().expect("window event"))),
+            Ok(None) | Err(RegistryError::EventQueueClosed) => return out,
+            Err(RegistryError::EventQueueLagged { missed }) => out.push(Err(missed)),
+            Err(err) => panic!("unexpected error: {err:?}"),
+        }
+    }
+}
+
+#[test]
+fn drop_newest_keeps_buffer_and_reports_gap() {
+    let (_ptrs, ids) = window_ids(4);
+    let queue = RegistryEventQueue::bounded(2);
+    let rx = subscribe(&queue, OverflowPolicy::DropNewest);
+
+    queue.send(ids.iter().copied().map(destroyed).collect()).expect("send should not block");
+
+    assert_eq!(drain(&rx), vec![Ok(ids[0]), Ok(ids[1]), Err(2)]);
+
+    queue.send(vec![destroyed(ids[3])]).expect("send");
+    assert_eq!(drain(&rx), vec![Ok(ids[3])]);
+}
+
+#[test]
+fn drop_oldest_keeps_latest_events() {
+    let (_ptrs, ids) = window_ids(5);
+    let queue = RegistryEventQueue::bounded(3);
+    let rx = subscribe(&queue, OverflowPolicy::DropOldest);
+
+    queue.send(ids.iter().copied().map(destroyed).collect()).expect("send should not block");
+
+    assert_eq!(drain(&rx), vec![Err(2), Ok(ids[2]), Ok(ids[3]), Ok(ids[4])]);
+}
+
+#[test]
+fn coalesce_merges_changes_per_window() {
+    let (_ptrs, ids) = window_ids(2);
+    let (a, b) = (ids[0], ids[1]);
+    let queue = RegistryEventQueue::bounded(2);
+    let rx = subscribe(&queue, OverflowPolicy::CoalesceChanges);
+
+    queue
+        .send(vec![
+            title_changed(a, "a0", "a1"),
+            title_changed(b, "b0", "b1"),
+            title_changed(a, "a1", "a2"),
+            title_changed(b, "b1", "b2"),
+            title_changed(a, "a2", "a3"),
+        ])
+        .expect("send should not block");
+
+    let events: Vec<RegistryEvent> = std::iter::from_fn(|| rx.try_recv().expect("no lag")).collect();
+    assert_eq!(events.len(), 2);
+    let titles: Vec<(WindowId, Option<String>, Option<String>)> = events
+        .into_iter()
+        .map(|event| match event {
+            RegistryEvent::WindowChanged { id, changes } => {
+                let title = changes.title.expect("title change");
+                (id, title.old, title.new)
+            }
+            other => panic!("unexpected event: {other:?}"),
+        })
+        .collect();
+    assert_eq!(
+        titles,
+        vec![
+            (a, Some("a0".to_string()), Some("a3".to_string())),
+            (b, Some("b0".to_string()), Some("b2".to_string())),
+        ]
+    );
+}
+
+#[test]
+fn coalesce_falls_back_to_dropping_oldest() {
+    let (_ptrs, ids) = window_ids(4);
+    let queue = RegistryEventQueue::bounded(2);
+    let rx = subscribe(&queue, OverflowPolicy::CoalesceChanges);
+
+    queue.send(ids.iter().copied().map(destroyed).collect()).expect("send should not block");
+
+    assert_eq!(drain(&rx), vec![Err(2), Ok(ids[2]), Ok(ids[3])]);
+}
+
+#[test]
+fn concurrent_receiver_sees_increasing_sequences() {
+    let (_ptrs, ids) = window_ids(4);
+    for overflow in [OverflowPolicy::DropNewest, OverflowPolicy::DropOldest, OverflowPolicy::CoalesceChanges] {
+        let queue = RegistryEventQueue::bounded(4);
+        let rx = subscribe(&queue, overflow);
+        let reader = std::thread::spawn(move || {
+            let mut sequences = Vec::new();
+            loop {
+                match rx.recv_envelope() {
+                    Ok(envelope) => sequences.push(envelope.sequence),
+                    Err(RegistryError::EventQueueLagged { .. }) => {}
+                    Err(RegistryError::EventQueueClosed) => return sequences,
+                    Err(err) => panic!("unexpected error: {err:?}"),
+                }
+            }
+        });
+
+        for round in 0..20_000 {
+            let id = ids[round % ids.len()];
+            let event = if round % 7 == 0 { destroyed(id) } else { title_changed(id, "old", "new") };
+            queue.send(vec![event]).expect("send should not block");
+        }
+        drop(queue);
+
+        let sequences = reader.join().expect("reader thread");
+        assert!(!sequences.is_empty());
+        assert!(sequences.windows(2).all(|pair| pair[0] < pair[1]), "{overflow:?} reordered events");
+    }
+}
+
+#[test]
+fn disconnect_closes_after_lag_marker() {
+    let (_ptrs, ids) = window_ids(3);
+    let queue = RegistryEventQueue::bounded(1);
+    let slow = subscribe(&queue, OverflowPolicy::Disconnect);
+    let other = queue.subscribe_with_options(SubscribeOptions {
+        overflow: OverflowPolicy::DropNewest,
+        ..SubscribeOptions::default()
+    });
+
+    queue.send(vec![destroyed(ids[0]), destroyed(ids[1])]).expect("send should not block");
+
+    assert_eq!(slow.recv().expect("buffered event").window(), Some(ids[0]));
+    assert!(matches!(slow.recv(), Err(RegistryError::EventQueueLagged { missed: 1 })));
+    assert!(matches!(slow.recv(), Err(RegistryError::EventQueueClosed)));
+
+    drain(&other);
+    queue.send(vec![destroyed(ids[2])]).expect("send");
+    assert_eq!(drain(&other), vec![Ok(ids[2])]);
+}
+
+#[test]
+fn dropped_receiver_is_pruned() {
+    let (_ptrs, ids) = window_ids(1);
+    let queue = RegistryEventQueue::bounded(1);
+    let rx = subscribe(&queue, OverflowPolicy::DropOldest);
+    let clone = rx.clone();
+    drop(rx);
+
+    queue.send(vec![destroyed(ids[0])]).expect("send");
+    assert_eq!(drain(&clone), vec![Ok(ids[0])]);
+    drop(clone);
+
+    for _ in 0..3 {
+        queue.send(vec![destroyed(ids[0])]).expect("send should not block");
+    }
+}
+
+#[test]
+fn iter_skips_lag_markers() {
+    let (_ptrs, ids) = window_ids(3);
+    let queue = RegistryEventQueue::bounded(1);
+    let rx = subscribe(&queue, OverflowPolicy::DropNewest);
+
+    queue.send(ids.iter().copied().map(destroyed).collect()).expect("send");
+    drop(queue);
+
+    let seen: Vec<WindowId> = rx.iter().filter_map(|event| event.window()).collect();
+    assert_eq!(seen, vec![ids[0]]);
+}
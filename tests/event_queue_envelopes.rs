@@ -0,0 +1,99 @@
+use std::time::Instant;
+
+use window_registry::{
+    EventOperation,
+    Registry,
+    RegistryEvent,
+    RegistryEventQueue,
+    SharedRegistry,
+    WindowGeometry,
+    WindowUpdate,
+};
+
+mod common;
+use common::TestPtrs;
+
+#[test]
+fn envelopes_carry_sequence_batch_and_operation() {
+    let reg = SharedRegistry::new(Registry::new());
+    let queue = RegistryEventQueue::unbounded();
+    let rx = queue.subscribe();
+    let p = TestPtrs::new();
+    let (dk, sk) = unsafe { p.keys() };
+
+    let before = Instant::now();
+    let id = reg.insert_window_queued(dk, sk, &queue).expect("insert");
+    reg.on_map_queued(id, &queue).expect("map");
+    let update = WindowUpdate {
+        geometry: Some(Some(WindowGeometry { x: 0, y: 0, width: 10, height: 10 })),
+        title: Some(Some("t".to_string())),
+        ..WindowUpdate::default()
+    };
+    reg.update_window_queued(id, update, &queue).expect("update");
+    reg.remove_window_queued(id, &queue).expect("remove");
+
+    let envelopes: Vec<_> = std::iter::from_fn(|| rx.try_recv_envelope().expect("queue open")).collect();
+    let operations: Vec<EventOperation> = envelopes.iter().map(|e| e.operation).collect();
+    assert_eq!(
+        operations,
+        vec![EventOperation::Insert, EventOperation::Map, EventOperation::Update, EventOperation::Remove]
+    );
+
+    let sequences: Vec<u64> = envelopes.iter().map(|e| e.sequence).collect();
+    assert_eq!(sequences, (sequences[0]..sequences[0] + 4).collect::<Vec<_>>());
+
+    let batches: Vec<u64> = envelopes.iter().map(|e| e.batch_id).collect();
+    assert!(batches.windows(2).all(|pair| pair[0] < pair[1]));
+
+    assert!(envelopes.iter().all(|e| e.timestamp >= before));
+    assert!(envelopes.windows(2).all(|pair| pair[0].timestamp <= pair[1].timestamp));
+    assert!(matches!(envelopes[3].event, RegistryEvent::WindowDestroyed { id: ev_id } if ev_id == id));
+}
+
+#[test]
+fn events_of_one_call_share_a_batch() {
+    let reg = SharedRegistry::new(Registry::new());
+    let queue = RegistryEventQueue::unbounded();
+    let p1 = TestPtrs::new();
+    let p2 = TestPtrs::new();
+    let (dk1, sk1) = unsafe { p1.keys() };
+    let (dk2, sk2) = unsafe { p2.keys() };
+    let parent = reg.insert_window_queued(dk1, sk1, &queue).expect("insert parent");
+    let child = reg.insert_window_queued(dk2, sk2, &queue).expect("insert child");
+    reg.update_window_queued(
+        child,
+        WindowUpdate { parent_id: Some(Some(parent)), ..WindowUpdate::default() },
+        &queue,
+    )
+    .expect("set parent");
+
+    let rx = queue.subscribe();
+    reg.remove_window_queued(parent, &queue).expect("remove parent");
+
+    let envelopes: Vec<_> = std::iter::from_fn(|| rx.try_recv_envelope().expect("queue open")).collect();
+    assert!(envelopes.len() > 1);
+    assert!(envelopes.iter().all(|e| e.batch_id == envelopes[0].batch_id));
+    assert!(envelopes.iter().all(|e| e.operation == EventOperation::Remove));
+    assert!(envelopes.windows(2).all(|pair| pair[1].sequence == pair[0].sequence + 1));
+}
+
+#[test]
+fn direct_sends_are_external() {
+    let mut reg = Registry::new();
+    let p = TestPtrs::new();
+    let (dk, sk) = unsafe { p.keys() };
+    let (_id, events) = reg.insert_window(dk, sk).expect("insert");
+
+    let queue = RegistryEventQueue::unbounded();
+    let rx = queue.subscribe();
+    let ticket = queue.reserve_for(EventOperation::Insert);
+    let batch = ticket.sequence();
+    ticket.send(events.clone()).expect("send ticket");
+    queue.send(events).expect("send");
+
+    let first = rx.recv_envelope().expect("first");
+    let second = rx.recv_envelope().expect("second");
+    assert_eq!((first.batch_id, first.operation), (batch, EventOperation::Insert));
+    assert_eq!(second.operation, EventOperation::External);
+    assert_eq!(second.sequence, first.sequence + 1);
+}
@@ -0,0 +1,169 @@
+use window_registry::{
+    ChangeField,
+    EventFilter,
+    EventKind,
+    OutputId,
+    Registry,
+    RegistryEvent,
+    RegistryEventQueue,
+    SharedRegistry,
+    SubscribeOptions,
+    WindowId,
+    WindowUpdate,
+    WorkspaceId,
+};
+
+mod common;
+use common::TestPtrs;
+
+fn drain(receiver: &window_registry::RegistryEventReceiver) -> Vec<RegistryEvent> {
+    let mut out = Vec::new();
+    while let Some(event) = receiver.try_recv().expect("queue open") {
+        out.push(event);
+    }
+    out
+}
+
+fn set_title(reg: &SharedRegistry, queue: &RegistryEventQueue, id: WindowId, title: &str) {
+    let update = WindowUpdate { title: Some(Some(title.to_string())), ..WindowUpdate::default() };
+    reg.update_window_queued(id, update, queue).expect("set title");
+}
+
+fn place(reg: &SharedRegistry, queue: &RegistryEventQueue, id: WindowId, workspace: u32) {
+    let update = WindowUpdate {
+        workspace: Some(Some(WorkspaceId(workspace))),
+        output: Some(Some(OutputId(1))),
+        ..WindowUpdate::default()
+    };
+    reg.update_window_queued(id, update, queue).expect("place");
+}
+
+#[test]
+fn filter_by_kind() {
+    let reg = SharedRegistry::new(Registry::new());
+    let queue = RegistryEventQueue::unbounded();
+    let lifecycle = queue.subscribe_filtered(EventFilter::kinds([EventKind::Created, EventKind::Destroyed]));
+
+    let p = TestPtrs::new();
+    let (dk, sk) = unsafe { p.keys() };
+    let id = reg.insert_window_queued(dk, sk, &queue).expect("insert");
+    set_title(&reg, &queue, id, "ignored");
+    reg.remove_window_queued(id, &queue).expect("remove");
+
+    let events = drain(&lifecycle);
+    assert_eq!(events.len(), 2);
+    assert!(matches!(events[0], RegistryEvent::WindowCreated { .. }));
+    assert!(matches!(events[1], RegistryEvent::WindowDestroyed { .. }));
+}
+
+#[test]
+fn filter_by_window_and_field() {
+    let reg = SharedRegistry::new(Registry::new());
+    let queue = RegistryEventQueue::unbounded();
+
+    let p1 = TestPtrs::new();
+    let p2 = TestPtrs::new();
+    let (dk1, sk1) = unsafe { p1.keys() };
+    let (dk2, sk2) = unsafe { p2.keys() };
+    let mine = reg.insert_window_queued(dk1, sk1, &queue).expect("insert mine");
+    let other = reg.insert_window_queued(dk2, sk2, &queue).expect("insert other");
+
+    let title_bar = queue.subscribe_filtered(
+        EventFilter::window(mine).with_fields([ChangeField::Title]),
+    );
+    let everything = queue.subscribe();
+
+    set_title(&reg, &queue, other, "other");
+    reg.on_map_queued(mine, &queue).expect("map mine");
+    set_title(&reg, &queue, mine, "mine");
+
+    let events = drain(&title_bar);
+    assert_eq!(events.len(), 1);
+    assert!(matches!(
+        events[0],
+        RegistryEvent::WindowChanged { id, ref changes }
+            if id == mine && changes.title.as_ref().and_then(|c| c.new.as_deref()) == Some("mine")
+    ));
+    assert_eq!(drain(&everything).len(), 3);
+}
+
+#[test]
+fn filter_by_workspace_tracks_moves() {
+    let reg = SharedRegistry::new(Registry::new());
+    let queue = RegistryEventQueue::unbounded();
+    let pager = queue.subscribe_filtered(EventFilter::default().on_workspace(WorkspaceId(2)));
+
+    let p1 = TestPtrs::new();
+    let p2 = TestPtrs::new();
+    let (dk1, sk1) = unsafe { p1.keys() };
+    let (dk2, sk2) = unsafe { p2.keys() };
+    let a = reg.insert_window_queued(dk1, sk1, &queue).expect("insert A");
+    let b = reg.insert_window_queued(dk2, sk2, &queue).expect("insert B");
+
+    place(&reg, &queue, a, 2);
+    place(&reg, &queue, b, 1);
+    set_title(&reg, &queue, a, "on two");
+    set_title(&reg, &queue, b, "on one");
+    place(&reg, &queue, a, 3);
+    set_title(&reg, &queue, a, "on three");
+    reg.remove_window_queued(b, &queue).expect("remove B");
+
+    let events = drain(&pager);
+    let ids: Vec<WindowId> = events.iter().filter_map(|e| e.window()).collect();
+    assert_eq!(ids, vec![a, a, a]);
+    assert!(matches!(&events[2], RegistryEvent::WindowChanged { changes, .. } if changes.workspace.is_some()));
+}
+
+#[test]
+fn snapshot_subscription_filters_existing_windows_by_workspace() {
+    let reg = SharedRegistry::new(Registry::new());
+    let ptrs: Vec<TestPtrs> = (0..2).map(|_| TestPtrs::new()).collect();
+    let ids: Vec<WindowId> = ptrs
+        .iter()
+        .map(|p| {
+            let (dk, sk) = unsafe { p.keys() };
+            reg.insert_window_with(dk, sk, |_| {}).expect("insert")
+        })
+        .collect();
+    let [a, b] = ids[..] else { unreachable!() };
+    // Placed before the queue exists, so it never sees these moves.
+    for (id, workspace) in [(a, 2), (b, 1)] {
+        let update = WindowUpdate {
+            workspace: Some(Some(WorkspaceId(workspace))),
+            output: Some(Some(OutputId(1))),
+            ..WindowUpdate::default()
+        };
+        reg.update_window_with(id, update, |_| {}).expect("place");
+    }
+
+    let queue = RegistryEventQueue::unbounded();
+    let options = SubscribeOptions {
+        filter: Some(EventFilter::default().on_workspace(WorkspaceId(2))),
+        ..SubscribeOptions::default()
+    };
+    let (_, pager) = reg.subscribe_with_snapshot_options(&queue, options);
+    set_title(&reg, &queue, a, "on two");
+    set_title(&reg, &queue, b, "on one");
+    place(&reg, &queue, a, 3);
+    set_title(&reg, &queue, a, "on three");
+
+    let ids: Vec<WindowId> = drain(&pager).iter().filter_map(|e| e.window()).collect();
+    assert_eq!(ids, vec![a, a]);
+}
+
+#[test]
+fn filtered_subscriber_does_not_block_on_skipped_events() {
+    let queue = RegistryEventQueue::bounded(1);
+    let _idle = queue.subscribe_filtered(EventFilter::kinds([EventKind::Created]));
+
+    let mut reg = Registry::new();
+    let p = TestPtrs::new();
+    let (dk, sk) = unsafe { p.keys() };
+    let id = reg.insert_window(dk, sk).expect("insert").0;
+
+    for _ in 0..4 {
+        queue
+            .send(vec![RegistryEvent::WindowDestroyed { id }])
+            .expect("send should not block");
+    }
+}
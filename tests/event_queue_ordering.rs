@@ -0,0 +1,103 @@
+use std::{sync::Arc, thread};
+
+use window_registry::{
+    Registry,
+    RegistryEvent,
+    RegistryEventQueue,
+    SharedRegistry,
+    WindowUpdate,
+};
+
+mod common;
+use common::TestPtrs;
+
+#[test]
+fn batches_are_delivered_in_reservation_order() {
+    let mut reg = Registry::new();
+    let p1 = TestPtrs::new();
+    let p2 = TestPtrs::new();
+    let (dk1, sk1) = unsafe { p1.keys() };
+    let (dk2, sk2) = unsafe { p2.keys() };
+    let (a, created_a) = reg.insert_window(dk1, sk1).expect("insert A");
+    let (b, created_b) = reg.insert_window(dk2, sk2).expect("insert B");
+
+    let queue = RegistryEventQueue::unbounded();
+    let rx = queue.subscribe();
+
+    let first = queue.reserve();
+    let second = queue.reserve();
+    assert!(first.sequence() < second.sequence());
+
+    second.send(created_b).expect("send second");
+    assert!(rx.try_recv().expect("queue open").is_none());
+
+    first.send(created_a).expect("send first");
+    assert_eq!(rx.try_recv().expect("queue open").and_then(|e| e.window()), Some(a));
+    assert_eq!(rx.try_recv().expect("queue open").and_then(|e| e.window()), Some(b));
+}
+
+#[test]
+fn dropped_ticket_releases_later_batches() {
+    let mut reg = Registry::new();
+    let p = TestPtrs::new();
+    let (dk, sk) = unsafe { p.keys() };
+    let (id, created) = reg.insert_window(dk, sk).expect("insert");
+
+    let queue = RegistryEventQueue::unbounded();
+    let rx = queue.subscribe();
+
+    let abandoned = queue.reserve();
+    queue.send(created).expect("send");
+    assert!(rx.try_recv().expect("queue open").is_none());
+
+    drop(abandoned);
+    assert_eq!(rx.try_recv().expect("queue open").and_then(|e| e.window()), Some(id));
+}
+
+#[test]
+fn concurrent_queued_updates_arrive_in_mutation_order() {
+    const THREADS: usize = 4;
+    const UPDATES: usize = 200;
+
+    let reg = SharedRegistry::new(Registry::new());
+    let queue = RegistryEventQueue::unbounded();
+    let p = TestPtrs::new();
+    let (dk, sk) = unsafe { p.keys() };
+    let id = reg.insert_window_queued(dk, sk, &queue).expect("insert");
+    let rx = queue.subscribe();
+
+    let reg = Arc::new(reg);
+    let workers: Vec<_> = (0..THREADS)
+        .map(|t| {
+            let reg = Arc::clone(&reg);
+            let queue = queue.clone();
+            thread::spawn(move || {
+                for n in 0..UPDATES {
+                    let update = WindowUpdate {
+                        title: Some(Some(format!("{t}-{n}"))),
+                        ..WindowUpdate::default()
+                    };
+                    reg.update_window_queued(id, update, &queue).expect("update");
+                }
+            })
+        })
+        .collect();
+    for worker in workers {
+        worker.join().expect("worker should finish");
+    }
+
+    // Each change's `old` must be the previous change's `new`.
+    let mut last: Option<String> = None;
+    let mut seen = 0;
+    while let Some(event) = rx.try_recv().expect("queue open") {
+        let RegistryEvent::WindowChanged { changes, .. } = event else {
+            panic!("unexpected event: {event:?}");
+        };
+        let title = changes.title.expect("title change");
+        assert_eq!(title.old, last);
+        last = title.new;
+        seen += 1;
+    }
+    assert_eq!(seen, THREADS * UPDATES);
+    assert_eq!(reg.snapshot(id).expect("snapshot").title, last);
+}
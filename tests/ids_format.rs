@@ -75,3 +75,19 @@ fn ids_as_ptr_round_trip() {
     assert_eq!(dk_ptr as usize, p.ds as usize);
     assert_eq!(sk_ptr as usize, p.s as usize);
 }
+
+#[test]
+fn window_id_display_round_trips_through_from_str() {
+    let mut reg = window_registry::Registry::new();
+    let p = TestPtrs::new();
+    let (dk, sk) = unsafe { p.keys() };
+    let id = reg.insert_window(dk, sk).expect("insert_window should succeed").0;
+
+    let text = id.to_string();
+    assert_eq!(text, "0:1");
+    assert_eq!(text.parse::<window_registry::WindowId>(), Ok(id));
+
+    assert!("0".parse::<window_registry::WindowId>().is_err());
+    assert!("0:0".parse::<window_registry::WindowId>().is_err());
+    assert!("x:1".parse::<window_registry::WindowId>().is_err());
+}
@@ -0,0 +1,250 @@
+#![cfg(feature = "ipc")]
+
+use std::{
+    io::{BufRead, BufReader, Write},
+    os::unix::net::UnixStream,
+    path::Path,
+    sync::{Arc, Mutex},
+    time::Duration,
+};
+
+use window_registry::{
+    IpcResponse,
+    IpcServer,
+    Registry,
+    RegistryError,
+    RegistryEvent,
+    RegistryEventQueue,
+    SharedRegistry,
+    WindowGeometry,
+    WindowId,
+};
+
+mod common;
+use common::{TempSocket, TestPtrs};
+
+struct Client {
+    reader: BufReader<UnixStream>,
+    writer: UnixStream,
+}
+
+impl Client {
+    fn connect(path: &Path) -> Self {
+        let writer = UnixStream::connect(path).expect("connect should succeed");
+        writer.set_read_timeout(Some(Duration::from_secs(2))).expect("set timeout");
+        let reader = BufReader::new(writer.try_clone().expect("clone stream"));
+        Self { reader, writer }
+    }
+
+    fn send(&mut self, line: &str) {
+        self.writer.write_all(line.as_bytes()).expect("write request");
+        self.writer.write_all(b"\n").expect("write newline");
+    }
+
+    fn recv(&mut self) -> IpcResponse {
+        let mut line = String::new();
+        self.reader.read_line(&mut line).expect("read response");
+        serde_json::from_str(&line).expect("parse response")
+    }
+
+    fn request(&mut self, line: &str) -> IpcResponse {
+        self.send(line);
+        self.recv()
+    }
+}
+
+fn id_json(id: WindowId) -> String {
+    serde_json::to_string(&id).expect("serialize id")
+}
+
+fn start(reg: &SharedRegistry, queue: &RegistryEventQueue) -> TempSocket {
+    let path = TempSocket::new("ipc");
+    let server = IpcServer::bind(&path, reg.clone(), queue.clone()).expect("bind should succeed");
+    server.spawn();
+    path
+}
+
+#[test]
+fn ipc_lists_and_gets_windows() {
+    let reg = SharedRegistry::new(Registry::new());
+    let queue = RegistryEventQueue::unbounded();
+    let p = TestPtrs::new();
+    let (dk, sk) = unsafe { p.keys() };
+    let id = reg.insert_window_queued(dk, sk, &queue).expect("insert");
+
+    let path = start(&reg, &queue);
+    let mut client = Client::connect(&path);
+
+    match client.request(r#"{"cmd":"list"}"#) {
+        IpcResponse::Windows { windows } => {
+            assert_eq!(windows.len(), 1);
+            assert_eq!(windows[0].id, id);
+        }
+        other => panic!("unexpected response: {other:?}"),
+    }
+
+    match client.request(&format!(r#"{{"cmd":"get","id":{}}}"#, id_json(id))) {
+        IpcResponse::Window { window: Some(window) } => assert_eq!(window.id, id),
+        other => panic!("unexpected response: {other:?}"),
+    }
+}
+
+#[test]
+fn ipc_query_selects_windows() {
+    let reg = SharedRegistry::new(Registry::new());
+    let queue = RegistryEventQueue::unbounded();
+    let p1 = TestPtrs::new();
+    let p2 = TestPtrs::new();
+    let (dk1, sk1) = unsafe { p1.keys() };
+    let (dk2, sk2) = unsafe { p2.keys() };
+    reg.insert_window_queued(dk1, sk1, &queue).expect("insert");
+    let mapped = reg.insert_window_queued(dk2, sk2, &queue).expect("insert");
+    reg.on_map_queued(mapped, &queue).expect("map");
+
+    let path = start(&reg, &queue);
+    let mut client = Client::connect(&path);
+
+    match client.request(r#"{"cmd":"query","query":"mapped"}"#) {
+        IpcResponse::Windows { windows } => {
+            assert_eq!(windows.iter().map(|w| w.id).collect::<Vec<_>>(), vec![mapped]);
+        }
+        other => panic!("unexpected response: {other:?}"),
+    }
+    assert!(matches!(
+        client.request(r#"{"cmd":"query","query":"workspace=\"x\""}"#),
+        IpcResponse::BadRequest { .. }
+    ));
+}
+
+#[test]
+fn ipc_focus_and_move_update_registry() {
+    let reg = SharedRegistry::new(Registry::new());
+    let queue = RegistryEventQueue::unbounded();
+    let p = TestPtrs::new();
+    let (dk, sk) = unsafe { p.keys() };
+    let id = reg.insert_window_queued(dk, sk, &queue).expect("insert");
+
+    let path = start(&reg, &queue);
+    let mut client = Client::connect(&path);
+
+    let response = client.request(&format!(r#"{{"cmd":"focus","id":{}}}"#, id_json(id)));
+    assert!(matches!(response, IpcResponse::Ok));
+    assert!(reg.snapshot(id).expect("snapshot").is_focused);
+
+    let response = client.request(&format!(
+        r#"{{"cmd":"move","id":{},"geometry":{{"x":5,"y":6,"width":70,"height":80}}}}"#,
+        id_json(id)
+    ));
+    assert!(matches!(response, IpcResponse::Ok));
+    assert_eq!(
+        reg.snapshot(id).expect("snapshot").geometry,
+        Some(WindowGeometry { x: 5, y: 6, width: 70, height: 80 })
+    );
+}
+
+#[test]
+fn ipc_reports_errors_and_bad_requests() {
+    let reg = SharedRegistry::new(Registry::new());
+    let queue = RegistryEventQueue::unbounded();
+    let p = TestPtrs::new();
+    let (dk, sk) = unsafe { p.keys() };
+    let id = reg.insert_window_queued(dk, sk, &queue).expect("insert");
+    reg.remove_window_queued(id, &queue).expect("remove");
+
+    let path = start(&reg, &queue);
+    let mut client = Client::connect(&path);
+
+    let response = client.request(&format!(r#"{{"cmd":"focus","id":{}}}"#, id_json(id)));
+    assert!(matches!(
+        response,
+        IpcResponse::Error { error: RegistryError::InvalidWindowId(err_id) } if err_id == id
+    ));
+
+    assert!(matches!(client.request("not json"), IpcResponse::BadRequest { .. }));
+    let response = client.request(&format!(r#"{{"cmd":"close","id":{}}}"#, id_json(id)));
+    assert!(matches!(response, IpcResponse::BadRequest { .. }));
+}
+
+#[test]
+fn ipc_close_uses_handler() {
+    let reg = SharedRegistry::new(Registry::new());
+    let queue = RegistryEventQueue::unbounded();
+    let p = TestPtrs::new();
+    let (dk, sk) = unsafe { p.keys() };
+    let id = reg.insert_window_queued(dk, sk, &queue).expect("insert");
+
+    let closed = Arc::new(Mutex::new(Vec::new()));
+    let path = TempSocket::new("ipc");
+    let server = IpcServer::bind(&path, reg.clone(), queue.clone())
+        .expect("bind should succeed")
+        .on_close({
+            let closed = closed.clone();
+            move |id| {
+                closed.lock().unwrap().push(id);
+                Ok(())
+            }
+        });
+    server.spawn();
+
+    let mut client = Client::connect(&path);
+    let response = client.request(&format!(r#"{{"cmd":"close","id":{}}}"#, id_json(id)));
+    assert!(matches!(response, IpcResponse::Ok));
+    assert_eq!(*closed.lock().unwrap(), vec![id]);
+}
+
+#[test]
+fn ipc_subscribe_streams_events() {
+    let reg = SharedRegistry::new(Registry::new());
+    let queue = RegistryEventQueue::unbounded();
+    let path = start(&reg, &queue);
+
+    let mut subscriber = Client::connect(&path);
+    assert!(matches!(subscriber.request(r#"{"cmd":"subscribe"}"#), IpcResponse::Subscribed));
+
+    let p = TestPtrs::new();
+    let (dk, sk) = unsafe { p.keys() };
+    let id = reg.insert_window_queued(dk, sk, &queue).expect("insert");
+
+    let mut control = Client::connect(&path);
+    let response = control.request(&format!(r#"{{"cmd":"focus","id":{}}}"#, id_json(id)));
+    assert!(matches!(response, IpcResponse::Ok));
+
+    assert!(matches!(
+        subscriber.recv(),
+        IpcResponse::Event { event: RegistryEvent::WindowCreated { id: ev_id, .. } } if ev_id == id
+    ));
+    assert!(matches!(
+        subscriber.recv(),
+        IpcResponse::Event { event: RegistryEvent::WindowChanged { id: ev_id, ref changes } }
+            if ev_id == id && changes.is_focused.is_some()
+    ));
+}
+
+#[test]
+fn ipc_slow_subscriber_lags_instead_of_blocking() {
+    let reg = SharedRegistry::new(Registry::new());
+    let queue = RegistryEventQueue::bounded(4);
+    let path = start(&reg, &queue);
+
+    let mut subscriber = Client::connect(&path);
+    assert!(matches!(subscriber.request(r#"{"cmd":"subscribe"}"#), IpcResponse::Subscribed));
+    let p = TestPtrs::new();
+    let (dk, sk) = unsafe { p.keys() };
+    let id = reg.insert_window_queued(dk, sk, &queue).expect("insert");
+
+    // Far more than the socket buffers hold while the subscriber isn't reading.
+    let (done_tx, done_rx) = std::sync::mpsc::channel();
+    let sender = queue.clone();
+    std::thread::spawn(move || {
+        for _ in 0..50_000 {
+            sender.send(vec![RegistryEvent::WindowDestroyed { id }]).expect("send");
+        }
+        let _ = done_tx.send(());
+    });
+    done_rx.recv_timeout(Duration::from_secs(20)).expect("a slow subscriber must not block the queue");
+
+    let lagged = std::iter::repeat_with(|| subscriber.recv())
+        .take(60_000)
+        .find(|response| !matches!(response, IpcResponse::Event { .. }));
+    assert!(matches!(lagged, Some(IpcResponse::Lagged { missed }) if missed > 0));
+}
@@ -0,0 +1,219 @@
+#![cfg(feature = "journal")]
+
+use std::{
+    io::{self, Cursor, Write},
+    sync::{Arc, Mutex},
+};
+
+use window_registry::{
+    replay,
+    with_raw_keys,
+    ChildRemovalPolicy,
+    FakeWeston,
+    JournalError,
+    JournalOp,
+    JournalWriter,
+    OutputId,
+    Registry,
+    RegistryAdapter,
+    RegistryError,
+    RegistryEventQueue,
+    SharedRegistry,
+    WestonEvent,
+    WindowGeometry,
+    WindowInfo,
+    WindowUpdate,
+    WorkspaceId,
+};
+
+mod common;
+use common::TestPtrs;
+
+#[derive(Clone, Default)]
+struct SharedBuf(Arc<Mutex<Vec<u8>>>);
+
+impl Write for SharedBuf {
+    fn write(&mut self, data: &[u8]) -> io::Result<usize> {
+        self.0.lock().unwrap().write(data)
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        Ok(())
+    }
+}
+
+impl SharedBuf {
+    fn text(&self) -> String {
+        String::from_utf8(self.0.lock().unwrap().clone()).expect("journal is utf-8")
+    }
+}
+
+fn journaled() -> (SharedRegistry, SharedBuf) {
+    let buf = SharedBuf::default();
+    let writer = JournalWriter::new(buf.clone()).expect("write header");
+    (SharedRegistry::with_journal(Registry::new(), writer), buf)
+}
+
+/// Original keys serialize to the tokens that replayed keys hold raw.
+fn same_windows(original: Vec<WindowInfo>, replayed: Vec<WindowInfo>) {
+    let original = serde_json::to_string(&original).expect("serialize");
+    let replayed = with_raw_keys(|| serde_json::to_string(&replayed).expect("serialize"));
+    assert_eq!(original, replayed);
+}
+
+#[test]
+fn replay_rebuilds_session_driven_through_adapter() {
+    let (reg, buf) = journaled();
+    let queue = RegistryEventQueue::unbounded();
+    let mut fake = FakeWeston::new(RegistryAdapter::new(reg.clone(), queue));
+
+    let p1 = TestPtrs::new();
+    let p2 = TestPtrs::new();
+    let (dk1, sk1) = unsafe { p1.keys() };
+    let (dk2, sk2) = unsafe { p2.keys() };
+    fake.push(WestonEvent::NewSurface { dk: dk1, sk: sk1 });
+    fake.push(WestonEvent::NewSurface { dk: dk2, sk: sk2 });
+    fake.run().expect("create");
+
+    let main = reg.from_desktop(dk1).expect("main");
+    let dialog = reg.from_desktop(dk2).expect("dialog");
+    fake.push(WestonEvent::Map { id: main });
+    fake.push(WestonEvent::Map { id: dialog });
+    fake.push(WestonEvent::Parent { id: dialog, parent: Some(main) });
+    fake.push(WestonEvent::Output { id: dialog, output: OutputId(1), workspace: WorkspaceId(3) });
+    fake.push(WestonEvent::Configure { id: dialog, geom: WindowGeometry { x: 5, y: 5, width: 50, height: 40 } });
+    fake.push(WestonEvent::Focus { id: dialog, focused: true });
+    fake.push(WestonEvent::Unmap { id: dialog });
+    fake.run().expect("session");
+
+    let replayed = replay(Cursor::new(buf.text())).expect("replay matches");
+    same_windows(reg.snapshot_all(), replayed.snapshot_all());
+}
+
+#[test]
+fn transactions_and_failures_are_journaled() {
+    let (reg, buf) = journaled();
+    let p = TestPtrs::new();
+    let (dk, sk) = unsafe { p.keys() };
+
+    let id = reg
+        .transaction_with(
+            |tx| {
+                let id = tx.insert_window(dk, sk)?;
+                tx.on_map(id)?;
+                let update = WindowUpdate { title: Some(Some("editor".to_string())), ..WindowUpdate::default() };
+                tx.update_window(id, update)?;
+                Ok(id)
+            },
+            |_| {},
+        )
+        .expect("transaction");
+    let err = reg.insert_window_with(dk, sk, |_| {}).expect_err("duplicate key");
+    assert!(matches!(err, RegistryError::DesktopKeyAlreadyRegistered { .. }));
+    reg.remove_window_with(id, |_| {}).expect("remove");
+
+    let text = buf.text();
+    assert_eq!(text.lines().count(), 4);
+    assert!(text.lines().nth(1).unwrap().contains(r#""kind":"transaction""#));
+    assert!(text.lines().nth(2).unwrap().contains(r#""error""#));
+
+    let replayed = replay(Cursor::new(text)).expect("replay matches");
+    assert!(replayed.snapshot_all().is_empty());
+}
+
+#[test]
+fn abandoned_transactions_and_policy_changes_are_journaled() {
+    let (reg, buf) = journaled();
+    let ptrs: Vec<TestPtrs> = (0..2).map(|_| TestPtrs::new()).collect();
+    let keys: Vec<_> = ptrs.iter().map(|p| unsafe { p.keys() }).collect();
+
+    reg.set_child_removal_policy(ChildRemovalPolicy::DestroySubtree);
+    let parent = reg.insert_window_with(keys[0].0, keys[0].1, |_| {}).expect("insert");
+    let err = reg
+        .transaction_with(
+            |tx| {
+                let child = tx.insert_window(keys[1].0, keys[1].1)?;
+                let update = WindowUpdate { parent_id: Some(Some(parent)), ..WindowUpdate::default() };
+                tx.update_window(child, update)?;
+                Err::<(), _>(RegistryError::ParentIsSelf { id: child })
+            },
+            |_| {},
+        )
+        .expect_err("closure gives up");
+    assert!(matches!(err, RegistryError::ParentIsSelf { .. }));
+    assert_eq!(reg.snapshot_all().len(), 1);
+
+    // A failing operation inside the closure is recorded as well.
+    reg.transaction_with(
+        |tx| {
+            tx.remove_window(parent)?;
+            tx.on_map(parent)
+        },
+        |_| {},
+    )
+    .expect_err("window is gone");
+    reg.transaction_with(
+        |tx| {
+            let child = tx.insert_window(keys[1].0, keys[1].1)?;
+            let update = WindowUpdate { parent_id: Some(Some(parent)), ..WindowUpdate::default() };
+            tx.update_window(child, update)
+        },
+        |_| {},
+    )
+    .expect("adopt");
+    reg.remove_window_with(parent, |_| {}).expect("remove");
+    assert!(reg.snapshot_all().is_empty());
+
+    let text = buf.text();
+    assert_eq!(text.lines().count(), 7);
+    assert!(text.lines().nth(1).unwrap().contains(r#""kind":"set_child_removal_policy""#));
+    assert!(text.lines().nth(3).unwrap().contains(r#""abandoned""#));
+    assert!(!text.lines().nth(5).unwrap().contains(r#""abandoned""#));
+
+    // Replay only matches if the policy is replayed and the abandoned batches stay unapplied.
+    let replayed = replay(Cursor::new(text)).expect("replay matches");
+    assert!(replayed.snapshot_all().is_empty());
+}
+
+#[test]
+fn replay_detects_divergence() {
+    let (reg, buf) = journaled();
+    let p = TestPtrs::new();
+    let (dk, sk) = unsafe { p.keys() };
+    let id = reg.insert_window_with(dk, sk, |_| {}).expect("insert");
+    let update = WindowUpdate { title: Some(Some("recorded".to_string())), ..WindowUpdate::default() };
+    reg.update_window_with(id, update, |_| {}).expect("update");
+
+    let tampered = buf.text().replace(r#""new":"recorded""#, r#""new":"other""#);
+    match replay(Cursor::new(tampered)) {
+        Err(JournalError::Mismatch { seq, .. }) => assert_eq!(seq, 1),
+        other => panic!("expected mismatch, got {other:?}"),
+    }
+}
+
+#[test]
+fn replay_rejects_unknown_versions_and_garbage() {
+    let err = replay(Cursor::new("{\"format\":\"window_registry.journal\",\"version\":99}\n"))
+        .expect_err("future version");
+    assert!(matches!(err, JournalError::UnsupportedFormat { version: 99, .. }));
+
+    let (_reg, buf) = journaled();
+    let err = replay(Cursor::new(buf.text() + "not json\n")).expect_err("garbage");
+    assert!(matches!(err, JournalError::Parse { line: 2, .. }));
+}
+
+#[test]
+fn writer_journals_plain_registry() {
+    let mut reg = Registry::new();
+    let mut writer = JournalWriter::new(Vec::new()).expect("header");
+    let p = TestPtrs::new();
+    let (dk, sk) = unsafe { p.keys() };
+
+    writer.apply(&mut reg, JournalOp::Insert { dk, sk }).expect("insert");
+    let id = reg.from_desktop(dk).expect("id");
+    writer.apply(&mut reg, JournalOp::Map { id }).expect("map");
+    writer.flush().expect("flush");
+
+    let replayed = replay(Cursor::new(writer.into_inner())).expect("replay matches");
+    same_windows(reg.snapshot_all(), replayed.snapshot_all());
+}
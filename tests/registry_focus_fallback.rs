@@ -0,0 +1,167 @@
+use window_registry::{
+    FocusFallback,
+    LifecycleState,
+    Registry,
+    RegistryEvent,
+    WindowChange,
+    WindowId,
+    WindowRecord,
+    WindowState,
+    WindowUpdate,
+};
+
+mod common;
+use common::{focus, insert_mapped, place, TestPtrs};
+
+#[test]
+fn unmap_focused_window_groups_focus_with_lifecycle() {
+    let mut reg = Registry::new();
+    reg.set_focus_fallback(Vec::new());
+    let p = TestPtrs::new();
+    let id = insert_mapped(&mut reg, &p);
+    focus(&mut reg, id);
+
+    let events = reg.on_unmap(id).expect("on_unmap should succeed");
+
+    assert_eq!(events.len(), 1);
+    assert!(matches!(
+        events[0],
+        RegistryEvent::WindowChanged { id: ev_id, ref changes }
+            if ev_id == id
+                && changes.lifecycle == Some(WindowChange { old: LifecycleState::Mapped, new: LifecycleState::Unmapped })
+                && changes.is_focused == Some(WindowChange { old: true, new: false })
+    ));
+    assert!(!reg.snapshot(id).expect("snapshot").is_focused);
+}
+
+#[test]
+fn unmap_focused_transient_falls_back_to_parent() {
+    let mut reg = Registry::new();
+    let p1 = TestPtrs::new();
+    let p2 = TestPtrs::new();
+    let p3 = TestPtrs::new();
+    let parent = insert_mapped(&mut reg, &p1);
+    let other = insert_mapped(&mut reg, &p2);
+    let dialog = insert_mapped(&mut reg, &p3);
+
+    let update = WindowUpdate { parent_id: Some(Some(parent)), ..WindowUpdate::default() };
+    reg.update_window(dialog, update).expect("set parent should succeed");
+
+    focus(&mut reg, other);
+    focus(&mut reg, dialog);
+
+    let events = reg.on_unmap(dialog).expect("on_unmap should succeed");
+
+    assert_eq!(events.len(), 2);
+    assert!(matches!(
+        events[1],
+        RegistryEvent::WindowChanged { id, ref changes }
+            if id == parent && changes.is_focused == Some(WindowChange { old: false, new: true })
+    ));
+    assert!(reg.snapshot(parent).expect("parent").is_focused);
+    assert!(!reg.snapshot(other).expect("other").is_focused);
+}
+
+#[test]
+fn remove_focused_window_falls_back_to_most_recently_focused() {
+    let mut reg = Registry::new();
+    let p1 = TestPtrs::new();
+    let p2 = TestPtrs::new();
+    let p3 = TestPtrs::new();
+    let a = insert_mapped(&mut reg, &p1);
+    let b = insert_mapped(&mut reg, &p2);
+    let c = insert_mapped(&mut reg, &p3);
+
+    focus(&mut reg, a);
+    focus(&mut reg, c);
+    focus(&mut reg, b);
+
+    let (_record, events) = reg.remove_window(b).expect("remove_window should succeed");
+
+    assert!(matches!(events[events.len() - 2], RegistryEvent::WindowDestroyed { id } if id == b));
+    assert!(matches!(
+        events[events.len() - 1],
+        RegistryEvent::WindowChanged { id, ref changes }
+            if id == c && changes.is_focused == Some(WindowChange { old: false, new: true })
+    ));
+    assert!(reg.snapshot(c).expect("c").is_focused);
+}
+
+#[test]
+fn fallback_skips_unmapped_and_minimized_windows() {
+    let mut reg = Registry::new();
+    reg.set_focus_fallback(vec![FocusFallback::MostRecentlyFocused]);
+    let p1 = TestPtrs::new();
+    let p2 = TestPtrs::new();
+    let p3 = TestPtrs::new();
+    let hidden = insert_mapped(&mut reg, &p1);
+    let minimized = insert_mapped(&mut reg, &p2);
+    let current = insert_mapped(&mut reg, &p3);
+
+    focus(&mut reg, hidden);
+    focus(&mut reg, minimized);
+    focus(&mut reg, current);
+
+    reg.on_unmap(hidden).expect("unmap hidden");
+    let update = WindowUpdate {
+        state: Some(WindowState { minimized: true, maximized: false, fullscreen: false }),
+        ..WindowUpdate::default()
+    };
+    reg.update_window(minimized, update).expect("minimize");
+
+    let events = reg.on_unmap(current).expect("unmap current");
+    assert_eq!(events.len(), 1);
+    assert!(reg.snapshot_all().iter().all(|w| !w.is_focused));
+}
+
+#[test]
+fn top_of_stack_fallback_stays_on_same_workspace_and_output() {
+    let mut reg = Registry::new();
+    reg.set_focus_fallback(vec![FocusFallback::TopOfStack]);
+    let p1 = TestPtrs::new();
+    let p2 = TestPtrs::new();
+    let p3 = TestPtrs::new();
+    let p4 = TestPtrs::new();
+    let low = insert_mapped(&mut reg, &p1);
+    let high = insert_mapped(&mut reg, &p2);
+    let elsewhere = insert_mapped(&mut reg, &p3);
+    let lost = insert_mapped(&mut reg, &p4);
+
+    place(&mut reg, low, 1, 1);
+    place(&mut reg, high, 1, 1);
+    place(&mut reg, elsewhere, 2, 1);
+    place(&mut reg, lost, 1, 1);
+    focus(&mut reg, lost);
+
+    reg.remove_window(lost).expect("remove_window should succeed");
+
+    assert!(reg.snapshot(high).expect("high").is_focused);
+    assert!(!reg.snapshot(low).expect("low").is_focused);
+    assert!(!reg.snapshot(elsewhere).expect("elsewhere").is_focused);
+}
+
+fn first_created(reg: &Registry, lost: &WindowRecord) -> Option<WindowId> {
+    reg.snapshot_all()
+        .into_iter()
+        .map(|w| w.id)
+        .find(|id| *id != lost.id)
+}
+
+#[test]
+fn custom_fallback_is_used() {
+    let mut reg = Registry::new();
+    reg.set_focus_fallback(vec![FocusFallback::Custom(first_created)]);
+    let p1 = TestPtrs::new();
+    let p2 = TestPtrs::new();
+    let p3 = TestPtrs::new();
+    let first = insert_mapped(&mut reg, &p1);
+    let second = insert_mapped(&mut reg, &p2);
+    let lost = insert_mapped(&mut reg, &p3);
+
+    focus(&mut reg, second);
+    focus(&mut reg, lost);
+    reg.on_unmap(lost).expect("on_unmap should succeed");
+
+    assert!(reg.snapshot(first).expect("first").is_focused);
+    assert!(!reg.snapshot(second).expect("second").is_focused);
+}
@@ -0,0 +1,105 @@
+use window_registry::{
+    OutputId,
+    Registry,
+    SharedRegistry,
+    WindowId,
+    WindowUpdate,
+    WorkspaceId,
+};
+
+mod common;
+use common::{focus, insert, place, TestPtrs};
+
+#[test]
+fn focus_history_orders_most_recent_first() {
+    let mut reg = Registry::new();
+    let p1 = TestPtrs::new();
+    let p2 = TestPtrs::new();
+    let p3 = TestPtrs::new();
+    let a = insert(&mut reg, &p1);
+    let b = insert(&mut reg, &p2);
+    let c = insert(&mut reg, &p3);
+
+    assert!(reg.focus_history().is_empty());
+
+    focus(&mut reg, a);
+    focus(&mut reg, b);
+    focus(&mut reg, c);
+    focus(&mut reg, a);
+
+    assert_eq!(reg.focus_history(), &[a, c, b]);
+}
+
+#[test]
+fn focus_history_ignores_refocus_and_unfocus() {
+    let mut reg = Registry::new();
+    let p1 = TestPtrs::new();
+    let p2 = TestPtrs::new();
+    let a = insert(&mut reg, &p1);
+    let b = insert(&mut reg, &p2);
+
+    focus(&mut reg, a);
+    focus(&mut reg, b);
+    focus(&mut reg, b);
+
+    let update = WindowUpdate { is_focused: Some(false), ..WindowUpdate::default() };
+    reg.update_window(b, update).expect("unfocus should succeed");
+
+    assert_eq!(reg.focus_history(), &[b, a]);
+}
+
+#[test]
+fn focus_history_prunes_destroyed_windows() {
+    let mut reg = Registry::new();
+    let p1 = TestPtrs::new();
+    let p2 = TestPtrs::new();
+    let a = insert(&mut reg, &p1);
+    let b = insert(&mut reg, &p2);
+
+    focus(&mut reg, a);
+    focus(&mut reg, b);
+    reg.remove_window(a).expect("remove_window should succeed");
+
+    assert_eq!(reg.focus_history(), &[b]);
+}
+
+#[test]
+fn focus_history_filters_by_workspace_and_output() {
+    let mut reg = Registry::new();
+    let p1 = TestPtrs::new();
+    let p2 = TestPtrs::new();
+    let p3 = TestPtrs::new();
+    let a = insert(&mut reg, &p1);
+    let b = insert(&mut reg, &p2);
+    let c = insert(&mut reg, &p3);
+
+    place(&mut reg, a, 1, 1);
+    place(&mut reg, b, 2, 1);
+    place(&mut reg, c, 1, 2);
+
+    focus(&mut reg, a);
+    focus(&mut reg, b);
+    focus(&mut reg, c);
+
+    assert_eq!(reg.focus_history_on_workspace(WorkspaceId(1)), vec![c, a]);
+    assert_eq!(reg.focus_history_on_workspace(WorkspaceId(3)), Vec::<WindowId>::new());
+    assert_eq!(reg.focus_history_on_output(OutputId(1)), vec![b, a]);
+}
+
+#[test]
+fn shared_registry_exposes_focus_history() {
+    let reg = SharedRegistry::new(Registry::new());
+    let p1 = TestPtrs::new();
+    let p2 = TestPtrs::new();
+    let (dk1, sk1) = unsafe { p1.keys() };
+    let (dk2, sk2) = unsafe { p2.keys() };
+    let a = reg.insert_window_with(dk1, sk1, |_| {}).expect("insert A");
+    let b = reg.insert_window_with(dk2, sk2, |_| {}).expect("insert B");
+
+    for id in [b, a] {
+        let update = WindowUpdate { is_focused: Some(true), ..WindowUpdate::default() };
+        reg.update_window_with(id, update, |_| {}).expect("focus should succeed");
+    }
+
+    assert_eq!(reg.focus_history(), vec![a, b]);
+}
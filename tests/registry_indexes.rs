@@ -0,0 +1,126 @@
+use window_registry::{
+    ChildRemovalPolicy,
+    OutputId,
+    Registry,
+    WindowId,
+    WindowRecord,
+    WindowUpdate,
+    WorkspaceId,
+};
+
+mod common;
+use common::{insert, place, TestPtrs};
+
+fn set_app_id(reg: &mut Registry, id: WindowId, app_id: Option<&str>) {
+    let update = WindowUpdate { app_id: Some(app_id.map(str::to_string)), ..WindowUpdate::default() };
+    reg.update_window(id, update).expect("set app_id should succeed");
+}
+
+fn sorted(mut ids: Vec<WindowId>) -> Vec<WindowId> {
+    ids.sort_by_key(|id| id.to_string());
+    ids
+}
+
+/// Every index lookup must agree with a full scan.
+fn assert_indexes_consistent(reg: &Registry) {
+    let all = reg.snapshot_all();
+    let scan = |pred: &dyn Fn(&window_registry::WindowInfo) -> bool| {
+        sorted(all.iter().filter(|w| pred(w)).map(|w| w.id).collect())
+    };
+    for n in 0..4 {
+        assert_eq!(
+            sorted(reg.windows_on_workspace(WorkspaceId(n)).to_vec()),
+            scan(&|w| w.workspace == Some(WorkspaceId(n))),
+            "workspace {n}"
+        );
+        assert_eq!(
+            sorted(reg.windows_on_output(OutputId(n)).to_vec()),
+            scan(&|w| w.output == Some(OutputId(n))),
+            "output {n}"
+        );
+    }
+    for app_id in ["term", "browser"] {
+        assert_eq!(
+            sorted(reg.windows_with_app_id(app_id).to_vec()),
+            scan(&|w| w.app_id.as_deref() == Some(app_id)),
+            "app_id {app_id}"
+        );
+    }
+}
+
+#[test]
+fn lookups_follow_updates() {
+    let mut reg = Registry::new();
+    let ptrs: Vec<TestPtrs> = (0..3).map(|_| TestPtrs::new()).collect();
+    let ids: Vec<WindowId> = ptrs.iter().map(|p| insert(&mut reg, p)).collect();
+
+    assert!(reg.windows_on_workspace(WorkspaceId(1)).is_empty());
+
+    place(&mut reg, ids[0], 1, 1);
+    place(&mut reg, ids[1], 1, 2);
+    place(&mut reg, ids[2], 2, 2);
+    set_app_id(&mut reg, ids[0], Some("term"));
+    set_app_id(&mut reg, ids[2], Some("term"));
+
+    assert_eq!(reg.windows_on_workspace(WorkspaceId(1)), &[ids[0], ids[1]]);
+    assert_eq!(reg.windows_on_output(OutputId(2)), &[ids[1], ids[2]]);
+    assert_eq!(reg.windows_with_app_id("term"), &[ids[0], ids[2]]);
+
+    place(&mut reg, ids[0], 2, 2);
+    set_app_id(&mut reg, ids[2], Some("browser"));
+    assert_eq!(reg.windows_on_workspace(WorkspaceId(1)), &[ids[1]]);
+    assert_eq!(reg.windows_on_workspace(WorkspaceId(2)), &[ids[2], ids[0]]);
+    assert_eq!(reg.windows_with_app_id("term"), &[ids[0]]);
+
+    let clear = WindowUpdate { workspace: Some(None), output: Some(None), ..WindowUpdate::default() };
+    reg.update_window(ids[1], clear).expect("clear placement");
+    set_app_id(&mut reg, ids[0], None);
+    assert!(reg.windows_on_workspace(WorkspaceId(1)).is_empty());
+    assert!(reg.windows_with_app_id("term").is_empty());
+    assert_indexes_consistent(&reg);
+}
+
+#[test]
+fn removal_and_rejected_updates_keep_indexes_consistent() {
+    let mut reg = Registry::new();
+    reg.set_child_removal_policy(ChildRemovalPolicy::DestroySubtree);
+    let ptrs: Vec<TestPtrs> = (0..4).map(|_| TestPtrs::new()).collect();
+    let ids: Vec<WindowId> = ptrs.iter().map(|p| insert(&mut reg, p)).collect();
+    for (n, id) in ids.iter().enumerate() {
+        place(&mut reg, *id, n as u32 % 2, 1);
+        set_app_id(&mut reg, *id, Some(if n % 2 == 0 { "term" } else { "browser" }));
+    }
+    reg.update_window(ids[1], WindowUpdate { parent_id: Some(Some(ids[0])), ..WindowUpdate::default() })
+        .expect("parent");
+
+    // Fails validation after the app_id would change: nothing may be indexed.
+    let bad = WindowUpdate {
+        app_id: Some(Some("browser".to_string())),
+        workspace: Some(Some(WorkspaceId(3))),
+        output: Some(None),
+        ..WindowUpdate::default()
+    };
+    reg.update_window(ids[2], bad).expect_err("workspace without output");
+    assert_indexes_consistent(&reg);
+
+    // A failed transaction leaves the indexes of the real registry alone.
+    {
+        let mut tx = reg.transaction();
+        tx.update_window(ids[3], WindowUpdate { app_id: Some(Some("term".to_string())), ..WindowUpdate::default() })
+            .expect("staged");
+        tx.update_window(ids[3], WindowUpdate { parent_id: Some(Some(ids[3])), ..WindowUpdate::default() })
+            .expect_err("own parent");
+        tx.commit().expect_err("rolled back");
+    }
+    assert_eq!(reg.windows_with_app_id("browser"), &[ids[1], ids[3]]);
+
+    reg.remove_window(ids[0]).expect("remove subtree");
+    assert_indexes_consistent(&reg);
+    assert_eq!(reg.windows_on_workspace(WorkspaceId(0)), &[ids[2]]);
+    assert_eq!(reg.windows_with_app_id("browser"), &[ids[3]]);
+
+    let record: WindowRecord = reg.remove(ids[3]).expect("low-level remove");
+    assert!(reg.windows_with_app_id("browser").is_empty());
+    let new_id = reg.insert(record);
+    assert_eq!(reg.windows_with_app_id("browser"), &[new_id]);
+}
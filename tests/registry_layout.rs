@@ -0,0 +1,208 @@
+use window_registry::{
+    Gaps,
+    Insets,
+    Layout,
+    OutputId,
+    OutputInfo,
+    OutputUpdate,
+    Registry,
+    RegistryError,
+    RegistryEvent,
+    RuleActions,
+    RuleMatch,
+    SharedRegistry,
+    StackLayer,
+    WindowGeometry,
+    WindowId,
+    WindowState,
+    WindowUpdate,
+    WindowRule,
+    WorkspaceId,
+};
+
+mod common;
+use common::{insert, TestPtrs};
+
+fn rect(x: i32, y: i32, width: i32, height: i32) -> WindowGeometry {
+    WindowGeometry { x, y, width, height }
+}
+
+fn insert_placed(reg: &mut Registry, p: &TestPtrs, workspace: WorkspaceId) -> WindowId {
+    let id = insert(reg, p);
+    let update = WindowUpdate {
+        workspace: Some(Some(workspace)),
+        output: Some(Some(OutputId(1))),
+        geometry: Some(Some(rect(0, 0, 100, 100))),
+        ..WindowUpdate::default()
+    };
+    reg.update_window(id, update).expect("place");
+    reg.on_map(id).expect("map");
+    id
+}
+
+const GAPS: Gaps = Gaps { inner: 10, outer: 20 };
+
+#[test]
+fn layouts_split_the_area_with_gaps() {
+    let area = rect(0, 0, 1000, 600);
+    let arrange = |layout: Layout, count| layout.arrange(area, GAPS, count).expect("arrange");
+
+    assert_eq!(
+        arrange(Layout::default(), 3),
+        [rect(20, 20, 475, 560), rect(505, 20, 475, 275), rect(505, 305, 475, 275)]
+    );
+    // Without stack windows the masters take the whole width.
+    assert_eq!(
+        arrange(Layout::MasterStack { masters: 2, ratio: 0.7 }, 2),
+        [rect(20, 20, 960, 275), rect(20, 305, 960, 275)]
+    );
+    // The first tiles take the remainder of an uneven split.
+    assert_eq!(arrange(Layout::Columns, 3), [rect(20, 20, 314, 560), rect(344, 20, 313, 560), rect(667, 20, 313, 560)]);
+    assert_eq!(
+        arrange(Layout::Grid, 5),
+        [
+            rect(20, 20, 314, 275),
+            rect(344, 20, 313, 275),
+            rect(667, 20, 313, 275),
+            rect(20, 305, 475, 275),
+            rect(505, 305, 475, 275),
+        ]
+    );
+    assert_eq!(arrange(Layout::Monocle, 2), [rect(20, 20, 960, 560); 2]);
+    assert!(arrange(Layout::Grid, 0).is_empty());
+
+    // Tiles that don't fit shrink to nothing instead of overlapping.
+    let tiny = Layout::Columns.arrange(rect(0, 0, 30, 30), GAPS, 2).expect("arrange");
+    assert!(tiny.iter().all(|tile| tile.width == 0 && tile.height == 0));
+
+    for ratio in [0.0, 1.0, f64::NAN] {
+        assert!(matches!(
+            Layout::MasterStack { masters: 1, ratio }.arrange(area, GAPS, 2),
+            Err(RegistryError::InvalidLayoutRatio { .. })
+        ));
+    }
+}
+
+#[test]
+fn apply_layout_tiles_eligible_windows_in_the_usable_area() {
+    let mut reg = Registry::new();
+    let mut output = OutputInfo::new(OutputId(1), "DP-1", rect(1000, 0, 1040, 640));
+    output.reserved = Insets { top: 40, ..Insets::default() };
+    reg.add_output(output).expect("add");
+    let (ws, _) = reg.create_workspace("code", OutputId(1)).expect("create");
+    let ptrs: Vec<TestPtrs> = (0..7).map(|_| TestPtrs::new()).collect();
+    let ids: Vec<WindowId> = ptrs.iter().map(|p| insert_placed(&mut reg, p, ws)).collect();
+    let [editor, terminal, floating, dialog, minimized, panel, hidden] = ids[..] else { unreachable!() };
+
+    let minimize = WindowState { minimized: true, ..WindowState::default() };
+    let skip = [
+        (floating, WindowUpdate { floating: Some(true), ..WindowUpdate::default() }),
+        (dialog, WindowUpdate { parent_id: Some(Some(editor)), ..WindowUpdate::default() }),
+        (minimized, WindowUpdate { state: Some(minimize), ..WindowUpdate::default() }),
+        (panel, WindowUpdate { layer: Some(StackLayer::Top), ..WindowUpdate::default() }),
+    ];
+    for (id, update) in skip {
+        reg.update_window(id, update).expect("update");
+    }
+    reg.on_unmap(hidden).expect("unmap");
+    assert_eq!(reg.tiled_windows(ws), [editor, terminal]);
+    assert_eq!(reg.query(&"floating".parse().expect("parse")), [floating]);
+
+    let layout = Layout::MasterStack { masters: 1, ratio: 0.6 };
+    let expected = [(editor, rect(1020, 60, 594, 560)), (terminal, rect(1624, 60, 396, 560))];
+    assert_eq!(reg.compute_layout(ws, layout, GAPS).expect("compute"), expected);
+
+    let events = reg.apply_layout(ws, layout, GAPS).expect("apply");
+    let moved: Vec<(WindowId, Option<WindowGeometry>)> = events
+        .iter()
+        .filter_map(|event| match event {
+            RegistryEvent::WindowChanged { id, changes } => changes.geometry.as_ref().map(|change| (*id, change.new)),
+            _ => None,
+        })
+        .collect();
+    assert_eq!(moved, expected.map(|(id, geometry)| (id, Some(geometry))));
+    assert_eq!(reg.snapshot(floating).expect("live").geometry, Some(rect(0, 0, 100, 100)));
+    assert!(reg.apply_layout(ws, layout, GAPS).expect("unchanged").is_empty());
+
+    // A wider panel shrinks the usable area.
+    let reserved = Insets { top: 40, left: 100, ..Insets::default() };
+    let update = OutputUpdate { reserved: Some(reserved), ..OutputUpdate::default() };
+    reg.update_output(OutputId(1), update).expect("update");
+    assert_eq!(reg.output(OutputId(1)).expect("known").usable_area(), rect(1100, 40, 940, 600));
+    let events = reg.apply_layout(ws, Layout::Monocle, Gaps::default()).expect("apply");
+    assert_eq!(events.len(), 2);
+    assert_eq!(reg.snapshot(terminal).expect("live").geometry, Some(rect(1100, 40, 940, 600)));
+
+    assert!(matches!(
+        reg.apply_layout(WorkspaceId(99), layout, GAPS),
+        Err(RegistryError::InvalidWorkspaceId(WorkspaceId(99)))
+    ));
+    assert!(matches!(
+        reg.apply_layout(ws, Layout::MasterStack { masters: 1, ratio: 1.5 }, GAPS),
+        Err(RegistryError::InvalidLayoutRatio { .. })
+    ));
+}
+
+#[test]
+fn rule_geometry_survives_layouts() {
+    let mut reg = Registry::new();
+    reg.add_output(OutputInfo::new(OutputId(1), "DP-1", rect(0, 0, 800, 600))).expect("add");
+    let (ws, _) = reg.create_workspace("main", OutputId(1)).expect("create");
+    let pip = rect(500, 400, 280, 180);
+    reg.add_rule(WindowRule::new(
+        RuleMatch::app_id("mpv"),
+        RuleActions { geometry: Some(pip), ..RuleActions::default() },
+    ));
+    let ptrs: Vec<TestPtrs> = (0..2).map(|_| TestPtrs::new()).collect();
+    let [editor, video] = [0, 1].map(|i| insert_placed(&mut reg, &ptrs[i], ws));
+
+    let update = WindowUpdate { app_id: Some(Some("mpv".to_string())), ..WindowUpdate::default() };
+    reg.update_window(video, update).expect("set app_id");
+    assert!(reg.snapshot(video).expect("live").floating);
+    assert_eq!(reg.tiled_windows(ws), [editor]);
+
+    reg.apply_layout(ws, Layout::Monocle, Gaps::default()).expect("apply");
+    assert_eq!(reg.snapshot(editor).expect("live").geometry, Some(rect(0, 0, 800, 600)));
+    assert_eq!(reg.snapshot(video).expect("live").geometry, Some(pip));
+}
+
+#[test]
+fn apply_layout_is_one_transaction_batch() {
+    let reg = SharedRegistry::new(Registry::new());
+    let ptrs: Vec<TestPtrs> = (0..3).map(|_| TestPtrs::new()).collect();
+    let ids: Vec<WindowId> = ptrs
+        .iter()
+        .map(|p| {
+            let (dk, sk) = unsafe { p.keys() };
+            reg.insert_window_with(dk, sk, |_| {}).expect("insert")
+        })
+        .collect();
+    let ws = reg
+        .transaction_with(
+            |tx| {
+                tx.add_output(OutputInfo::new(OutputId(1), "DP-1", rect(0, 0, 900, 300)))?;
+                let ws = tx.create_workspace("main", OutputId(1))?;
+                for id in &ids {
+                    let update = WindowUpdate {
+                        workspace: Some(Some(ws)),
+                        output: Some(Some(OutputId(1))),
+                        ..WindowUpdate::default()
+                    };
+                    tx.update_window(*id, update)?;
+                    tx.on_map(*id)?;
+                }
+                Ok(ws)
+            },
+            |_| {},
+        )
+        .expect("setup");
+
+    let mut batches = Vec::new();
+    reg.transaction_with(|tx| tx.apply_layout(ws, Layout::Columns, Gaps::default()), |batch| batches.push(batch))
+        .expect("apply");
+    assert_eq!(batches.len(), 1);
+    assert_eq!(batches[0].len(), 3);
+    let geometries: Vec<_> = ids.iter().map(|id| reg.snapshot(*id).expect("live").geometry).collect();
+    assert_eq!(geometries, [Some(rect(0, 0, 300, 300)), Some(rect(300, 0, 300, 300)), Some(rect(600, 0, 300, 300))]);
+    assert_eq!(reg.compute_layout(ws, Layout::Columns, Gaps::default()).expect("compute").len(), 3);
+}
@@ -0,0 +1,153 @@
+use window_registry::{
+    ChangeField,
+    ChildRemovalPolicy,
+    OutputId,
+    OutputInfo,
+    Registry,
+    RegistryError,
+    RegistryEvent,
+    RegistryMirror,
+    WindowGeometry,
+    WindowId,
+    WindowInfo,
+    WindowUpdate,
+};
+
+mod common;
+use common::TestPtrs;
+
+fn assert_in_sync(mirror: &RegistryMirror, reg: &Registry) {
+    let render = |windows: Vec<WindowInfo>| format!("{windows:?}");
+    assert_eq!(render(mirror.snapshot_all()), render(reg.snapshot_all()));
+}
+
+fn retitle(reg: &mut Registry, id: WindowId, title: &str) -> Vec<RegistryEvent> {
+    let update = WindowUpdate { title: Some(Some(title.to_string())), ..WindowUpdate::default() };
+    reg.update_window(id, update).expect("retitle")
+}
+
+#[test]
+fn mirror_follows_registry_events() {
+    let mut reg = Registry::new();
+    reg.set_child_removal_policy(ChildRemovalPolicy::Reparent);
+    let ptrs: Vec<TestPtrs> = (0..3).map(|_| TestPtrs::new()).collect();
+
+    let (dk, sk) = unsafe { ptrs[0].keys() };
+    let (root, events) = reg.insert_window(dk, sk).expect("insert root");
+    let mut mirror = RegistryMirror::new(Vec::new());
+    mirror.apply_all(&events).expect("apply insert");
+
+    let mut ids = vec![root];
+    for p in &ptrs[1..] {
+        let (dk, sk) = unsafe { p.keys() };
+        let (id, events) = reg.insert_window(dk, sk).expect("insert");
+        mirror.apply_all(&events).expect("apply insert");
+        ids.push(id);
+    }
+    let (mid, leaf) = (ids[1], ids[2]);
+
+    let mut batches = Vec::new();
+    for id in &ids {
+        batches.push(reg.on_map(*id).expect("map"));
+    }
+    batches.push(
+        reg.update_window(mid, WindowUpdate { parent_id: Some(Some(root)), ..WindowUpdate::default() })
+            .expect("parent mid"),
+    );
+    batches.push(
+        reg.update_window(leaf, WindowUpdate { parent_id: Some(Some(mid)), ..WindowUpdate::default() })
+            .expect("parent leaf"),
+    );
+    batches.push(
+        reg.update_window(
+            leaf,
+            WindowUpdate {
+                geometry: Some(Some(WindowGeometry { x: 1, y: 2, width: 30, height: 40 })),
+                is_focused: Some(true),
+                ..WindowUpdate::default()
+            },
+        )
+        .expect("focus leaf"),
+    );
+    batches.push(retitle(&mut reg, root, "root"));
+    batches.push(reg.remove_window(mid).expect("remove mid").1);
+    batches.push(reg.on_unmap(leaf).expect("unmap leaf"));
+
+    for events in &batches {
+        mirror.apply_all(events).expect("apply batch");
+    }
+    assert_eq!(mirror.len(), 2);
+    assert_in_sync(&mirror, &reg);
+    assert_eq!(mirror.get(leaf).expect("leaf mirrored").parent_id, Some(root));
+    assert!(mirror.get(mid).is_none());
+}
+
+#[test]
+fn mirror_reports_divergence_and_recovers_with_reset() {
+    let mut reg = Registry::new();
+    let p = TestPtrs::new();
+    let (dk, sk) = unsafe { p.keys() };
+    let (id, _) = reg.insert_window(dk, sk).expect("insert");
+    let mut mirror = RegistryMirror::new(reg.snapshot_all());
+
+    let _lost = retitle(&mut reg, id, "first");
+    let events = retitle(&mut reg, id, "second");
+
+    let err = mirror.apply_all(&events).expect_err("old title does not match");
+    assert!(matches!(
+        err,
+        RegistryError::MirrorDiverged { id: err_id, field: Some(ChangeField::Title) } if err_id == id
+    ));
+    assert_eq!(mirror.get(id).expect("still mirrored").title, None);
+
+    mirror.reset(reg.snapshot_all());
+    assert_in_sync(&mirror, &reg);
+}
+
+#[test]
+fn mirror_reports_unknown_and_duplicate_windows() {
+    let mut reg = Registry::new();
+    let p = TestPtrs::new();
+    let (dk, sk) = unsafe { p.keys() };
+    let (id, created) = reg.insert_window(dk, sk).expect("insert");
+
+    let mut mirror = RegistryMirror::new(reg.snapshot_all());
+    assert!(matches!(
+        mirror.apply_all(&created),
+        Err(RegistryError::MirrorDiverged { field: None, .. })
+    ));
+
+    let (_record, removed) = reg.remove_window(id).expect("remove");
+    mirror.apply_all(&removed).expect("apply remove");
+    assert!(mirror.is_empty());
+    assert!(matches!(
+        mirror.apply(&RegistryEvent::WindowDestroyed { id }),
+        Err(RegistryError::MirrorDiverged { id: err_id, field: None }) if err_id == id
+    ));
+}
+
+#[test]
+fn mirror_applies_coalesced_transaction_batches() {
+    let mut reg = Registry::new();
+    reg.add_output(OutputInfo::new(OutputId(1), "DP-1", WindowGeometry { x: 0, y: 0, width: 800, height: 600 }))
+        .expect("add output");
+    let (ws, _) = reg.create_workspace("main", OutputId(1)).expect("create workspace");
+    let ptrs: Vec<TestPtrs> = (0..2).map(|_| TestPtrs::new()).collect();
+    let (dk, sk) = unsafe { ptrs[0].keys() };
+    let (w, _) = reg.insert_window(dk, sk).expect("insert");
+    let mut mirror = RegistryMirror::new(reg.snapshot_all());
+
+    // The title change must not absorb the move: Z's stack index depends on W still being
+    // on the output-less stack when Z is created.
+    let mut tx = reg.transaction();
+    tx.update_window(w, WindowUpdate { title: Some(Some("w".into())), ..WindowUpdate::default() }).expect("retitle");
+    let (dk, sk) = unsafe { ptrs[1].keys() };
+    tx.insert_window(dk, sk).expect("insert z");
+    let update =
+        WindowUpdate { workspace: Some(Some(ws)), output: Some(Some(OutputId(1))), ..WindowUpdate::default() };
+    tx.update_window(w, update).expect("move");
+    let events = tx.commit().expect("commit");
+
+    mirror.apply_all(&events).expect("apply coalesced batch");
+    assert_in_sync(&mirror, &reg);
+}
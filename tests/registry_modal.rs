@@ -0,0 +1,162 @@
+use window_registry::{
+    Query,
+    Registry,
+    RegistryError,
+    RegistryEvent,
+    SharedRegistry,
+    WindowChange,
+    WindowId,
+    WindowUpdate,
+};
+
+mod common;
+use common::{focus, insert_mapped, TestPtrs};
+
+fn make_modal(reg: &mut Registry, id: WindowId, parent: WindowId) -> Vec<RegistryEvent> {
+    let update = WindowUpdate { parent_id: Some(Some(parent)), modal: Some(true), ..WindowUpdate::default() };
+    reg.update_window(id, update).expect("make modal")
+}
+
+fn focused(reg: &Registry) -> Vec<WindowId> {
+    reg.query(&Query::Focused)
+}
+
+#[test]
+fn modal_requires_a_parent() {
+    let mut reg = Registry::new();
+    let ptrs: Vec<TestPtrs> = (0..2).map(|_| TestPtrs::new()).collect();
+    let parent = insert_mapped(&mut reg, &ptrs[0]);
+    let dialog = insert_mapped(&mut reg, &ptrs[1]);
+
+    let update = WindowUpdate { modal: Some(true), ..WindowUpdate::default() };
+    assert!(matches!(
+        reg.update_window(dialog, update),
+        Err(RegistryError::ModalWithoutParent { id }) if id == dialog
+    ));
+
+    let events = make_modal(&mut reg, dialog, parent);
+    assert!(matches!(
+        events.last(),
+        Some(RegistryEvent::WindowChanged { id, changes })
+            if *id == dialog && changes.modal == Some(WindowChange { old: false, new: true })
+    ));
+    assert_eq!(reg.query(&"modal".parse().expect("parse")), [dialog]);
+
+    // Detaching a modal window needs the flag cleared in the same update.
+    let detach = WindowUpdate { parent_id: Some(None), ..WindowUpdate::default() };
+    assert!(matches!(reg.update_window(dialog, detach.clone()), Err(RegistryError::ModalWithoutParent { .. })));
+    let update = WindowUpdate { remove_children: vec![dialog], ..WindowUpdate::default() };
+    assert!(matches!(
+        reg.update_window(parent, update),
+        Err(RegistryError::ModalWithoutParent { id }) if id == dialog
+    ));
+    reg.update_window(dialog, WindowUpdate { modal: Some(false), ..detach }).expect("detach");
+    assert!(!reg.snapshot(dialog).expect("live").modal);
+}
+
+#[test]
+fn focusing_a_blocked_window_focuses_its_dialog() {
+    let mut reg = Registry::new();
+    let ptrs: Vec<TestPtrs> = (0..4).map(|_| TestPtrs::new()).collect();
+    let editor = insert_mapped(&mut reg, &ptrs[0]);
+    let other = insert_mapped(&mut reg, &ptrs[1]);
+    let save = insert_mapped(&mut reg, &ptrs[2]);
+    let confirm = insert_mapped(&mut reg, &ptrs[3]);
+    make_modal(&mut reg, save, editor);
+    focus(&mut reg, other);
+
+    assert_eq!(reg.input_blocked_windows(), [editor]);
+    assert_eq!(reg.modal_child(editor), Some(save));
+    let events = focus(&mut reg, editor);
+    assert_eq!(events.len(), 2);
+    assert!(matches!(events[0], RegistryEvent::WindowChanged { id, .. } if id == other));
+    assert!(matches!(
+        &events[1],
+        RegistryEvent::WindowChanged { id, changes }
+            if *id == save && changes.is_focused == Some(WindowChange { old: false, new: true })
+    ));
+    assert_eq!(focused(&reg), [save]);
+
+    // Nested dialogs: focus goes to the innermost one, the other fields still apply.
+    make_modal(&mut reg, confirm, save);
+    assert_eq!(reg.input_blocked_windows(), [editor, save]);
+    assert_eq!(focused(&reg), [confirm]);
+    let update =
+        WindowUpdate { is_focused: Some(true), title: Some(Some("main.rs".into())), ..WindowUpdate::default() };
+    reg.update_window(editor, update).expect("focus");
+    assert_eq!(focused(&reg), [confirm]);
+    assert_eq!(reg.snapshot(editor).expect("live").title.as_deref(), Some("main.rs"));
+
+    // Closing the dialogs unblocks the chain; focus falls back to the parent.
+    reg.on_unmap(confirm).expect("unmap");
+    assert_eq!(focused(&reg), [save]);
+    reg.on_unmap(save).expect("unmap");
+    assert!(reg.input_blocked_windows().is_empty());
+    assert_eq!(focused(&reg), [editor]);
+    focus(&mut reg, editor);
+    assert_eq!(focused(&reg), [editor]);
+}
+
+#[test]
+fn mapping_a_dialog_takes_focus_from_its_parent() {
+    let reg = SharedRegistry::new(Registry::new());
+    let ptrs: Vec<TestPtrs> = (0..2).map(|_| TestPtrs::new()).collect();
+    let ids: Vec<WindowId> = ptrs
+        .iter()
+        .map(|p| {
+            let (dk, sk) = unsafe { p.keys() };
+            reg.insert_window_with(dk, sk, |_| {}).expect("insert")
+        })
+        .collect();
+    let [parent, dialog] = ids[..] else { unreachable!() };
+
+    reg.transaction_with(
+        |tx| {
+            tx.on_map(parent)?;
+            tx.update_window(parent, WindowUpdate { is_focused: Some(true), ..WindowUpdate::default() })?;
+            tx.update_window(
+                dialog,
+                WindowUpdate { parent_id: Some(Some(parent)), modal: Some(true), ..WindowUpdate::default() },
+            )
+        },
+        |_| {},
+    )
+    .expect("transaction");
+    assert!(!reg.is_input_blocked(parent));
+
+    let mut events = Vec::new();
+    reg.transaction_with(|tx| tx.on_map(dialog), |batch| events = batch).expect("map");
+    let focus: Vec<(WindowId, bool)> = events
+        .iter()
+        .filter_map(|event| match event {
+            RegistryEvent::WindowChanged { id, changes } => changes.is_focused.as_ref().map(|c| (*id, c.new)),
+            _ => None,
+        })
+        .collect();
+    // The dialog's focus gain is folded into its map change, which comes first.
+    assert_eq!(focus, [(dialog, true), (parent, false)]);
+    assert!(reg.is_input_blocked(parent));
+    assert_eq!(reg.input_blocked_windows(), [parent]);
+    assert!(reg.snapshot(dialog).expect("live").is_focused);
+    assert!(!reg.snapshot(parent).expect("live").is_focused);
+}
+
+#[test]
+fn orphaned_dialogs_stop_being_modal() {
+    let mut reg = Registry::new();
+    let ptrs: Vec<TestPtrs> = (0..2).map(|_| TestPtrs::new()).collect();
+    let parent = insert_mapped(&mut reg, &ptrs[0]);
+    let dialog = insert_mapped(&mut reg, &ptrs[1]);
+    make_modal(&mut reg, dialog, parent);
+
+    let (_, events) = reg.remove_window(parent).expect("remove");
+    assert!(events.iter().any(|event| matches!(
+        event,
+        RegistryEvent::WindowChanged { id, changes }
+            if *id == dialog
+                && changes.parent_id.is_some()
+                && changes.modal == Some(WindowChange { old: true, new: false })
+    )));
+    let info = reg.snapshot(dialog).expect("live");
+    assert_eq!((info.parent_id, info.modal), (None, false));
+}
@@ -0,0 +1,190 @@
+use window_registry::{
+    EventFilter,
+    EventKind,
+    OutputId,
+    OutputInfo,
+    OutputTransform,
+    OutputUpdate,
+    Registry,
+    RegistryError,
+    RegistryEvent,
+    RegistryEventQueue,
+    SharedRegistry,
+    WindowGeometry,
+    WindowId,
+    WindowUpdate,
+    WorkspaceId,
+};
+
+mod common;
+use common::{insert, TestPtrs};
+
+fn rect(x: i32, y: i32, width: i32, height: i32) -> WindowGeometry {
+    WindowGeometry { x, y, width, height }
+}
+
+fn place(reg: &mut Registry, id: WindowId, workspace: WorkspaceId, output: u32, geometry: WindowGeometry) {
+    let update = WindowUpdate {
+        workspace: Some(Some(workspace)),
+        output: Some(Some(OutputId(output))),
+        geometry: Some(Some(geometry)),
+        ..WindowUpdate::default()
+    };
+    reg.update_window(id, update).expect("place");
+}
+
+#[test]
+fn add_update_and_validate_outputs() {
+    let mut reg = Registry::new();
+    let p = TestPtrs::new();
+    let id = insert(&mut reg, &p);
+
+    // Free-form output ids keep working until the first output is added.
+    let on_nine = WindowUpdate {
+        workspace: Some(Some(WorkspaceId(1))),
+        output: Some(Some(OutputId(9))),
+        ..WindowUpdate::default()
+    };
+    reg.update_window(id, on_nine.clone()).expect("unmanaged");
+
+    let events = reg.add_output(OutputInfo::new(OutputId(1), "DP-1", rect(0, 0, 1920, 1080))).expect("add");
+    assert!(matches!(&events[..], [RegistryEvent::OutputAdded { output }] if output.id == OutputId(1)));
+    assert!(matches!(
+        reg.add_output(OutputInfo::new(OutputId(1), "DP-2", rect(0, 0, 1, 1))),
+        Err(RegistryError::OutputAlreadyRegistered(OutputId(1)))
+    ));
+    assert!(matches!(
+        reg.add_output(OutputInfo::new(OutputId(2), "DP-1", rect(0, 0, 1, 1))),
+        Err(RegistryError::OutputNameTaken { existing: OutputId(1), .. })
+    ));
+    assert!(matches!(
+        reg.update_window(id, on_nine),
+        Err(RegistryError::UnknownOutput { output: OutputId(9), .. })
+    ));
+    assert!(matches!(reg.create_workspace("web", OutputId(9)), Err(RegistryError::InvalidOutputId(OutputId(9)))));
+
+    let update = OutputUpdate { scale: Some(2.0), transform: Some(OutputTransform::Rotate90), ..OutputUpdate::default() };
+    let events = reg.update_output(OutputId(1), update.clone()).expect("update");
+    assert!(matches!(
+        &events[..],
+        [RegistryEvent::OutputChanged { old, new, .. }] if old.scale == 1.0 && new.scale == 2.0
+    ));
+    assert!(reg.update_output(OutputId(1), update).expect("no change").is_empty());
+    assert!(matches!(
+        reg.update_output(OutputId(1), OutputUpdate { scale: Some(0.0), ..OutputUpdate::default() }),
+        Err(RegistryError::InvalidOutputScale { .. })
+    ));
+    let output = reg.output(OutputId(1)).expect("known");
+    assert_eq!((output.scale, output.transform), (2.0, OutputTransform::Rotate90));
+    assert_eq!(reg.output_by_name("DP-1"), Some(OutputId(1)));
+}
+
+#[test]
+fn removing_output_migrates_workspaces_and_windows() {
+    let mut reg = Registry::new();
+    reg.add_output(OutputInfo::new(OutputId(1), "eDP-1", rect(0, 0, 1920, 1080))).expect("add");
+    reg.add_output(OutputInfo::new(OutputId(2), "HDMI-A-1", rect(1920, 0, 1280, 720))).expect("add");
+    let (laptop, _) = reg.create_workspace("laptop", OutputId(1)).expect("create");
+    let (tv, _) = reg.create_workspace("tv", OutputId(2)).expect("create");
+    let ptrs: Vec<TestPtrs> = (0..2).map(|_| TestPtrs::new()).collect();
+    let small = insert(&mut reg, &ptrs[0]);
+    let wide = insert(&mut reg, &ptrs[1]);
+    place(&mut reg, small, tv, 2, rect(2020, 100, 400, 300));
+    place(&mut reg, wide, tv, 2, rect(1920, 600, 1280, 100));
+
+    let events = reg.remove_output(OutputId(2)).expect("remove");
+    assert!(matches!(
+        events[0],
+        RegistryEvent::WorkspaceMoved { id, old: OutputId(2), new: OutputId(1) } if id == tv
+    ));
+    assert!(matches!(events[1], RegistryEvent::WorkspaceActivated { output: OutputId(2), new: None, .. }));
+    let moved: Vec<WindowId> = events
+        .iter()
+        .filter(|event| matches!(event, RegistryEvent::WindowChanged { changes, .. } if changes.output.is_some()))
+        .filter_map(|event| event.window())
+        .collect();
+    assert_eq!(moved, [small, wide]);
+    assert!(matches!(events.last(), Some(RegistryEvent::OutputRemoved { fallback: Some(OutputId(1)), .. })));
+
+    // Offsets from the output origin are kept, clamped to the fallback.
+    assert_eq!(reg.snapshot(small).expect("live").geometry, Some(rect(100, 100, 400, 300)));
+    assert_eq!(reg.snapshot(wide).expect("live").geometry, Some(rect(0, 600, 1280, 100)));
+    assert_eq!(reg.workspace(tv).expect("known").output, OutputId(1));
+    assert_eq!(reg.active_workspace(OutputId(1)), Some(laptop));
+    assert_eq!(reg.windows_on_output(OutputId(1)), &[small, wide]);
+    assert!(reg.windows_on_output(OutputId(2)).is_empty());
+    assert_eq!(reg.outputs().len(), 1);
+
+    // The last output has nowhere to send its workspaces.
+    assert!(matches!(reg.remove_output(OutputId(1)), Err(RegistryError::NoFallbackOutput { .. })));
+}
+
+#[test]
+fn failed_output_removal_changes_nothing() {
+    let mut reg = Registry::new();
+    reg.add_output(OutputInfo::new(OutputId(1), "eDP-1", rect(100, 0, 1920, 1080))).expect("add");
+    reg.add_output(OutputInfo::new(OutputId(2), "HDMI-A-1", rect(0, 0, 1280, 720))).expect("add");
+    let (tv, _) = reg.create_workspace("tv", OutputId(2)).expect("create");
+    let ptrs: Vec<TestPtrs> = (0..2).map(|_| TestPtrs::new()).collect();
+    let small = insert(&mut reg, &ptrs[0]);
+    let huge = insert(&mut reg, &ptrs[1]);
+    place(&mut reg, small, tv, 2, rect(0, 0, 400, 300));
+    // Moved onto the fallback's origin, its right edge no longer fits in an i32.
+    place(&mut reg, huge, tv, 2, rect(0, 0, i32::MAX, 100));
+
+    assert!(matches!(
+        reg.remove_output(OutputId(2)),
+        Err(RegistryError::GeometryOverflow { id, .. }) if id == huge
+    ));
+    assert_eq!(reg.outputs().len(), 2);
+    assert_eq!(reg.workspace(tv).expect("known").output, OutputId(2));
+    assert_eq!(reg.active_workspace(OutputId(2)), Some(tv));
+    assert_eq!(reg.windows_on_output(OutputId(2)), &[small, huge]);
+    assert_eq!(reg.snapshot(small).expect("live").geometry, Some(rect(0, 0, 400, 300)));
+}
+
+#[test]
+fn removal_prefers_enabled_fallback_and_takes_over_activation() {
+    let mut reg = Registry::new();
+    for (id, name) in [(1, "DP-1"), (2, "DP-2"), (3, "DP-3")] {
+        reg.add_output(OutputInfo::new(OutputId(id), name, rect(0, 0, 800, 600))).expect("add");
+    }
+    reg.update_output(OutputId(2), OutputUpdate { enabled: Some(false), ..OutputUpdate::default() }).expect("disable");
+    let (ws, _) = reg.create_workspace("main", OutputId(1)).expect("create");
+
+    let events = reg.remove_output(OutputId(1)).expect("remove");
+    assert!(matches!(events.last(), Some(RegistryEvent::OutputRemoved { fallback: Some(OutputId(3)), .. })));
+    assert_eq!(reg.active_workspace(OutputId(3)), Some(ws));
+
+    // Empty outputs go away even without a fallback.
+    reg.remove_output(OutputId(2)).expect("remove");
+    let err = reg.remove_output(OutputId(3)).expect_err("holds a workspace");
+    assert!(matches!(err, RegistryError::NoFallbackOutput { output: OutputId(3) }));
+}
+
+#[test]
+fn output_events_flow_through_queued_transactions() {
+    let reg = SharedRegistry::new(Registry::new());
+    let queue = RegistryEventQueue::unbounded();
+    let output_events = queue.subscribe_filtered(EventFilter::kinds([EventKind::Output]));
+    let on_output_one = queue.subscribe_filtered(EventFilter::default().on_output(OutputId(1)));
+
+    reg.transaction_queued(
+        |tx| {
+            tx.add_output(OutputInfo::new(OutputId(1), "DP-1", rect(0, 0, 800, 600)))?;
+            tx.add_output(OutputInfo::new(OutputId(2), "DP-2", rect(800, 0, 800, 600)))?;
+            tx.create_workspace("web", OutputId(2))?;
+            tx.remove_output(OutputId(2))
+        },
+        &queue,
+    )
+    .expect("transaction");
+    drop(queue);
+
+    assert_eq!(reg.outputs().iter().map(|out| out.id).collect::<Vec<_>>(), [OutputId(1)]);
+    let outputs: Vec<Option<OutputId>> = output_events.iter().map(|event| event.output()).collect();
+    assert_eq!(outputs, [Some(OutputId(1)), Some(OutputId(2)), Some(OutputId(2))]);
+    // The output-1 filter sees its addition, the workspace moving over and its activation.
+    let kinds: Vec<EventKind> = on_output_one.iter().map(|event| event.kind()).collect();
+    assert_eq!(kinds, [EventKind::Output, EventKind::Workspace, EventKind::Workspace]);
+}
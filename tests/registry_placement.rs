@@ -0,0 +1,176 @@
+use window_registry::{
+    ChildRemovalPolicy,
+    OutputId,
+    Placement,
+    PlacementStore,
+    Registry,
+    RegistryEvent,
+    WindowGeometry,
+    WindowId,
+    WindowState,
+    WindowUpdate,
+    WorkspaceId,
+};
+
+mod common;
+use common::{insert, TestPtrs};
+
+fn set_app_id(reg: &mut Registry, id: WindowId, app_id: &str) -> Vec<RegistryEvent> {
+    let update = WindowUpdate { app_id: Some(Some(app_id.to_string())), ..WindowUpdate::default() };
+    reg.update_window(id, update).expect("set app_id should succeed")
+}
+
+fn geometry(x: i32, y: i32) -> WindowGeometry {
+    WindowGeometry { x, y, width: 800, height: 600 }
+}
+
+/// Maps `id` and places it at `geom` on workspace 2 / output 1, maximized.
+fn place(reg: &mut Registry, id: WindowId, geom: WindowGeometry) {
+    reg.on_map(id).expect("map");
+    let update = WindowUpdate {
+        geometry: Some(Some(geom)),
+        state: Some(WindowState { maximized: true, ..WindowState::default() }),
+        workspace: Some(Some(WorkspaceId(2))),
+        output: Some(Some(OutputId(1))),
+        ..WindowUpdate::default()
+    };
+    reg.update_window(id, update).expect("place");
+}
+
+fn with_memory() -> Registry {
+    let mut reg = Registry::new();
+    reg.set_placement_store(Some(PlacementStore::new()));
+    reg
+}
+
+#[test]
+fn placement_is_restored_for_next_window_with_app_id() {
+    let mut reg = with_memory();
+    let p1 = TestPtrs::new();
+    let p2 = TestPtrs::new();
+
+    let first = insert(&mut reg, &p1);
+    set_app_id(&mut reg, first, "firefox");
+    place(&mut reg, first, geometry(40, 30));
+    reg.remove_window(first).expect("remove");
+
+    let saved = reg.placement_store().expect("enabled").get("firefox").copied();
+    assert_eq!(
+        saved,
+        Some(Placement {
+            geometry: Some(geometry(40, 30)),
+            state: WindowState { maximized: true, ..WindowState::default() },
+            workspace: Some(WorkspaceId(2)),
+            output: Some(OutputId(1)),
+        })
+    );
+
+    let second = insert(&mut reg, &p2);
+    let events = set_app_id(&mut reg, second, "firefox");
+    assert_eq!(events.len(), 1, "restore shares the app_id change: {events:?}");
+    let RegistryEvent::WindowChanged { changes, .. } = &events[0] else { panic!("{events:?}") };
+    assert!(changes.app_id.is_some());
+    assert_eq!(changes.geometry.as_ref().map(|c| c.new), Some(Some(geometry(40, 30))));
+
+    let info = reg.snapshot(second).expect("live");
+    assert_eq!(info.geometry, Some(geometry(40, 30)));
+    assert!(info.state.maximized);
+    assert_eq!(info.workspace, Some(WorkspaceId(2)));
+    assert_eq!(info.output, Some(OutputId(1)));
+}
+
+#[test]
+fn explicit_fields_win_over_saved_placement() {
+    let mut reg = with_memory();
+    let p1 = TestPtrs::new();
+    let p2 = TestPtrs::new();
+
+    let first = insert(&mut reg, &p1);
+    set_app_id(&mut reg, first, "term");
+    place(&mut reg, first, geometry(10, 10));
+    reg.remove_window(first).expect("remove");
+
+    let second = insert(&mut reg, &p2);
+    let update = WindowUpdate {
+        app_id: Some(Some("term".to_string())),
+        geometry: Some(Some(geometry(500, 500))),
+        workspace: Some(Some(WorkspaceId(7))),
+        output: Some(Some(OutputId(3))),
+        ..WindowUpdate::default()
+    };
+    reg.update_window(second, update).expect("update");
+
+    let info = reg.snapshot(second).expect("live");
+    assert_eq!(info.geometry, Some(geometry(500, 500)));
+    assert_eq!(info.workspace, Some(WorkspaceId(7)));
+    assert_eq!(info.output, Some(OutputId(3)));
+    assert!(info.state.maximized, "state was not given, so it is restored");
+
+    // Only the first app_id triggers a restore.
+    reg.update_window(second, WindowUpdate { geometry: Some(Some(geometry(1, 1))), ..WindowUpdate::default() })
+        .expect("move");
+    set_app_id(&mut reg, second, "other");
+    set_app_id(&mut reg, second, "term");
+    assert_eq!(reg.snapshot(second).expect("live").geometry, Some(geometry(1, 1)));
+}
+
+#[test]
+fn transients_unmapped_and_minimized_windows_are_handled() {
+    let mut reg = with_memory();
+    reg.set_child_removal_policy(ChildRemovalPolicy::DestroySubtree);
+    let p1 = TestPtrs::new();
+    let p2 = TestPtrs::new();
+    let p3 = TestPtrs::new();
+
+    let never_mapped = insert(&mut reg, &p1);
+    set_app_id(&mut reg, never_mapped, "ghost");
+    reg.remove_window(never_mapped).expect("remove");
+    assert!(reg.placement_store().expect("enabled").get("ghost").is_none());
+
+    let main = insert(&mut reg, &p2);
+    let dialog = insert(&mut reg, &p3);
+    set_app_id(&mut reg, main, "gimp");
+    place(&mut reg, main, geometry(0, 0));
+    reg.update_window(main, WindowUpdate { state: Some(WindowState { minimized: true, ..WindowState::default() }), ..WindowUpdate::default() })
+        .expect("minimize");
+    reg.update_window(dialog, WindowUpdate { parent_id: Some(Some(main)), ..WindowUpdate::default() })
+        .expect("parent");
+    set_app_id(&mut reg, dialog, "gimp");
+    place(&mut reg, dialog, geometry(300, 300));
+
+    reg.remove_window(main).expect("remove subtree");
+
+    let saved = reg.placement_store().expect("enabled").get("gimp").copied().expect("saved");
+    assert_eq!(saved.geometry, Some(geometry(0, 0)), "the dialog must not overwrite the main window");
+    assert_eq!(saved.state, WindowState::default(), "windows do not come back minimized");
+}
+
+#[test]
+fn placement_memory_is_off_by_default() {
+    let mut reg = Registry::new();
+    let p1 = TestPtrs::new();
+    let p2 = TestPtrs::new();
+
+    let first = insert(&mut reg, &p1);
+    set_app_id(&mut reg, first, "firefox");
+    place(&mut reg, first, geometry(40, 30));
+    reg.remove_window(first).expect("remove");
+    assert!(reg.placement_store().is_none());
+
+    let second = insert(&mut reg, &p2);
+    set_app_id(&mut reg, second, "firefox");
+    assert_eq!(reg.snapshot(second).expect("live").geometry, None);
+}
+
+#[cfg(feature = "serde")]
+#[test]
+fn placement_store_round_trips_as_map() {
+    let mut store = PlacementStore::new();
+    store.insert("firefox", Placement { geometry: Some(geometry(1, 2)), ..Placement::default() });
+
+    let json = serde_json::to_value(&store).expect("serialize");
+    assert!(json.get("firefox").is_some(), "{json}");
+
+    let back: PlacementStore = serde_json::from_value(json).expect("deserialize");
+    assert_eq!(back, store);
+}
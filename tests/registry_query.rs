@@ -0,0 +1,93 @@
+use window_registry::{
+    LifecycleState,
+    OutputId,
+    Query,
+    Registry,
+    WindowId,
+    WindowState,
+    WindowUpdate,
+    WorkspaceId,
+};
+
+mod common;
+use common::{insert, TestPtrs};
+
+fn setup(app_id: &str, title: &str, workspace: u32, reg: &mut Registry, p: &TestPtrs) -> WindowId {
+    let id = insert(reg, p);
+    let update = WindowUpdate {
+        app_id: Some(Some(app_id.to_string())),
+        title: Some(Some(title.to_string())),
+        workspace: Some(Some(WorkspaceId(workspace))),
+        output: Some(Some(OutputId(1))),
+        ..WindowUpdate::default()
+    };
+    reg.update_window(id, update).expect("setup");
+    id
+}
+
+fn query(reg: &Registry, text: &str) -> Vec<WindowId> {
+    let query: Query = text.parse().unwrap_or_else(|err| panic!("{text}: {err}"));
+    reg.query(&query)
+}
+
+#[test]
+fn textual_queries_select_windows() {
+    let mut reg = Registry::new();
+    let ptrs: Vec<TestPtrs> = (0..4).map(|_| TestPtrs::new()).collect();
+    let ff3 = setup("firefox", "Mozilla Firefox", 3, &mut reg, &ptrs[0]);
+    let ff1 = setup("firefox", "Open File", 1, &mut reg, &ptrs[1]);
+    let term = setup("foot", "~", 3, &mut reg, &ptrs[2]);
+    let bare = insert(&mut reg, &ptrs[3]);
+    reg.on_map(ff3).expect("map");
+    reg.on_map(term).expect("map");
+    reg.update_window(ff1, WindowUpdate { parent_id: Some(Some(ff3)), ..WindowUpdate::default() })
+        .expect("parent");
+    let minimized = WindowState { minimized: true, ..WindowState::default() };
+    reg.update_window(term, WindowUpdate { state: Some(minimized), ..WindowUpdate::default() })
+        .expect("minimize");
+
+    assert_eq!(query(&reg, r#"app_id="firefox" && workspace=3 && mapped"#), vec![ff3]);
+    assert_eq!(query(&reg, r#"app_id == "firefox""#), vec![ff3, ff1]);
+    assert_eq!(query(&reg, "workspace=3 && !minimized"), vec![ff3]);
+    assert_eq!(query(&reg, r#"title~"^Open" || minimized"#), vec![ff1, term]);
+    assert_eq!(query(&reg, "transient"), vec![ff1]);
+    assert_eq!(query(&reg, &format!("parent={ff3}")), vec![ff1]);
+    assert_eq!(query(&reg, "app_id=none"), vec![bare]);
+    assert_eq!(query(&reg, "output!=none && !(created || unmapped)"), vec![ff3, term]);
+    assert_eq!(query(&reg, &format!("id={bare} || id={term}")), vec![term, bare]);
+    assert_eq!(query(&reg, r#"title="\"quoted\"""#), Vec::<WindowId>::new());
+}
+
+#[test]
+fn built_queries_match_records() {
+    let mut reg = Registry::new();
+    let p1 = TestPtrs::new();
+    let p2 = TestPtrs::new();
+    let a = setup("mpv", "video", 2, &mut reg, &p1);
+    let b = setup("mpv", "audio", 5, &mut reg, &p2);
+    reg.on_map(b).expect("map");
+
+    let query = Query::AppId(Some("mpv".to_string())).and(!Query::Lifecycle(LifecycleState::Mapped));
+    assert_eq!(reg.query(&query), vec![a]);
+    let query = Query::Workspace(Some(WorkspaceId(5))).or(Query::Title(Some("video".to_string())));
+    assert_eq!(reg.query(&query), vec![a, b]);
+}
+
+#[test]
+fn parse_errors_report_position() {
+    for (text, position) in [
+        ("app_id=", 7),
+        ("workspace=three", 10),
+        ("colour=\"red\"", 0),
+        ("mapped &&", 9),
+        ("(mapped", 7),
+        ("mapped focused", 7),
+        ("title~\"(\"", 6),
+        ("workspace~\"1\"", 0),
+        ("app_id=\"open", 7),
+        ("mapped & focused", 7),
+    ] {
+        let err = Query::parse(text).expect_err(text);
+        assert_eq!(err.position(), position, "{text}: {err}");
+    }
+}
@@ -0,0 +1,221 @@
+use window_registry::{
+    ChildRemovalPolicy,
+    Registry,
+    RegistryError,
+    RegistryEvent,
+    WindowChange,
+    WindowId,
+    WindowUpdate,
+};
+
+mod common;
+use common::TestPtrs;
+
+fn set_parent(reg: &mut Registry, child: WindowId, parent: WindowId) {
+    let update = WindowUpdate { parent_id: Some(Some(parent)), ..WindowUpdate::default() };
+    reg.update_window(child, update).expect("set parent should succeed");
+}
+
+#[test]
+fn remove_window_detaches_from_parent() {
+    let mut reg = Registry::new();
+    let p1 = TestPtrs::new();
+    let p2 = TestPtrs::new();
+
+    let (dk1, sk1) = unsafe { p1.keys() };
+    let (dk2, sk2) = unsafe { p2.keys() };
+    let parent = reg.insert_window(dk1, sk1).expect("insert parent").0;
+    let child = reg.insert_window(dk2, sk2).expect("insert child").0;
+    set_parent(&mut reg, child, parent);
+
+    let (_record, events) = reg.remove_window(child).expect("remove child");
+
+    assert_eq!(events.len(), 2);
+    assert!(matches!(
+        events[0],
+        RegistryEvent::WindowChanged { id, ref changes }
+            if id == parent
+                && changes.children == Some(WindowChange { old: vec![child], new: vec![] })
+    ));
+    assert!(matches!(events[1], RegistryEvent::WindowDestroyed { id } if id == child));
+
+    let parent_snap = reg.snapshot(parent).expect("parent snapshot");
+    assert!(parent_snap.children.is_empty());
+}
+
+#[test]
+fn remove_window_orphans_children_by_default() {
+    let mut reg = Registry::new();
+    let p1 = TestPtrs::new();
+    let p2 = TestPtrs::new();
+    let p3 = TestPtrs::new();
+
+    let (dk1, sk1) = unsafe { p1.keys() };
+    let (dk2, sk2) = unsafe { p2.keys() };
+    let (dk3, sk3) = unsafe { p3.keys() };
+    let parent = reg.insert_window(dk1, sk1).expect("insert parent").0;
+    let child_a = reg.insert_window(dk2, sk2).expect("insert child A").0;
+    let child_b = reg.insert_window(dk3, sk3).expect("insert child B").0;
+    set_parent(&mut reg, child_a, parent);
+    set_parent(&mut reg, child_b, parent);
+
+    assert_eq!(reg.child_removal_policy(), ChildRemovalPolicy::Orphan);
+    let (_record, events) = reg.remove_window(parent).expect("remove parent");
+
+    assert_eq!(events.len(), 5);
+    assert!(matches!(
+        events[0],
+        RegistryEvent::WindowChanged { id, ref changes }
+            if id == child_a
+                && changes.parent_id == Some(WindowChange { old: Some(parent), new: None })
+    ));
+    assert!(matches!(
+        events[1],
+        RegistryEvent::WindowChanged { id, ref changes }
+            if id == child_b
+                && changes.parent_id == Some(WindowChange { old: Some(parent), new: None })
+    ));
+    assert!(matches!(
+        events[2],
+        RegistryEvent::WindowChanged { id, ref changes }
+            if id == child_a && changes.stack_index == Some(WindowChange { old: 1, new: 0 })
+    ));
+    assert!(matches!(
+        events[3],
+        RegistryEvent::WindowChanged { id, ref changes }
+            if id == child_b && changes.stack_index == Some(WindowChange { old: 2, new: 1 })
+    ));
+    assert!(matches!(events[4], RegistryEvent::WindowDestroyed { id } if id == parent));
+
+    assert_eq!(reg.snapshot(child_a).expect("child A").parent_id, None);
+    assert_eq!(reg.snapshot(child_b).expect("child B").parent_id, None);
+}
+
+#[test]
+fn remove_window_reparents_children_to_grandparent() {
+    let mut reg = Registry::new();
+    reg.set_child_removal_policy(ChildRemovalPolicy::Reparent);
+
+    let p1 = TestPtrs::new();
+    let p2 = TestPtrs::new();
+    let p3 = TestPtrs::new();
+
+    let (dk1, sk1) = unsafe { p1.keys() };
+    let (dk2, sk2) = unsafe { p2.keys() };
+    let (dk3, sk3) = unsafe { p3.keys() };
+    let grandparent = reg.insert_window(dk1, sk1).expect("insert grandparent").0;
+    let parent = reg.insert_window(dk2, sk2).expect("insert parent").0;
+    let child = reg.insert_window(dk3, sk3).expect("insert child").0;
+    set_parent(&mut reg, parent, grandparent);
+    set_parent(&mut reg, child, parent);
+
+    let (_record, events) = reg.remove_window(parent).expect("remove parent");
+
+    assert_eq!(events.len(), 4);
+    assert!(matches!(
+        events[0],
+        RegistryEvent::WindowChanged { id, ref changes }
+            if id == grandparent
+                && changes.children == Some(WindowChange { old: vec![parent], new: vec![child] })
+    ));
+    assert!(matches!(
+        events[1],
+        RegistryEvent::WindowChanged { id, ref changes }
+            if id == child
+                && changes.parent_id == Some(WindowChange { old: Some(parent), new: Some(grandparent) })
+    ));
+    assert!(matches!(
+        events[2],
+        RegistryEvent::WindowChanged { id, ref changes }
+            if id == child && changes.stack_index == Some(WindowChange { old: 2, new: 1 })
+    ));
+    assert!(matches!(events[3], RegistryEvent::WindowDestroyed { id } if id == parent));
+
+    assert_eq!(reg.snapshot(grandparent).expect("grandparent").children, vec![child]);
+    assert_eq!(reg.snapshot(child).expect("child").parent_id, Some(grandparent));
+}
+
+#[test]
+fn remove_window_reparent_without_grandparent_orphans() {
+    let mut reg = Registry::new();
+    reg.set_child_removal_policy(ChildRemovalPolicy::Reparent);
+
+    let p1 = TestPtrs::new();
+    let p2 = TestPtrs::new();
+
+    let (dk1, sk1) = unsafe { p1.keys() };
+    let (dk2, sk2) = unsafe { p2.keys() };
+    let parent = reg.insert_window(dk1, sk1).expect("insert parent").0;
+    let child = reg.insert_window(dk2, sk2).expect("insert child").0;
+    set_parent(&mut reg, child, parent);
+
+    reg.remove_window(parent).expect("remove parent");
+
+    assert_eq!(reg.snapshot(child).expect("child").parent_id, None);
+}
+
+#[test]
+fn remove_window_destroys_transient_subtree() {
+    let mut reg = Registry::new();
+    reg.set_child_removal_policy(ChildRemovalPolicy::DestroySubtree);
+
+    let p1 = TestPtrs::new();
+    let p2 = TestPtrs::new();
+    let p3 = TestPtrs::new();
+    let p4 = TestPtrs::new();
+
+    let (dk1, sk1) = unsafe { p1.keys() };
+    let (dk2, sk2) = unsafe { p2.keys() };
+    let (dk3, sk3) = unsafe { p3.keys() };
+    let (dk4, sk4) = unsafe { p4.keys() };
+    let root = reg.insert_window(dk1, sk1).expect("insert root").0;
+    let dialog = reg.insert_window(dk2, sk2).expect("insert dialog").0;
+    let nested = reg.insert_window(dk3, sk3).expect("insert nested").0;
+    let other = reg.insert_window(dk4, sk4).expect("insert other").0;
+    set_parent(&mut reg, dialog, root);
+    set_parent(&mut reg, nested, dialog);
+
+    let (record, events) = reg.remove_window(root).expect("remove root");
+    assert_eq!(record.id, root);
+
+    assert_eq!(events.len(), 4);
+    assert!(matches!(
+        events[0],
+        RegistryEvent::WindowChanged { id, ref changes }
+            if id == other && changes.stack_index == Some(WindowChange { old: 3, new: 0 })
+    ));
+    assert!(matches!(events[1], RegistryEvent::WindowDestroyed { id } if id == nested));
+    assert!(matches!(events[2], RegistryEvent::WindowDestroyed { id } if id == dialog));
+    assert!(matches!(events[3], RegistryEvent::WindowDestroyed { id } if id == root));
+
+    assert!(reg.snapshot(dialog).is_none());
+    assert!(reg.snapshot(nested).is_none());
+    assert_eq!(reg.from_desktop(dk2), None);
+    assert_eq!(reg.from_surface(sk3), None);
+    assert_eq!(reg.snapshot_all().len(), 1);
+}
+
+#[test]
+fn update_window_after_parent_removed_succeeds() {
+    let mut reg = Registry::new();
+    let p1 = TestPtrs::new();
+    let p2 = TestPtrs::new();
+    let p3 = TestPtrs::new();
+
+    let (dk1, sk1) = unsafe { p1.keys() };
+    let (dk2, sk2) = unsafe { p2.keys() };
+    let (dk3, sk3) = unsafe { p3.keys() };
+    let parent = reg.insert_window(dk1, sk1).expect("insert parent").0;
+    let child = reg.insert_window(dk2, sk2).expect("insert child").0;
+    let next_parent = reg.insert_window(dk3, sk3).expect("insert next parent").0;
+    set_parent(&mut reg, child, parent);
+
+    reg.remove_window(parent).expect("remove parent");
+
+    let update = WindowUpdate { parent_id: Some(Some(parent)), ..WindowUpdate::default() };
+    let err = reg.update_window(child, update).expect_err("stale parent should fail");
+    assert!(matches!(err, RegistryError::ParentNotFound { .. }));
+
+    set_parent(&mut reg, child, next_parent);
+    assert_eq!(reg.snapshot(next_parent).expect("next parent").children, vec![child]);
+}
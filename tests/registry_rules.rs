@@ -0,0 +1,193 @@
+use window_registry::{
+    FakeWeston,
+    OutputId,
+    Registry,
+    RegistryAdapter,
+    RegistryError,
+    RegistryEvent,
+    RegistryEventQueue,
+    RuleActions,
+    RuleMatch,
+    SharedRegistry,
+    WestonEvent,
+    WindowGeometry,
+    WindowId,
+    WindowRule,
+    WindowState,
+    WindowUpdate,
+    WorkspaceId,
+};
+
+mod common;
+use common::{insert, TestPtrs};
+
+fn set_app_id(reg: &mut Registry, id: WindowId, app_id: &str) -> Vec<RegistryEvent> {
+    let update = WindowUpdate { app_id: Some(Some(app_id.to_string())), ..WindowUpdate::default() };
+    reg.update_window(id, update).expect("set app_id should succeed")
+}
+
+fn send_to(workspace: u32, output: u32) -> RuleActions {
+    RuleActions { place: Some((WorkspaceId(workspace), OutputId(output))), ..RuleActions::default() }
+}
+
+const FLOAT: WindowGeometry = WindowGeometry { x: 100, y: 100, width: 400, height: 300 };
+
+#[test]
+fn app_id_rule_applies_in_same_event() {
+    let mut reg = Registry::new();
+    reg.add_rule(WindowRule::new(RuleMatch::app_id("slack"), send_to(4, 1)));
+    let p = TestPtrs::new();
+    let id = insert(&mut reg, &p);
+
+    let events = set_app_id(&mut reg, id, "slack");
+    assert_eq!(events.len(), 1, "{events:?}");
+    let RegistryEvent::WindowChanged { changes, .. } = &events[0] else { panic!("{events:?}") };
+    assert!(changes.app_id.is_some());
+    assert_eq!(changes.workspace.as_ref().map(|c| c.new), Some(Some(WorkspaceId(4))));
+    assert_eq!(changes.output.as_ref().map(|c| c.new), Some(Some(OutputId(1))));
+
+    // Rules run once: moving the window afterwards sticks.
+    let update = WindowUpdate {
+        workspace: Some(Some(WorkspaceId(1))),
+        output: Some(Some(OutputId(1))),
+        title: Some(Some("general".to_string())),
+        ..WindowUpdate::default()
+    };
+    reg.update_window(id, update).expect("move");
+    reg.on_map(id).expect("map");
+    assert_eq!(reg.snapshot(id).expect("live").workspace, Some(WorkspaceId(1)));
+}
+
+#[test]
+fn title_and_parent_rules_float_dialogs_on_map() {
+    let mut reg = Registry::new();
+    let matches = RuleMatch::default().with_title("^Open File").expect("valid pattern").with_parent(true);
+    reg.set_rules(vec![WindowRule::new(
+        matches,
+        RuleActions { geometry: Some(FLOAT), ..RuleActions::default() },
+    )]);
+    let p1 = TestPtrs::new();
+    let p2 = TestPtrs::new();
+    let main = insert(&mut reg, &p1);
+    let dialog = insert(&mut reg, &p2);
+
+    // The title becomes known before the parent: no match yet.
+    let title = WindowUpdate { title: Some(Some("Open File - Editor".to_string())), ..WindowUpdate::default() };
+    reg.update_window(dialog, title).expect("title");
+    reg.update_window(dialog, WindowUpdate { parent_id: Some(Some(main)), ..WindowUpdate::default() })
+        .expect("parent");
+    assert_eq!(reg.snapshot(dialog).expect("live").geometry, None);
+
+    let events = reg.on_map(dialog).expect("map");
+    assert_eq!(events.len(), 1, "{events:?}");
+    let RegistryEvent::WindowChanged { changes, .. } = &events[0] else { panic!("{events:?}") };
+    assert!(changes.lifecycle.is_some());
+    assert_eq!(changes.geometry.as_ref().map(|c| c.new), Some(Some(FLOAT)));
+
+    reg.on_map(main).expect("map main");
+    assert_eq!(reg.snapshot(main).expect("live").geometry, None);
+}
+
+#[test]
+fn output_and_state_rules() {
+    let mut reg = Registry::new();
+    let maximized = WindowState { maximized: true, ..WindowState::default() };
+    reg.add_rule(WindowRule::new(
+        RuleMatch::default().on_output(OutputId(2)),
+        RuleActions { state: Some(maximized), ..RuleActions::default() },
+    ));
+    let p1 = TestPtrs::new();
+    let p2 = TestPtrs::new();
+    let left = insert(&mut reg, &p1);
+    let right = insert(&mut reg, &p2);
+
+    for (id, output) in [(left, 1), (right, 2)] {
+        let update = WindowUpdate {
+            workspace: Some(Some(WorkspaceId(1))),
+            output: Some(Some(OutputId(output))),
+            ..WindowUpdate::default()
+        };
+        reg.update_window(id, update).expect("place");
+        reg.on_map(id).expect("map");
+    }
+
+    assert_eq!(reg.snapshot(left).expect("live").state, WindowState::default());
+    assert_eq!(reg.snapshot(right).expect("live").state, maximized);
+}
+
+#[test]
+fn skip_focus_drops_focus_and_fallback() {
+    let mut reg = Registry::new();
+    reg.add_rule(WindowRule::new(
+        RuleMatch::app_id("notify"),
+        RuleActions { skip_focus: true, ..RuleActions::default() },
+    ));
+    let p1 = TestPtrs::new();
+    let p2 = TestPtrs::new();
+    let popup = insert(&mut reg, &p1);
+    let editor = insert(&mut reg, &p2);
+    reg.on_map(popup).expect("map");
+    reg.on_map(editor).expect("map");
+
+    let update = WindowUpdate {
+        app_id: Some(Some("notify".to_string())),
+        is_focused: Some(true),
+        ..WindowUpdate::default()
+    };
+    reg.update_window(popup, update).expect("app_id");
+    assert!(reg.skips_focus(popup));
+    assert!(!reg.snapshot(popup).expect("live").is_focused);
+
+    // An explicit focus later still works, but the fallback never picks it.
+    reg.update_window(popup, WindowUpdate { is_focused: Some(true), ..WindowUpdate::default() })
+        .expect("focus popup");
+    reg.update_window(editor, WindowUpdate { is_focused: Some(true), ..WindowUpdate::default() })
+        .expect("focus editor");
+    reg.on_unmap(editor).expect("unmap");
+    assert!(!reg.snapshot(popup).expect("live").is_focused);
+}
+
+#[test]
+fn failing_rule_action_leaves_window_untouched() {
+    let mut reg = Registry::new();
+    let broken = WindowGeometry { x: 0, y: 0, width: -1, height: 10 };
+    reg.add_rule(WindowRule::new(
+        RuleMatch::default(),
+        RuleActions { geometry: Some(broken), ..RuleActions::default() },
+    ));
+    let p = TestPtrs::new();
+    let id = insert(&mut reg, &p);
+
+    let err = reg.on_map(id).expect_err("rule geometry is invalid");
+    assert!(matches!(err, RegistryError::InvalidGeometry { .. }));
+    assert_eq!(reg.snapshot(id).expect("live").lifecycle, window_registry::LifecycleState::Created);
+
+    let err = RuleMatch::default().with_title("(").expect_err("bad pattern");
+    assert!(matches!(err, RegistryError::InvalidRulePattern { .. }));
+}
+
+#[test]
+fn rules_apply_through_adapter() {
+    let mut reg = Registry::new();
+    reg.add_rule(WindowRule::new(RuleMatch::app_id("mpv"), send_to(9, 3)));
+    let reg = SharedRegistry::new(reg);
+    let queue = RegistryEventQueue::unbounded();
+    let rx = queue.subscribe();
+    let mut fake = FakeWeston::new(RegistryAdapter::new(reg.clone(), queue.clone()));
+
+    let p = TestPtrs::new();
+    let (dk, sk) = unsafe { p.keys() };
+    fake.push(WestonEvent::NewSurface { dk, sk });
+    fake.run().expect("create");
+    let id = reg.from_desktop(dk).expect("id");
+    fake.push(WestonEvent::AppId { id, app_id: Some("mpv".to_string()) });
+    fake.run().expect("app_id");
+
+    rx.recv().expect("created");
+    let envelope = rx.recv_envelope().expect("changed");
+    assert!(matches!(
+        &envelope.event,
+        RegistryEvent::WindowChanged { changes, .. } if changes.app_id.is_some() && changes.workspace.is_some()
+    ));
+    assert_eq!(reg.snapshot(id).expect("live").output, Some(OutputId(3)));
+}
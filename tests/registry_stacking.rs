@@ -0,0 +1,123 @@
+use window_registry::{
+    OutputId,
+    Registry,
+    RegistryEvent,
+    SharedRegistry,
+    StackLayer,
+    WindowChange,
+    WindowId,
+    WindowUpdate,
+    WorkspaceId,
+};
+
+mod common;
+use common::{insert, stack_moves, TestPtrs};
+
+fn place(reg: &mut Registry, id: WindowId, output: u32, layer: StackLayer) -> Vec<RegistryEvent> {
+    let update = WindowUpdate {
+        workspace: Some(Some(WorkspaceId(output))),
+        output: Some(Some(OutputId(output))),
+        layer: Some(layer),
+        ..WindowUpdate::default()
+    };
+    reg.update_window(id, update).expect("place")
+}
+
+#[test]
+fn layers_order_windows_back_to_front() {
+    let mut reg = Registry::new();
+    let ptrs: Vec<TestPtrs> = (0..5).map(|_| TestPtrs::new()).collect();
+    let ids: Vec<WindowId> = ptrs.iter().map(|p| insert(&mut reg, p)).collect();
+    let [panel, wallpaper, a, b, c] = ids[..] else { unreachable!() };
+
+    place(&mut reg, panel, 1, StackLayer::Top);
+    place(&mut reg, wallpaper, 1, StackLayer::Background);
+    for id in [a, b, c] {
+        place(&mut reg, id, 1, StackLayer::Normal);
+    }
+    assert_eq!(reg.stacking_order(OutputId(1)), [wallpaper, a, b, c, panel]);
+    assert_eq!(reg.stack(Some(OutputId(1)), StackLayer::Normal), &[a, b, c]);
+    assert_eq!(reg.snapshot(panel).expect("live").stack_index, 0);
+
+    // Only the windows of the affected stack move.
+    let events = reg.raise_above(a, b).expect("raise above");
+    assert_eq!(stack_moves(&events), [(b, 1, 0), (a, 0, 1)]);
+    let events = reg.lower_below(c, a).expect("lower below");
+    assert_eq!(stack_moves(&events), [(a, 1, 2), (c, 2, 1)]);
+    assert_eq!(reg.stack(Some(OutputId(1)), StackLayer::Normal), &[b, c, a]);
+
+    assert!(reg.raise(a).expect("already on top").is_empty());
+    reg.lower(a).expect("lower");
+    assert_eq!(reg.stacking_order(OutputId(1)), [wallpaper, a, b, c, panel]);
+    assert!(reg.raise_above(a, a).expect("self").is_empty());
+
+    // A layer change lands on top of the new layer.
+    let events = place(&mut reg, a, 1, StackLayer::Overlay);
+    let own = events.last().expect("own change");
+    assert!(matches!(
+        own,
+        RegistryEvent::WindowChanged { id, changes }
+            if *id == a && changes.layer == Some(WindowChange { old: StackLayer::Normal, new: StackLayer::Overlay })
+    ));
+    assert_eq!(stack_moves(&events), [(b, 1, 0), (c, 2, 1)]);
+    assert_eq!(reg.stacking_order(OutputId(1)), [wallpaper, b, c, panel, a]);
+}
+
+#[test]
+fn stacks_are_separate_per_output() {
+    let mut reg = Registry::new();
+    let ptrs: Vec<TestPtrs> = (0..4).map(|_| TestPtrs::new()).collect();
+    let ids: Vec<WindowId> = ptrs.iter().map(|p| insert(&mut reg, p)).collect();
+    for id in &ids[..2] {
+        place(&mut reg, *id, 1, StackLayer::Normal);
+    }
+    for id in &ids[2..] {
+        place(&mut reg, *id, 2, StackLayer::Normal);
+    }
+    assert_eq!(reg.stacking_order(OutputId(2)), &ids[2..]);
+    assert_eq!(reg.snapshot(ids[2]).expect("live").stack_index, 0);
+
+    // Removal renumbers only the removed window's stack.
+    let (_, events) = reg.remove_window(ids[0]).expect("remove");
+    assert_eq!(stack_moves(&events), [(ids[1], 1, 0)]);
+
+    // Moving to another output goes on top there.
+    let events = place(&mut reg, ids[2], 1, StackLayer::Normal);
+    assert_eq!(stack_moves(&events), [(ids[3], 1, 0), (ids[2], 0, 1)]);
+    assert_eq!(reg.stacking_order(OutputId(1)), [ids[1], ids[2]]);
+    assert_eq!(reg.stacking_order(OutputId(2)), [ids[3]]);
+}
+
+#[test]
+fn restacks_run_in_transactions() {
+    let reg = SharedRegistry::new(Registry::new());
+    let ptrs: Vec<TestPtrs> = (0..3).map(|_| TestPtrs::new()).collect();
+    let ids: Vec<WindowId> = ptrs
+        .iter()
+        .map(|p| {
+            let (dk, sk) = unsafe { p.keys() };
+            reg.insert_window_with(dk, sk, |_| {}).expect("insert")
+        })
+        .collect();
+
+    let mut events = Vec::new();
+    reg.transaction_with(
+        |tx| {
+            for id in &ids {
+                let update = WindowUpdate {
+                    workspace: Some(Some(WorkspaceId(1))),
+                    output: Some(Some(OutputId(1))),
+                    ..WindowUpdate::default()
+                };
+                tx.update_window(*id, update)?;
+            }
+            tx.lower(ids[2])?;
+            tx.raise_above(ids[0], ids[1])
+        },
+        |batch| events = batch,
+    )
+    .expect("transaction");
+    // One coalesced change per window.
+    assert_eq!(events.len(), 3);
+    assert_eq!(reg.stacking_order(OutputId(1)), [ids[2], ids[1], ids[0]]);
+}
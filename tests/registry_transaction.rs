@@ -0,0 +1,209 @@
+use window_registry::{
+    LifecycleState,
+    Registry,
+    RegistryError,
+    RegistryEvent,
+    RegistryEventQueue,
+    SharedRegistry,
+    WindowChange,
+    WindowGeometry,
+    WindowUpdate,
+};
+
+mod common;
+use common::{insert, TestPtrs};
+
+fn geometry(x: i32) -> WindowUpdate {
+    WindowUpdate {
+        geometry: Some(Some(WindowGeometry { x, y: 0, width: 100, height: 100 })),
+        ..WindowUpdate::default()
+    }
+}
+
+#[test]
+fn transaction_applies_updates_to_many_windows() {
+    let mut reg = Registry::new();
+    let p1 = TestPtrs::new();
+    let p2 = TestPtrs::new();
+    let a = insert(&mut reg, &p1);
+    let b = insert(&mut reg, &p2);
+
+    let mut tx = reg.transaction();
+    tx.update_window(a, geometry(10)).expect("update A");
+    tx.update_window(b, geometry(20)).expect("update B");
+    tx.on_map(a).expect("map A");
+    let events = tx.commit().expect("commit should succeed");
+
+    assert_eq!(events.len(), 2);
+    assert!(matches!(
+        events[0],
+        RegistryEvent::WindowChanged { id, ref changes }
+            if id == a
+                && changes.geometry.as_ref().map(|c| c.new) == Some(Some(WindowGeometry { x: 10, y: 0, width: 100, height: 100 }))
+                && changes.lifecycle == Some(WindowChange { old: LifecycleState::Created, new: LifecycleState::Mapped })
+    ));
+    assert!(matches!(events[1], RegistryEvent::WindowChanged { id, .. } if id == b));
+
+    assert_eq!(reg.snapshot(a).expect("A").lifecycle, LifecycleState::Mapped);
+    assert_eq!(reg.snapshot(b).expect("B").geometry.map(|g| g.x), Some(20));
+}
+
+#[test]
+fn transaction_failure_leaves_registry_untouched() {
+    let mut reg = Registry::new();
+    let p1 = TestPtrs::new();
+    let p2 = TestPtrs::new();
+    let a = insert(&mut reg, &p1);
+    let b = insert(&mut reg, &p2);
+    reg.remove_window(b).expect("remove B");
+
+    let mut tx = reg.transaction();
+    tx.update_window(a, geometry(10)).expect("update A");
+    let err = tx.update_window(b, geometry(20)).expect_err("stale id should fail");
+    assert!(matches!(err, RegistryError::InvalidWindowId(id) if id == b));
+    drop(tx);
+
+    assert_eq!(reg.snapshot(a).expect("A").geometry, None);
+}
+
+#[test]
+fn transaction_commit_reports_ignored_failure() {
+    let mut reg = Registry::new();
+    let p1 = TestPtrs::new();
+    let p2 = TestPtrs::new();
+    let a = insert(&mut reg, &p1);
+    let b = insert(&mut reg, &p2);
+    reg.remove_window(b).expect("remove B");
+
+    let mut tx = reg.transaction();
+    tx.update_window(a, geometry(10)).expect("update A");
+    let _ = tx.remove_window(b);
+    let err = tx.update_window(a, geometry(30)).expect_err("transaction already failed");
+    assert!(matches!(err, RegistryError::InvalidWindowId(id) if id == b));
+
+    let err = tx.commit().expect_err("commit should fail");
+    assert!(matches!(err, RegistryError::InvalidWindowId(id) if id == b));
+    assert_eq!(reg.snapshot(a).expect("A").geometry, None);
+}
+
+#[test]
+fn transaction_inserts_are_visible_to_later_operations() {
+    let mut reg = Registry::new();
+    let p1 = TestPtrs::new();
+    let p2 = TestPtrs::new();
+    let (dk1, sk1) = unsafe { p1.keys() };
+    let (dk2, sk2) = unsafe { p2.keys() };
+
+    let mut tx = reg.transaction();
+    let parent = tx.insert_window(dk1, sk1).expect("insert parent");
+    let child = tx.insert_window(dk2, sk2).expect("insert child");
+    tx.update_window(child, WindowUpdate { parent_id: Some(Some(parent)), ..WindowUpdate::default() })
+        .expect("set parent");
+    assert_eq!(tx.staged().snapshot_all().len(), 2);
+    let events = tx.commit().expect("commit should succeed");
+
+    assert_eq!(events.len(), 4);
+    assert!(matches!(events[0], RegistryEvent::WindowCreated { id, .. } if id == parent));
+    assert!(matches!(events[1], RegistryEvent::WindowCreated { id, .. } if id == child));
+    assert!(matches!(events[2], RegistryEvent::WindowChanged { id, .. } if id == parent));
+    assert!(matches!(events[3], RegistryEvent::WindowChanged { id, .. } if id == child));
+    assert_eq!(reg.snapshot(parent).expect("parent").children, vec![child]);
+}
+
+#[test]
+fn transaction_coalesces_and_drops_cancelled_changes() {
+    let mut reg = Registry::new();
+    let p = TestPtrs::new();
+    let a = insert(&mut reg, &p);
+
+    let mut tx = reg.transaction();
+    tx.update_window(a, geometry(10)).expect("first move");
+    tx.update_window(a, geometry(20)).expect("second move");
+    tx.on_map(a).expect("map");
+    tx.on_unmap(a).expect("unmap");
+    let events = tx.commit().expect("commit should succeed");
+
+    assert_eq!(events.len(), 1);
+    assert!(matches!(
+        events[0],
+        RegistryEvent::WindowChanged { id, ref changes }
+            if id == a
+                && changes.geometry.as_ref().map(|c| (c.old, c.new.map(|g| g.x))) == Some((None, Some(20)))
+                && changes.lifecycle == Some(WindowChange { old: LifecycleState::Created, new: LifecycleState::Unmapped })
+    ));
+}
+
+#[test]
+fn shared_transaction_with_dispatches_once() {
+    let reg = SharedRegistry::new(Registry::new());
+    let p1 = TestPtrs::new();
+    let p2 = TestPtrs::new();
+    let (dk1, sk1) = unsafe { p1.keys() };
+    let (dk2, sk2) = unsafe { p2.keys() };
+
+    let mut batches = Vec::new();
+    let (a, b) = reg
+        .transaction_with(
+            |tx| {
+                let a = tx.insert_window(dk1, sk1)?;
+                let b = tx.insert_window(dk2, sk2)?;
+                tx.on_map(a)?;
+                Ok((a, b))
+            },
+            |events| batches.push(events),
+        )
+        .expect("transaction_with should succeed");
+
+    assert_eq!(batches.len(), 1);
+    assert_eq!(batches[0].len(), 3);
+    assert!(reg.snapshot(a).is_some());
+    assert!(reg.snapshot(b).is_some());
+}
+
+#[test]
+fn shared_transaction_error_skips_dispatch() {
+    let reg = SharedRegistry::new(Registry::new());
+    let p = TestPtrs::new();
+    let (dk, sk) = unsafe { p.keys() };
+
+    let mut dispatched = false;
+    let err = reg
+        .transaction_with(
+            |tx| {
+                let id = tx.insert_window(dk, sk)?;
+                tx.insert_window(dk, sk)?;
+                Ok(id)
+            },
+            |_| dispatched = true,
+        )
+        .expect_err("duplicate insert should fail");
+
+    assert!(matches!(err, RegistryError::DesktopKeyAlreadyRegistered { .. }));
+    assert!(!dispatched);
+    assert!(reg.snapshot_all().is_empty());
+}
+
+#[test]
+fn shared_transaction_queued_sends_coalesced_batch() {
+    let reg = SharedRegistry::new(Registry::new());
+    let queue = RegistryEventQueue::unbounded();
+    let receiver = queue.subscribe();
+    let p = TestPtrs::new();
+    let (dk, sk) = unsafe { p.keys() };
+
+    let id = reg
+        .transaction_queued(
+            |tx| {
+                let id = tx.insert_window(dk, sk)?;
+                tx.update_window(id, geometry(5))?;
+                tx.update_window(id, geometry(6))?;
+                Ok(id)
+            },
+            &queue,
+        )
+        .expect("transaction_queued should succeed");
+
+    assert!(matches!(receiver.recv().expect("created"), RegistryEvent::WindowCreated { id: ev_id, .. } if ev_id == id));
+    assert!(matches!(receiver.recv().expect("changed"), RegistryEvent::WindowChanged { id: ev_id, .. } if ev_id == id));
+    assert!(receiver.try_recv().expect("queue open").is_none());
+}
@@ -0,0 +1,182 @@
+use window_registry::{
+    ChildRemovalPolicy,
+    OutputId,
+    Registry,
+    RegistryError,
+    RegistryEvent,
+    StackLayer,
+    WindowId,
+    WindowUpdate,
+    WorkspaceId,
+};
+
+mod common;
+use common::{insert, place, stack_moves, TestPtrs};
+
+fn set_parent(reg: &mut Registry, id: WindowId, parent: WindowId) -> Vec<RegistryEvent> {
+    let update = WindowUpdate { parent_id: Some(Some(parent)), ..WindowUpdate::default() };
+    reg.update_window(id, update).expect("set parent")
+}
+
+fn stack(reg: &Registry) -> &[WindowId] {
+    reg.stack(None, StackLayer::Normal)
+}
+
+#[test]
+fn transients_move_with_their_parent() {
+    let mut reg = Registry::new();
+    let ptrs: Vec<TestPtrs> = (0..4).map(|_| TestPtrs::new()).collect();
+    let ids: Vec<WindowId> = ptrs.iter().map(|p| insert(&mut reg, p)).collect();
+    let [a, b, dialog, popup] = ids[..] else { unreachable!() };
+
+    // A new transient lands right above its parent.
+    let events = set_parent(&mut reg, dialog, a);
+    assert_eq!(stack_moves(&events), [(b, 1, 2), (dialog, 2, 1)]);
+    set_parent(&mut reg, popup, dialog);
+    assert_eq!(stack(&reg), &[a, dialog, popup, b]);
+
+    // Raising the parent takes the whole group along, every moved window reports its index.
+    let events = reg.raise(a).expect("raise");
+    assert_eq!(stack_moves(&events), [(b, 3, 0), (a, 0, 1), (dialog, 1, 2), (popup, 2, 3)]);
+
+    // Raising a transient raises its parent's group with it.
+    reg.lower(a).expect("lower");
+    assert_eq!(stack(&reg), &[a, dialog, popup, b]);
+    reg.raise(popup).expect("raise");
+    assert_eq!(stack(&reg), &[b, a, dialog, popup]);
+
+    // Restacking against a transient places relative to its whole group.
+    reg.lower_below(b, popup).expect("lower below");
+    assert_eq!(stack(&reg), &[b, a, dialog, popup]);
+    reg.raise_above(b, dialog).expect("raise above");
+    assert_eq!(stack(&reg), &[a, dialog, popup, b]);
+}
+
+#[test]
+fn transients_cannot_leave_their_parents_group() {
+    let mut reg = Registry::new();
+    let ptrs: Vec<TestPtrs> = (0..4).map(|_| TestPtrs::new()).collect();
+    let ids: Vec<WindowId> = ptrs.iter().map(|p| insert(&mut reg, p)).collect();
+    let [a, b, first, second] = ids[..] else { unreachable!() };
+    set_parent(&mut reg, first, a);
+    set_parent(&mut reg, second, a);
+    assert_eq!(stack(&reg), &[a, first, second, b]);
+
+    assert!(matches!(
+        reg.lower_below(first, a),
+        Err(RegistryError::TransientOutsideGroup { id, sibling }) if id == first && sibling == a
+    ));
+    assert!(matches!(reg.raise_above(first, b), Err(RegistryError::TransientOutsideGroup { .. })));
+    // A window can't be placed inside its own group either.
+    assert!(matches!(reg.lower_below(a, second), Err(RegistryError::TransientOutsideGroup { .. })));
+
+    // Siblings reorder among themselves; lowering stops just above the parent.
+    reg.raise_above(first, second).expect("raise above");
+    assert_eq!(stack(&reg), &[a, second, first, b]);
+    reg.lower(first).expect("lower");
+    assert_eq!(stack(&reg), &[a, first, second, b]);
+    assert_eq!(reg.snapshot(first).expect("live").stack_index, 1);
+}
+
+#[test]
+fn groups_survive_adoption_and_removal() {
+    let mut reg = Registry::new();
+    let ptrs: Vec<TestPtrs> = (0..4).map(|_| TestPtrs::new()).collect();
+    let ids: Vec<WindowId> = ptrs.iter().map(|p| insert(&mut reg, p)).collect();
+    let [root, mid, other, leaf] = ids[..] else { unreachable!() };
+
+    // Adopting through `add_children` lifts the child onto its new parent's group.
+    let update = WindowUpdate { add_children: vec![leaf], ..WindowUpdate::default() };
+    reg.update_window(mid, update).expect("adopt");
+    assert_eq!(stack(&reg), &[root, mid, leaf, other]);
+    set_parent(&mut reg, mid, root);
+    assert_eq!(stack(&reg), &[root, mid, leaf, other]);
+    reg.raise(other).expect("raise");
+    reg.raise(root).expect("raise");
+    assert_eq!(stack(&reg), &[other, root, mid, leaf]);
+
+    // Reparented grandchildren stay grouped above the grandparent.
+    reg.set_child_removal_policy(ChildRemovalPolicy::Reparent);
+    let (_, events) = reg.remove_window(mid).expect("remove");
+    assert_eq!(stack_moves(&events), [(leaf, 3, 2)]);
+    assert_eq!(stack(&reg), &[other, root, leaf]);
+    assert_eq!(reg.snapshot(leaf).expect("live").parent_id, Some(root));
+
+    // Detaching keeps the window where it is.
+    let update = WindowUpdate { parent_id: Some(None), ..WindowUpdate::default() };
+    reg.update_window(leaf, update).expect("detach");
+    reg.raise(root).expect("raise");
+    assert_eq!(stack(&reg), &[other, leaf, root]);
+}
+
+#[test]
+fn transients_follow_their_parent_to_another_stack() {
+    let mut reg = Registry::new();
+    let ptrs: Vec<TestPtrs> = (0..5).map(|_| TestPtrs::new()).collect();
+    let ids: Vec<WindowId> = ptrs.iter().map(|p| insert(&mut reg, p)).collect();
+    let [a, dialog, popup, b, c] = ids[..] else { unreachable!() };
+    for id in &ids {
+        place(&mut reg, *id, 1, 1);
+    }
+    place(&mut reg, c, 2, 2);
+    set_parent(&mut reg, dialog, a);
+    set_parent(&mut reg, popup, dialog);
+    assert_eq!(reg.stacking_order(OutputId(1)), [a, dialog, popup, b]);
+
+    // The whole group lands on top of the new output, each transient reporting its move.
+    let update = WindowUpdate {
+        workspace: Some(Some(WorkspaceId(2))),
+        output: Some(Some(OutputId(2))),
+        ..WindowUpdate::default()
+    };
+    let events = reg.update_window(a, update).expect("move");
+    assert_eq!(reg.stacking_order(OutputId(1)), [b]);
+    assert_eq!(reg.stacking_order(OutputId(2)), [c, a, dialog, popup]);
+    assert_eq!(stack_moves(&events), [(dialog, 1, 2), (popup, 2, 3), (b, 3, 0), (a, 0, 1)]);
+    let moved: Vec<WindowId> = events
+        .iter()
+        .filter_map(|event| match event {
+            RegistryEvent::WindowChanged { id, changes } => changes.output.is_some().then_some(*id),
+            _ => None,
+        })
+        .collect();
+    assert_eq!(moved, [dialog, popup, a]);
+    for id in [dialog, popup] {
+        let snap = reg.snapshot(id).expect("live");
+        assert_eq!((snap.workspace, snap.output), (Some(WorkspaceId(2)), Some(OutputId(2))));
+    }
+    assert_eq!(reg.windows_on_output(OutputId(2)).len(), 4);
+
+    // A layer change takes the group along too.
+    let update = WindowUpdate { layer: Some(StackLayer::Top), ..WindowUpdate::default() };
+    reg.update_window(a, update).expect("raise layer");
+    assert_eq!(reg.stack(Some(OutputId(2)), StackLayer::Normal), [c]);
+    assert_eq!(reg.stack(Some(OutputId(2)), StackLayer::Top), [a, dialog, popup]);
+    assert_eq!(reg.snapshot(popup).expect("live").layer, StackLayer::Top);
+}
+
+#[test]
+fn moved_groups_restack_relative_to_each_other() {
+    let mut reg = Registry::new();
+    let ptrs: Vec<TestPtrs> = (0..4).map(|_| TestPtrs::new()).collect();
+    let ids: Vec<WindowId> = ptrs.iter().map(|p| insert(&mut reg, p)).collect();
+    let [a, first, second, b] = ids[..] else { unreachable!() };
+    set_parent(&mut reg, first, a);
+    set_parent(&mut reg, second, a);
+    place(&mut reg, b, 2, 2);
+    place(&mut reg, a, 2, 2);
+    assert_eq!(reg.stacking_order(OutputId(2)), [b, a, first, second]);
+
+    // The siblings moved with their parent, so they still share a stack.
+    reg.lower_below(second, first).expect("lower below sibling");
+    assert_eq!(reg.stacking_order(OutputId(2)), [b, a, second, first]);
+    reg.raise_above(second, first).expect("raise above sibling");
+    assert_eq!(reg.stacking_order(OutputId(2)), [b, a, first, second]);
+    reg.raise_above(b, first).expect("raise above group");
+    assert_eq!(reg.stacking_order(OutputId(2)), [a, first, second, b]);
+
+    let update = WindowUpdate { layer: Some(StackLayer::Top), ..WindowUpdate::default() };
+    reg.update_window(a, update).expect("change layer");
+    reg.lower_below(second, first).expect("lower below sibling");
+    assert_eq!(reg.stack(Some(OutputId(2)), StackLayer::Top), [a, second, first]);
+}
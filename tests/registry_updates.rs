@@ -3,6 +3,7 @@ use window_registry::{
     Registry,
     RegistryError,
     RegistryEvent,
+    StackLayer,
     WindowChange,
     WindowGeometry,
     WindowState,
@@ -322,7 +323,7 @@ fn update_window_rejects_duplicate_children() {
 }
 
 #[test]
-fn update_window_reorders_stack_indices() {
+fn lower_reorders_stack_indices() {
     let mut reg = Registry::new();
     let p1 = TestPtrs::new();
     let p2 = TestPtrs::new();
@@ -335,9 +336,7 @@ fn update_window_reorders_stack_indices() {
     let id2 = reg.insert_window(dk2, sk2).expect("insert B").0;
     let id3 = reg.insert_window(dk3, sk3).expect("insert C").0;
 
-    let mut update = WindowUpdate::default();
-    update.stack_index = Some(0);
-    let events = reg.update_window(id3, update).expect("stack reorder should succeed");
+    let events = reg.lower(id3).expect("stack reorder should succeed");
 
     assert_eq!(events.len(), 3);
     assert!(matches!(
@@ -368,7 +367,7 @@ fn update_window_reorders_stack_indices() {
 }
 
 #[test]
-fn update_window_rejects_stack_index_out_of_bounds() {
+fn raise_above_rejects_window_in_another_stack() {
     let mut reg = Registry::new();
     let p1 = TestPtrs::new();
     let p2 = TestPtrs::new();
@@ -376,15 +375,16 @@ fn update_window_rejects_stack_index_out_of_bounds() {
     let (dk1, sk1) = unsafe { p1.keys() };
     let (dk2, sk2) = unsafe { p2.keys() };
     let id1 = reg.insert_window(dk1, sk1).expect("insert A").0;
-    reg.insert_window(dk2, sk2).expect("insert B");
+    let id2 = reg.insert_window(dk2, sk2).expect("insert B").0;
 
     let mut update = WindowUpdate::default();
-    update.stack_index = Some(2);
-    let err = reg.update_window(id1, update).expect_err("out of bounds should fail");
+    update.layer = Some(StackLayer::Top);
+    reg.update_window(id2, update).expect("layer change should succeed");
+    let err = reg.raise_above(id1, id2).expect_err("different layers should fail");
 
     assert!(matches!(
         err,
-        RegistryError::StackIndexOutOfBounds { id, index, count } if id == id1 && index == 2 && count == 2
+        RegistryError::NotInSameStack { id, sibling } if id == id1 && sibling == id2
     ));
 }
 
@@ -491,7 +491,7 @@ fn update_window_rejects_missing_child_on_remove() {
 }
 
 #[test]
-fn update_window_reorders_stack_indices_forward() {
+fn raise_reorders_stack_indices_forward() {
     let mut reg = Registry::new();
     let p1 = TestPtrs::new();
     let p2 = TestPtrs::new();
@@ -504,9 +504,7 @@ fn update_window_reorders_stack_indices_forward() {
     let id2 = reg.insert_window(dk2, sk2).expect("insert B").0;
     let id3 = reg.insert_window(dk3, sk3).expect("insert C").0;
 
-    let mut update = WindowUpdate::default();
-    update.stack_index = Some(2);
-    let events = reg.update_window(id1, update).expect("stack reorder should succeed");
+    let events = reg.raise(id1).expect("stack reorder should succeed");
 
     assert_eq!(events.len(), 3);
     assert!(matches!(
@@ -558,7 +556,8 @@ fn update_window_parent_change_updates_old_and_new_parent() {
     update.parent_id = Some(Some(parent_b));
     let events = reg.update_window(child, update).expect("switch parent should succeed");
 
-    assert_eq!(events.len(), 3);
+    // The child follows its new parent: [A, child, B] becomes [A, B, child].
+    assert_eq!(events.len(), 4);
     assert!(matches!(
         events[0],
         RegistryEvent::WindowChanged { id, ref changes }
@@ -573,9 +572,15 @@ fn update_window_parent_change_updates_old_and_new_parent() {
     ));
     assert!(matches!(
         events[2],
+        RegistryEvent::WindowChanged { id, ref changes }
+            if id == parent_b && changes.stack_index == Some(WindowChange { old: 2, new: 1 })
+    ));
+    assert!(matches!(
+        events[3],
         RegistryEvent::WindowChanged { id, ref changes }
             if id == child
                 && changes.parent_id == Some(WindowChange { old: Some(parent_a), new: Some(parent_b) })
+                && changes.stack_index == Some(WindowChange { old: 1, new: 2 })
     ));
 
     let snap_a = reg.snapshot(parent_a).expect("parent A snapshot");
@@ -0,0 +1,193 @@
+use window_registry::{
+    EventFilter,
+    EventKind,
+    OutputId,
+    Registry,
+    RegistryError,
+    RegistryEvent,
+    RegistryEventQueue,
+    SharedRegistry,
+    WindowId,
+    WindowUpdate,
+    WorkspaceId,
+};
+
+mod common;
+use common::{insert, TestPtrs};
+
+fn place(reg: &mut Registry, id: WindowId, workspace: WorkspaceId, output: u32) -> Result<Vec<RegistryEvent>, RegistryError> {
+    let update = WindowUpdate {
+        workspace: Some(Some(workspace)),
+        output: Some(Some(OutputId(output))),
+        ..WindowUpdate::default()
+    };
+    reg.update_window(id, update)
+}
+
+fn names(reg: &Registry) -> Vec<String> {
+    reg.workspaces().into_iter().map(|ws| ws.name).collect()
+}
+
+#[test]
+fn create_rename_reorder_and_activate() {
+    let mut reg = Registry::new();
+
+    let (web, events) = reg.create_workspace("web", OutputId(1)).expect("create web");
+    assert!(matches!(events[0], RegistryEvent::WorkspaceCreated { id, output: OutputId(1), .. } if id == web));
+    assert!(matches!(
+        events[1],
+        RegistryEvent::WorkspaceActivated { output: OutputId(1), old: None, new: Some(id) } if id == web
+    ));
+    let (code, events) = reg.create_workspace("code", OutputId(1)).expect("create code");
+    assert_eq!(events.len(), 1, "output 1 already has an active workspace");
+    let (chat, _) = reg.create_workspace("chat", OutputId(2)).expect("create chat");
+
+    assert_eq!(reg.active_workspace(OutputId(1)), Some(web));
+    assert_eq!(reg.active_workspace(OutputId(2)), Some(chat));
+    assert!(matches!(
+        reg.create_workspace("web", OutputId(2)),
+        Err(RegistryError::WorkspaceNameTaken { existing, .. }) if existing == web
+    ));
+
+    let events = reg.rename_workspace(code, "dev").expect("rename");
+    assert!(matches!(&events[..], [RegistryEvent::WorkspaceRenamed { old, new, .. }] if old == "code" && new == "dev"));
+    assert_eq!(reg.workspace_by_name("dev"), Some(code));
+    assert!(reg.rename_workspace(code, "dev").expect("same name").is_empty());
+
+    let events = reg.reorder_workspace(chat, 0).expect("reorder");
+    assert!(matches!(&events[..], [RegistryEvent::WorkspaceReordered { old: 2, new: 0, .. }]));
+    assert_eq!(names(&reg), ["chat", "web", "dev"]);
+    assert!(matches!(
+        reg.reorder_workspace(chat, 3),
+        Err(RegistryError::WorkspaceIndexOutOfBounds { index: 3, count: 3, .. })
+    ));
+
+    let events = reg.activate_workspace(code).expect("activate");
+    assert!(matches!(
+        &events[..],
+        [RegistryEvent::WorkspaceActivated { old: Some(old), new: Some(new), .. }] if *old == web && *new == code
+    ));
+    assert!(reg.activate_workspace(code).expect("already active").is_empty());
+    let info = reg.workspace(code).expect("known");
+    assert!(info.active);
+    assert!(!reg.workspace(web).expect("known").active);
+
+    assert!(matches!(reg.activate_workspace(WorkspaceId(99)), Err(RegistryError::InvalidWorkspaceId(_))));
+}
+
+#[test]
+fn window_workspaces_are_validated_once_workspaces_exist() {
+    let mut reg = Registry::new();
+    let p = TestPtrs::new();
+    let id = insert(&mut reg, &p);
+
+    // Free-form ids keep working until the first workspace is created.
+    place(&mut reg, id, WorkspaceId(42), 1).expect("unmanaged");
+
+    let (web, _) = reg.create_workspace("web", OutputId(1)).expect("create");
+    place(&mut reg, id, web, 1).expect("known workspace");
+    assert!(matches!(
+        place(&mut reg, id, WorkspaceId(42), 1),
+        Err(RegistryError::UnknownWorkspace { workspace: WorkspaceId(42), .. })
+    ));
+    assert!(matches!(
+        place(&mut reg, id, web, 2),
+        Err(RegistryError::WorkspaceOutputMismatch { workspace: Some(_), output: Some(OutputId(2)), .. })
+    ));
+    let move_output = WindowUpdate { output: Some(Some(OutputId(2))), ..WindowUpdate::default() };
+    assert!(matches!(reg.update_window(id, move_output), Err(RegistryError::WorkspaceOutputMismatch { .. })));
+    assert_eq!(reg.snapshot(id).expect("live").workspace, Some(web));
+}
+
+#[test]
+fn removing_workspace_moves_windows_and_activation() {
+    let mut reg = Registry::new();
+    let (one, _) = reg.create_workspace("1", OutputId(1)).expect("create");
+    let (two, _) = reg.create_workspace("2", OutputId(1)).expect("create");
+    let (other, _) = reg.create_workspace("other", OutputId(2)).expect("create");
+    let ptrs: Vec<TestPtrs> = (0..2).map(|_| TestPtrs::new()).collect();
+    let a = insert(&mut reg, &ptrs[0]);
+    let b = insert(&mut reg, &ptrs[1]);
+    place(&mut reg, a, one, 1).expect("place");
+    place(&mut reg, b, other, 2).expect("place");
+
+    let events = reg.remove_workspace(one).expect("remove");
+    assert!(matches!(
+        &events[0],
+        RegistryEvent::WorkspaceActivated { output: OutputId(1), old: Some(old), new: Some(new) } if *old == one && *new == two
+    ));
+    assert!(matches!(
+        &events[1],
+        RegistryEvent::WindowChanged { id, changes } if *id == a && changes.workspace.as_ref().is_some_and(|c| c.new == Some(two))
+    ));
+    assert!(matches!(events[2], RegistryEvent::WorkspaceRemoved { id } if id == one));
+    assert_eq!(reg.windows_on_workspace(two), &[a]);
+    assert!(matches!(reg.remove_workspace(one), Err(RegistryError::InvalidWorkspaceId(_))));
+
+    // The last workspace of an output cannot drop its windows.
+    assert!(matches!(reg.remove_workspace(other), Err(RegistryError::WorkspaceNotEmpty { .. })));
+    reg.remove_window(b).expect("remove window");
+    let events = reg.remove_workspace(other).expect("now empty");
+    assert!(matches!(events[0], RegistryEvent::WorkspaceActivated { new: None, .. }));
+    assert_eq!(reg.active_workspace(OutputId(2)), None);
+
+    // Ids are not reused.
+    let (three, _) = reg.create_workspace("3", OutputId(2)).expect("create");
+    assert!(three != one && three != other);
+}
+
+#[test]
+fn failed_workspace_removal_changes_nothing() {
+    let mut reg = Registry::new();
+    let (one, _) = reg.create_workspace("1", OutputId(1)).expect("create");
+    reg.create_workspace("2", OutputId(1)).expect("create");
+    let ptrs: Vec<TestPtrs> = (0..2).map(|_| TestPtrs::new()).collect();
+    let a = insert(&mut reg, &ptrs[0]);
+    let b = insert(&mut reg, &ptrs[1]);
+    place(&mut reg, a, one, 1).expect("place");
+    place(&mut reg, b, one, 1).expect("place");
+    // An unchecked edit leaves `b` on an output its new workspace doesn't belong to.
+    reg.get_mut(b).expect("live").output = Some(OutputId(2));
+
+    assert!(matches!(
+        reg.remove_workspace(one),
+        Err(RegistryError::WorkspaceOutputMismatch { id, .. }) if id == b
+    ));
+    assert!(reg.workspace(one).is_some());
+    assert_eq!(reg.active_workspace(OutputId(1)), Some(one));
+    assert_eq!(reg.windows_on_workspace(one), &[a, b]);
+}
+
+#[test]
+fn workspace_events_flow_through_queued_transactions() {
+    let reg = SharedRegistry::new(Registry::new());
+    let queue = RegistryEventQueue::unbounded();
+    let workspace_events = queue.subscribe_filtered(EventFilter::kinds([EventKind::Workspace]));
+    let on_output_two = queue.subscribe_filtered(EventFilter::default().on_output(OutputId(2)));
+
+    let (web, chat) = reg
+        .transaction_queued(
+            |tx| {
+                let web = tx.create_workspace("web", OutputId(1))?;
+                let chat = tx.create_workspace("chat", OutputId(2))?;
+                tx.rename_workspace(chat, "im")?;
+                Ok((web, chat))
+            },
+            &queue,
+        )
+        .expect("transaction");
+    drop(queue);
+
+    assert_eq!(reg.workspace_by_name("im"), Some(chat));
+    assert_eq!(reg.active_workspace(OutputId(1)), Some(web));
+    let kinds: Vec<String> = workspace_events
+        .iter()
+        .map(|event| format!("{:?}", event).split_whitespace().next().unwrap_or_default().to_string())
+        .collect();
+    assert_eq!(
+        kinds,
+        ["WorkspaceCreated", "WorkspaceActivated", "WorkspaceCreated", "WorkspaceActivated", "WorkspaceRenamed"]
+    );
+    let seen: Vec<Option<WorkspaceId>> = on_output_two.iter().map(|event| event.workspace()).collect();
+    assert_eq!(seen, [Some(chat), Some(chat)]);
+}
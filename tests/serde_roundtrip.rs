@@ -0,0 +1,98 @@
+#![cfg(feature = "serde")]
+
+use window_registry::{
+    OutputId,
+    Registry,
+    RegistryError,
+    RegistryEvent,
+    WindowGeometry,
+    WindowInfo,
+    WindowUpdate,
+    WorkspaceId,
+    with_raw_keys,
+};
+
+mod common;
+use common::TestPtrs;
+
+#[test]
+fn window_id_serializes_index_and_generation() {
+    let mut reg = Registry::new();
+    let p = TestPtrs::new();
+    let (dk, sk) = unsafe { p.keys() };
+    let id = reg.insert_window(dk, sk).expect("insert_window should succeed").0;
+
+    let json = serde_json::to_value(id).expect("serialize id");
+    assert_eq!(json, serde_json::json!({ "index": 0, "gen": 1 }));
+
+    let back: window_registry::WindowId = serde_json::from_value(json).expect("deserialize id");
+    assert_eq!(back, id);
+}
+
+#[test]
+fn keys_are_opaque_unless_raw_requested() {
+    let p = TestPtrs::new();
+    let (dk, sk) = unsafe { p.keys() };
+    let addr = p.ds as usize as u64;
+
+    let opaque = serde_json::to_value(dk).expect("serialize dk");
+    assert_ne!(opaque, serde_json::json!(addr));
+    assert_eq!(opaque, serde_json::to_value(dk).expect("serialize dk again"));
+    assert_ne!(opaque, serde_json::to_value(sk).expect("serialize sk"));
+
+    let raw = with_raw_keys(|| serde_json::to_value(dk).expect("serialize raw dk"));
+    assert_eq!(raw, serde_json::json!(addr));
+
+    let after = serde_json::to_value(dk).expect("serialize after raw");
+    assert_eq!(after, opaque);
+}
+
+#[test]
+fn window_info_round_trips() {
+    let mut reg = Registry::new();
+    let p = TestPtrs::new();
+    let (dk, sk) = unsafe { p.keys() };
+    let id = reg.insert_window(dk, sk).expect("insert_window should succeed").0;
+
+    let update = WindowUpdate {
+        geometry: Some(Some(WindowGeometry { x: 1, y: 2, width: 3, height: 4 })),
+        workspace: Some(Some(WorkspaceId(2))),
+        output: Some(Some(OutputId(1))),
+        title: Some(Some("Editor".to_string())),
+        ..WindowUpdate::default()
+    };
+    reg.update_window(id, update).expect("update should succeed");
+
+    let info = reg.snapshot(id).expect("snapshot");
+    let json = with_raw_keys(|| serde_json::to_string(&info).expect("serialize info"));
+    let back: WindowInfo = serde_json::from_str(&json).expect("deserialize info");
+
+    assert_eq!(back.id, info.id);
+    assert_eq!(back.dk, info.dk);
+    assert_eq!(back.geometry, info.geometry);
+    assert_eq!(back.workspace, info.workspace);
+    assert_eq!(back.title, info.title);
+}
+
+#[test]
+fn events_and_errors_round_trip() {
+    let mut reg = Registry::new();
+    let p = TestPtrs::new();
+    let (dk, sk) = unsafe { p.keys() };
+    let (id, mut events) = reg.insert_window(dk, sk).expect("insert_window should succeed");
+    events.extend(reg.on_map(id).expect("on_map should succeed"));
+
+    let json = serde_json::to_string(&events).expect("serialize events");
+    let back: Vec<RegistryEvent> = serde_json::from_str(&json).expect("deserialize events");
+    assert!(matches!(back[0], RegistryEvent::WindowCreated { id: ev_id, .. } if ev_id == id));
+    assert!(matches!(
+        back[1],
+        RegistryEvent::WindowChanged { id: ev_id, ref changes }
+            if ev_id == id && changes.lifecycle.is_some()
+    ));
+
+    let err = RegistryError::InvalidWindowId(id);
+    let json = serde_json::to_string(&err).expect("serialize error");
+    let back: RegistryError = serde_json::from_str(&json).expect("deserialize error");
+    assert!(matches!(back, RegistryError::InvalidWindowId(err_id) if err_id == id));
+}
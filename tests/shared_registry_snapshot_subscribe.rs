@@ -0,0 +1,107 @@
+use std::{
+    sync::{
+        atomic::{AtomicBool, Ordering},
+        Arc,
+    },
+    thread,
+};
+
+use window_registry::{
+    Registry,
+    RegistryEvent,
+    RegistryEventQueue,
+    SharedRegistry,
+    WindowUpdate,
+};
+
+mod common;
+use common::TestPtrs;
+
+fn set_title(reg: &SharedRegistry, queue: &RegistryEventQueue, id: window_registry::WindowId, title: String) {
+    let update = WindowUpdate { title: Some(Some(title)), ..WindowUpdate::default() };
+    reg.update_window_queued(id, update, queue).expect("set title");
+}
+
+#[test]
+fn snapshot_subscription_starts_after_snapshot() {
+    let reg = SharedRegistry::new(Registry::new());
+    let queue = RegistryEventQueue::unbounded();
+    let p1 = TestPtrs::new();
+    let p2 = TestPtrs::new();
+    let (dk1, sk1) = unsafe { p1.keys() };
+    let (dk2, sk2) = unsafe { p2.keys() };
+    let a = reg.insert_window_queued(dk1, sk1, &queue).expect("insert A");
+
+    let (snapshot, rx) = reg.subscribe_with_snapshot(&queue);
+    assert_eq!(snapshot.iter().map(|w| w.id).collect::<Vec<_>>(), vec![a]);
+    assert!(rx.try_recv().expect("queue open").is_none());
+
+    let b = reg.insert_window_queued(dk2, sk2, &queue).expect("insert B");
+    assert!(matches!(
+        rx.try_recv().expect("queue open"),
+        Some(RegistryEvent::WindowCreated { id, .. }) if id == b
+    ));
+    assert!(rx.try_recv().expect("queue open").is_none());
+}
+
+#[test]
+fn snapshot_subscription_skips_batches_reserved_before_it() {
+    let reg = SharedRegistry::new(Registry::new());
+    let queue = RegistryEventQueue::unbounded();
+    let p = TestPtrs::new();
+    let (dk, sk) = unsafe { p.keys() };
+
+    // Holding an earlier ticket keeps the insert's batch undelivered while the snapshot is taken.
+    let earlier = queue.reserve();
+    let id = reg.insert_window_queued(dk, sk, &queue).expect("insert");
+    let (snapshot, rx) = reg.subscribe_with_snapshot(&queue);
+    assert_eq!(snapshot.len(), 1);
+
+    drop(earlier);
+    assert!(rx.try_recv().expect("queue open").is_none());
+
+    reg.on_map_queued(id, &queue).expect("map");
+    assert!(matches!(
+        rx.try_recv().expect("queue open"),
+        Some(RegistryEvent::WindowChanged { id: ev_id, .. }) if ev_id == id
+    ));
+}
+
+#[test]
+fn snapshot_subscription_has_no_gap_under_concurrent_writes() {
+    let reg = SharedRegistry::new(Registry::new());
+    let queue = RegistryEventQueue::unbounded();
+    let p = TestPtrs::new();
+    let (dk, sk) = unsafe { p.keys() };
+    let id = reg.insert_window_queued(dk, sk, &queue).expect("insert");
+
+    let started = Arc::new(AtomicBool::new(false));
+    let writer = {
+        let reg = reg.clone();
+        let queue = queue.clone();
+        let started = Arc::clone(&started);
+        thread::spawn(move || {
+            for n in 0..500 {
+                set_title(&reg, &queue, id, n.to_string());
+                started.store(true, Ordering::Release);
+            }
+        })
+    };
+
+    while !started.load(Ordering::Acquire) {
+        thread::yield_now();
+    }
+    let (snapshot, rx) = reg.subscribe_with_snapshot(&queue);
+    writer.join().expect("writer should finish");
+
+    let mut title = snapshot[0].title.clone();
+    while let Some(event) = rx.try_recv().expect("queue open") {
+        let RegistryEvent::WindowChanged { changes, .. } = event else {
+            panic!("unexpected event: {event:?}");
+        };
+        let change = changes.title.expect("title change");
+        assert_eq!(change.old, title);
+        title = change.new;
+    }
+    assert_eq!(title, reg.snapshot(id).expect("snapshot").title);
+}
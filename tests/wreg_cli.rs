@@ -0,0 +1,87 @@
+#![cfg(feature = "ipc")]
+
+use std::{
+    path::Path,
+    process::{Command, Output},
+};
+
+use window_registry::{
+    IpcServer,
+    Registry,
+    RegistryEventQueue,
+    SharedRegistry,
+    WindowUpdate,
+};
+
+mod common;
+use common::{TempSocket, TestPtrs};
+
+fn wreg(socket: &Path, args: &[&str]) -> Output {
+    Command::new(env!("CARGO_BIN_EXE_wreg"))
+        .arg("--socket")
+        .arg(socket)
+        .args(args)
+        .output()
+        .expect("wreg should run")
+}
+
+#[test]
+fn wreg_lists_tree_and_focuses() {
+    let reg = SharedRegistry::new(Registry::new());
+    let queue = RegistryEventQueue::unbounded();
+
+    let p1 = TestPtrs::new();
+    let p2 = TestPtrs::new();
+    let (dk1, sk1) = unsafe { p1.keys() };
+    let (dk2, sk2) = unsafe { p2.keys() };
+    let parent = reg.insert_window_queued(dk1, sk1, &queue).expect("insert parent");
+    let child = reg.insert_window_queued(dk2, sk2, &queue).expect("insert child");
+    let update = WindowUpdate {
+        parent_id: Some(Some(parent)),
+        title: Some(Some("Save As".to_string())),
+        ..WindowUpdate::default()
+    };
+    reg.update_window_queued(child, update, &queue).expect("set parent");
+
+    let path = TempSocket::new("cli-tree");
+    IpcServer::bind(&path, reg.clone(), queue.clone()).expect("bind").spawn();
+
+    let out = wreg(&path, &["list"]);
+    assert!(out.status.success());
+    let stdout = String::from_utf8(out.stdout).expect("utf8");
+    assert_eq!(stdout.lines().count(), 2);
+    assert!(stdout.contains(&parent.to_string()));
+
+    let out = wreg(&path, &["tree"]);
+    let stdout = String::from_utf8(out.stdout).expect("utf8");
+    let lines: Vec<&str> = stdout.lines().collect();
+    assert!(lines[0].starts_with(&parent.to_string()));
+    assert!(lines[1].starts_with(&format!("  {child}")));
+    assert!(lines[1].contains("Save As"));
+
+    let out = wreg(&path, &["--json", "get", &child.to_string()]);
+    assert!(out.status.success());
+    let json: serde_json::Value = serde_json::from_slice(&out.stdout).expect("json output");
+    assert_eq!(json["title"], "Save As");
+
+    let out = wreg(&path, &["focus", &child.to_string()]);
+    assert!(out.status.success());
+    assert!(reg.snapshot(child).expect("child").is_focused);
+}
+
+#[test]
+fn wreg_reports_errors() {
+    let reg = SharedRegistry::new(Registry::new());
+    let queue = RegistryEventQueue::unbounded();
+    let path = TempSocket::new("cli-errors");
+    IpcServer::bind(&path, reg, queue).expect("bind").spawn();
+
+    let out = wreg(&path, &["get", "7:1"]);
+    assert!(!out.status.success());
+
+    let out = wreg(&path, &["get", "nope"]);
+    assert_eq!(out.status.code(), Some(2));
+
+    let out = wreg(&path, &["bogus"]);
+    assert_eq!(out.status.code(), Some(2));
+}